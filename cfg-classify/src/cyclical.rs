@@ -6,6 +6,7 @@ use bit_matrix::BitMatrix;
 use bit_vec::BitVec;
 
 use cfg_grammar::{
+    diagnostic::Diagnostic,
     rule::{cfg_rule::CfgRule, RuleRef},
     AsRuleRef, RuleContainer,
 };
@@ -21,6 +22,17 @@ pub struct Cycles<G> {
     cycle_free: bool,
 }
 
+/// A cycle among unit derivations, which is a strongly connected component of the graph of
+/// unit rules.
+#[derive(Clone, Debug)]
+pub struct Cycle {
+    /// The symbols in the cycle, in increasing order.
+    pub syms: Vec<Symbol>,
+    /// A shortest chain of unit rules, such as `A ::= B`, `B ::= C`, `C ::= A`, that leads
+    /// from the first symbol back to itself.
+    pub witness: Vec<CfgRule>,
+}
+
 /// An iterator over the grammar's useless rules.
 pub struct CycleParticipants<'a, G, I> {
     rules: I,
@@ -32,13 +44,13 @@ where
     G: RuleContainer,
 {
     /// Analyzes the grammar's cycles.
-    pub fn new<'a>(grammar: &'a mut G) -> Cycles<&'a mut G> {
+    pub fn new(grammar: &mut G) -> Cycles<&mut G> {
         let unit_derivation = derivation::unit_derivation_matrix(grammar);
         let cycle_free = (0..grammar.num_syms()).all(|i| !unit_derivation[(i, i)]);
         Cycles {
-            unit_derivation: unit_derivation,
-            cycle_free: cycle_free,
-            grammar: grammar,
+            unit_derivation,
+            cycle_free,
+            grammar,
         }
     }
 
@@ -62,6 +74,84 @@ where
         }
     }
 
+    /// Returns a warning for every rule that participates in a cycle, in order of rules.
+    /// Source locations of the rules can be found through their histories.
+    pub fn diagnostics(&'a self) -> Vec<Diagnostic> {
+        self.cycle_participants()
+            .map(|rule| Diagnostic::cycle(rule.lhs, rule.rhs, rule.history_id))
+            .collect()
+    }
+
+    /// Returns the grammar's cycles, ordered by their first symbol. The result is valid until
+    /// the grammar is modified, for example by `rewrite_cycle`.
+    pub fn cycles(&self) -> Vec<Cycle> {
+        let num_syms = self.grammar.num_syms();
+        let mut in_cycle = BitVec::from_elem(num_syms, false);
+        let mut result = vec![];
+        for i in 0..num_syms {
+            if in_cycle[i] || !self.unit_derivation[(i, i)] {
+                continue;
+            }
+            let syms: Vec<Symbol> = (i..num_syms)
+                .filter(|&j| self.unit_derivation[(i, j)] && self.unit_derivation[(j, i)])
+                .map(Symbol::from)
+                .collect();
+            for sym in &syms {
+                in_cycle.set(sym.usize(), true);
+            }
+            let witness = self.witness(&syms);
+            result.push(Cycle { syms, witness });
+        }
+        result
+    }
+
+    /// Finds a shortest chain of unit rules from the first symbol back to itself, with a
+    /// breadth-first search.
+    fn witness(&self, syms: &[Symbol]) -> Vec<CfgRule> {
+        let start = syms[0];
+        let unit_rules: Vec<RuleRef> = self
+            .grammar
+            .rules()
+            .filter(|rule| {
+                rule.rhs.len() == 1
+                    && rule.lhs != rule.rhs[0]
+                    && syms.contains(&rule.lhs)
+                    && syms.contains(&rule.rhs[0])
+            })
+            .collect();
+        // For every reached symbol, the index of the rule that reached it.
+        let mut parent: BTreeMap<Symbol, usize> = BTreeMap::new();
+        let mut queue = vec![start];
+        let mut pos = 0;
+        while pos < queue.len() && !parent.contains_key(&start) {
+            let sym = queue[pos];
+            pos += 1;
+            for (idx, rule) in unit_rules.iter().enumerate() {
+                let next = rule.rhs[0];
+                if rule.lhs == sym && !parent.contains_key(&next) {
+                    parent.insert(next, idx);
+                    queue.push(next);
+                }
+            }
+        }
+        let mut witness = vec![];
+        let mut sym = start;
+        while let Some(&idx) = parent.get(&sym) {
+            let rule = unit_rules[idx];
+            witness.push(CfgRule {
+                lhs: rule.lhs,
+                rhs: rule.rhs.to_vec(),
+                history_id: rule.history_id,
+            });
+            sym = rule.lhs;
+            if sym == start {
+                break;
+            }
+        }
+        witness.reverse();
+        witness
+    }
+
     /// Removes all rules that participate in a cycle. Doesn't preserve the language represented
     /// by the grammar.
     pub fn remove_cycles(&mut self) {
@@ -105,32 +195,66 @@ where
             });
             // Rewrite symbols using the `translation` map, potentially leaving
             // some symbols unused.
-            let mut rewritten_rules = vec![];
-            self.grammar.retain(|mut rule| {
-                let mut changed = false;
-                if let Some(&Some(new_lhs)) = translation.get(&rule.lhs) {
-                    rule.lhs = new_lhs;
+            self.translate(&translation);
+        }
+    }
+
+    /// Rewrites the rules of a single cycle, merging the cycle's symbols into `into`. Other
+    /// cycles and symbols are left alone. Preserves the language represented by the grammar.
+    ///
+    /// Returns the grammar's remaining cycles. Cycles returned by earlier calls to `cycles`
+    /// may refer to merged symbols and rules that no longer exist, so they must not be used
+    /// after this call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `into` is not one of the cycle's symbols.
+    pub fn rewrite_cycle(&mut self, cycle: &Cycle, into: Symbol) -> Vec<Cycle> {
+        assert!(
+            cycle.syms.contains(&into),
+            "the cycle doesn't contain the symbol"
+        );
+        let in_cycle = |sym: &Symbol| cycle.syms.contains(sym);
+        self.grammar
+            .retain(|rule| !(rule.rhs.len() == 1 && in_cycle(&rule.lhs) && in_cycle(&rule.rhs[0])));
+        let translation = cycle
+            .syms
+            .iter()
+            .map(|&sym| (sym, if sym == into { None } else { Some(into) }))
+            .collect();
+        self.translate(&translation);
+        self.unit_derivation = derivation::unit_derivation_matrix(&*self.grammar);
+        self.cycle_free = (0..self.grammar.num_syms()).all(|i| !self.unit_derivation[(i, i)]);
+        self.cycles()
+    }
+
+    /// Rewrites symbols in all rules. A symbol that maps to `Some` is replaced.
+    fn translate(&mut self, translation: &BTreeMap<Symbol, Option<Symbol>>) {
+        let mut rewritten_rules = vec![];
+        self.grammar.retain(|mut rule| {
+            let mut changed = false;
+            if let Some(&Some(new_lhs)) = translation.get(&rule.lhs) {
+                rule.lhs = new_lhs;
+                changed = true;
+            }
+            let mut rhs = rule.rhs.to_vec();
+            for sym in &mut rhs {
+                if let Some(&Some(new_sym)) = translation.get(sym) {
+                    *sym = new_sym;
                     changed = true;
                 }
-                let mut rhs = rule.rhs.to_vec();
-                for sym in &mut rhs {
-                    if let Some(&Some(new_sym)) = translation.get(sym) {
-                        *sym = new_sym;
-                        changed = true;
-                    }
-                }
-                if changed {
-                    rewritten_rules.push(CfgRule {
-                        lhs: rule.lhs,
-                        rhs,
-                        history_id: rule.history_id,
-                    });
-                }
-                !changed
-            });
-            for rule in rewritten_rules {
-                self.grammar.add_rule(rule.as_rule_ref());
             }
+            if changed {
+                rewritten_rules.push(CfgRule {
+                    lhs: rule.lhs,
+                    rhs,
+                    history_id: rule.history_id,
+                });
+            }
+            !changed
+        });
+        for rule in rewritten_rules {
+            self.grammar.add_rule(rule.as_rule_ref());
         }
     }
 }
@@ -147,14 +271,9 @@ where
             return None;
         }
 
-        for rule in &mut self.rules {
-            if rule.rhs.len() == 1
+        self.rules.find(|&rule| {
+            rule.rhs.len() == 1
                 && self.cycles.unit_derivation[(rule.rhs[0].into(), rule.lhs.into())]
-            {
-                return Some(rule);
-            }
-        }
-
-        None
+        })
     }
 }
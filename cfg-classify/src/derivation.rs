@@ -3,7 +3,7 @@ use bit_matrix::BitMatrix;
 use cfg_grammar::RuleContainer;
 
 /// Returns the direct derivation matrix.
-pub fn direct_derivation_matrix<'a, G>(grammar: &'a G) -> BitMatrix
+pub fn direct_derivation_matrix<G>(grammar: &G) -> BitMatrix
 where
     G: RuleContainer,
 {
@@ -20,7 +20,7 @@ where
 }
 
 /// Returns the derivation matrix.
-pub fn reachability_matrix<'a, G>(grammar: &'a G) -> BitMatrix
+pub fn reachability_matrix<G>(grammar: &G) -> BitMatrix
 where
     G: RuleContainer,
 {
@@ -31,7 +31,7 @@ where
 }
 
 /// Returns the unit derivation matrix.
-pub fn unit_derivation_matrix<'a, G>(grammar: &'a G) -> BitMatrix
+pub fn unit_derivation_matrix<G>(grammar: &G) -> BitMatrix
 where
     G: RuleContainer,
 {
@@ -4,6 +4,7 @@
 // mod recursive;
 pub mod cyclical;
 mod derivation;
+pub mod lint;
 #[cfg(feature = "cfg-predict")]
 pub mod ll;
 pub mod lr;
@@ -0,0 +1,363 @@
+//! Configurable checks of a grammar's health.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bit_matrix::BitMatrix;
+use bit_vec::BitVec;
+
+use cfg_grammar::diagnostic::{Diagnostic, DiagnosticKind, Lint, Severity};
+use cfg_grammar::rhs_closure::RhsClosure;
+use cfg_grammar::rule::RuleRef;
+use cfg_grammar::{GrammarIndex, HistoryId, RuleContainer};
+use cfg_symbol::Symbol;
+
+use crate::derivation;
+
+/// Checks grammars with a configurable set of lints.
+///
+/// Every lint reports diagnostics with its default severity, unless configured otherwise.
+/// Lints can be disabled, or suppressed for single rules and symbols.
+#[derive(Clone, Debug)]
+pub struct Linter {
+    levels: BTreeMap<Lint, Option<Severity>>,
+    suppressed_rules: BTreeSet<(Lint, HistoryId)>,
+    suppressed_syms: BTreeSet<(Lint, Symbol)>,
+    terminals: Option<BTreeSet<Symbol>>,
+    reachable: Vec<Symbol>,
+    max_unit_chain: usize,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// Creates a linter with all lints enabled.
+    pub fn new() -> Self {
+        Linter {
+            levels: BTreeMap::new(),
+            suppressed_rules: BTreeSet::new(),
+            suppressed_syms: BTreeSet::new(),
+            terminals: None,
+            reachable: vec![],
+            max_unit_chain: 3,
+        }
+    }
+
+    /// Sets the severity of a lint.
+    pub fn severity(mut self, lint: Lint, severity: Severity) -> Self {
+        self.levels.insert(lint, Some(severity));
+        self
+    }
+
+    /// Disables a lint.
+    pub fn allow(mut self, lint: Lint) -> Self {
+        self.levels.insert(lint, None);
+        self
+    }
+
+    /// Suppresses a lint for the rule with the given history.
+    pub fn suppress_rule(mut self, lint: Lint, history_id: HistoryId) -> Self {
+        self.suppressed_rules.insert((lint, history_id));
+        self
+    }
+
+    /// Suppresses a lint for a symbol, including all rules with the symbol on the LHS.
+    pub fn suppress_symbol(mut self, lint: Lint, sym: Symbol) -> Self {
+        self.suppressed_syms.insert((lint, sym));
+        self
+    }
+
+    /// Declares terminal symbols. Without declared terminals, symbols without rules can't be
+    /// told apart from terminals, so `Lint::MissingRules` reports nothing, and
+    /// `Lint::UnusedTerminal` reports all symbols that are not used at all.
+    pub fn terminals<Sr>(mut self, syms: Sr) -> Self
+    where
+        Sr: AsRef<[Symbol]>,
+    {
+        self.terminals = Some(syms.as_ref().iter().cloned().collect());
+        self
+    }
+
+    /// Sets the start symbols. `Lint::ReachableOnlyThroughCycles` reports nothing without them.
+    pub fn reachable<Sr>(mut self, syms: Sr) -> Self
+    where
+        Sr: AsRef<[Symbol]>,
+    {
+        self.reachable = syms.as_ref().to_vec();
+        self
+    }
+
+    /// Sets the maximal number of rules in a chain of unit rules, such as `A ::= B`, whose RHS
+    /// is a single nonterminal. The default is 3.
+    pub fn max_unit_chain(mut self, max: usize) -> Self {
+        self.max_unit_chain = max;
+        self
+    }
+
+    /// Checks a grammar. Diagnostics are ordered by lint, then by rule or symbol.
+    pub fn lint<G>(&self, grammar: &G) -> Vec<Diagnostic>
+    where
+        G: RuleContainer,
+    {
+        let mut lints = Lints {
+            linter: self,
+            rules: grammar.rules().collect(),
+            index: GrammarIndex::new(grammar),
+            unit_derivation: derivation::unit_derivation_matrix(grammar),
+            num_syms: grammar.num_syms(),
+            diagnostics: vec![],
+        };
+        lints.duplicate_rules();
+        lints.ambiguous_shapes();
+        lints.missing_rules();
+        lints.unused_terminals();
+        lints.long_unit_chains();
+        lints.reachable_only_through_cycles();
+        lints.nullable_recursive(grammar);
+        lints.diagnostics
+    }
+
+    fn level(&self, lint: Lint) -> Option<Severity> {
+        match self.levels.get(&lint) {
+            Some(&level) => level,
+            None => Some(lint.default_severity()),
+        }
+    }
+}
+
+struct Lints<'a, 'g> {
+    linter: &'a Linter,
+    rules: Vec<RuleRef<'g>>,
+    index: GrammarIndex,
+    unit_derivation: BitMatrix,
+    num_syms: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Lints<'_, '_> {
+    fn report_rule(&mut self, lint: Lint, rule: RuleRef) {
+        if self
+            .linter
+            .suppressed_rules
+            .contains(&(lint, rule.history_id))
+        {
+            return;
+        }
+        self.report(lint, rule.lhs, Some(rule.history_id));
+    }
+
+    fn report(&mut self, lint: Lint, sym: Symbol, history_id: Option<HistoryId>) {
+        if self.linter.suppressed_syms.contains(&(lint, sym)) {
+            return;
+        }
+        if let Some(severity) = self.linter.level(lint) {
+            self.diagnostics.push(Diagnostic {
+                severity,
+                kind: DiagnosticKind::Lint { lint, sym },
+                history_id,
+            });
+        }
+    }
+
+    /// Checks whether the rule is a unit rule in a cycle of unit rules.
+    fn in_cycle(&self, rule: RuleRef) -> bool {
+        rule.rhs.len() == 1 && self.unit_derivation[(rule.rhs[0].into(), rule.lhs.into())]
+    }
+
+    fn duplicate_rules(&mut self) {
+        let mut seen = BTreeSet::new();
+        for rule in self.rules.clone() {
+            if !seen.insert((rule.lhs, rule.rhs)) {
+                self.report_rule(Lint::DuplicateRule, rule);
+            }
+        }
+    }
+
+    fn ambiguous_shapes(&mut self) {
+        for rule in self.rules.clone() {
+            let self_loop = rule.rhs == [rule.lhs];
+            let both_ends = rule.rhs.len() >= 2
+                && rule.rhs[0] == rule.lhs
+                && rule.rhs[rule.rhs.len() - 1] == rule.lhs;
+            if self_loop || both_ends || self.in_cycle(rule) {
+                self.report_rule(Lint::AmbiguousShape, rule);
+            }
+        }
+    }
+
+    fn missing_rules(&mut self) {
+        let terminals = match &self.linter.terminals {
+            Some(terminals) => terminals,
+            None => return,
+        };
+        let mut missing = BTreeSet::new();
+        for rule in &self.rules {
+            for &sym in rule.rhs {
+                if !self.index.is_nonterminal(sym) && !terminals.contains(&sym) {
+                    missing.insert(sym);
+                }
+            }
+        }
+        for &start in &self.linter.reachable {
+            if !self.index.is_nonterminal(start) && !terminals.contains(&start) {
+                missing.insert(start);
+            }
+        }
+        for sym in missing {
+            self.report(Lint::MissingRules, sym, None);
+        }
+    }
+
+    fn unused_terminals(&mut self) {
+        let mut used = BitVec::from_elem(self.num_syms, false);
+        for rule in &self.rules {
+            used.set(rule.lhs.usize(), true);
+            for &sym in rule.rhs {
+                used.set(sym.usize(), true);
+            }
+        }
+        for &start in &self.linter.reachable {
+            used.set(start.usize(), true);
+        }
+        let unused: Vec<Symbol> = match &self.linter.terminals {
+            Some(terminals) => terminals
+                .iter()
+                .cloned()
+                .filter(|sym| !used.get(sym.usize()).unwrap_or(false))
+                .collect(),
+            None => (0..self.num_syms)
+                .filter(|&i| !used[i])
+                .map(Symbol::from)
+                .collect(),
+        };
+        for sym in unused {
+            self.report(Lint::UnusedTerminal, sym, None);
+        }
+    }
+
+    fn long_unit_chains(&mut self) {
+        let index = &self.index;
+        let is_unit = |rule: &RuleRef| {
+            rule.rhs.len() == 1 && rule.rhs[0] != rule.lhs && index.is_nonterminal(rule.rhs[0])
+        };
+        let mut units: BTreeMap<Symbol, Vec<Symbol>> = BTreeMap::new();
+        let mut unit_targets = BTreeSet::new();
+        for rule in self.rules.iter().filter(|rule| is_unit(rule)) {
+            units.entry(rule.lhs).or_default().push(rule.rhs[0]);
+            unit_targets.insert(rule.rhs[0]);
+        }
+        let mut lengths = BTreeMap::new();
+        let mut long = vec![];
+        for &rule in &self.rules {
+            // Chains are reported once, at their first rule.
+            if is_unit(&rule) && !unit_targets.contains(&rule.lhs) {
+                let mut on_stack = BTreeSet::from([rule.lhs]);
+                let (len, _) = chain_len(&units, rule.rhs[0], &mut lengths, &mut on_stack);
+                if 1 + len > self.linter.max_unit_chain {
+                    long.push(rule);
+                }
+            }
+        }
+        for rule in long {
+            self.report_rule(Lint::LongUnitChain, rule);
+        }
+    }
+
+    fn reachable_only_through_cycles(&mut self) {
+        if self.linter.reachable.is_empty() {
+            return;
+        }
+        let all = self.reachable_syms(true);
+        let acyclic = self.reachable_syms(false);
+        for rule in self.rules.clone() {
+            if all[rule.lhs.usize()] && !acyclic[rule.lhs.usize()] {
+                self.report_rule(Lint::ReachableOnlyThroughCycles, rule);
+            }
+        }
+    }
+
+    /// Returns symbols reachable from the start symbols. Rules in cycles are followed only if
+    /// `through_cycles` is true.
+    fn reachable_syms(&self, through_cycles: bool) -> BitVec {
+        let mut reachable = BitVec::from_elem(self.num_syms, false);
+        let mut work_stack = self.linter.reachable.clone();
+        for &sym in &work_stack {
+            reachable.set(sym.usize(), true);
+        }
+        while let Some(sym) = work_stack.pop() {
+            if !self.index.is_nonterminal(sym) {
+                continue;
+            }
+            for &rule_idx in self.index.rules_for_lhs(sym) {
+                let rule = self.rules[rule_idx];
+                if !through_cycles && self.in_cycle(rule) {
+                    continue;
+                }
+                for &rhs_sym in rule.rhs {
+                    if !reachable[rhs_sym.usize()] {
+                        reachable.set(rhs_sym.usize(), true);
+                        work_stack.push(rhs_sym);
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
+    fn nullable_recursive<G>(&mut self, grammar: &G)
+    where
+        G: RuleContainer,
+    {
+        let mut nullable = BitVec::from_elem(self.num_syms, false);
+        for rule in &self.rules {
+            if rule.rhs.is_empty() {
+                nullable.set(rule.lhs.usize(), true);
+            }
+        }
+        RhsClosure::new(grammar).rhs_closure(&mut nullable);
+        let mut derivation = BitMatrix::new(self.num_syms, self.num_syms);
+        for rule in &self.rules {
+            for &sym in rule.rhs {
+                derivation.set(rule.lhs.usize(), sym.usize(), true);
+            }
+        }
+        derivation.transitive_closure();
+        for i in 0..self.num_syms {
+            if nullable[i] && derivation[(i, i)] {
+                self.report(Lint::NullableRecursive, Symbol::from(i), None);
+            }
+        }
+    }
+}
+
+/// Returns the number of rules in the longest chain of unit rules that starts at `sym`, and
+/// whether a cycle cut the search short. Cycles are not followed. A length that was cut short
+/// depends on the symbols on the stack, so only the other lengths are memoized.
+fn chain_len(
+    units: &BTreeMap<Symbol, Vec<Symbol>>,
+    sym: Symbol,
+    lengths: &mut BTreeMap<Symbol, usize>,
+    on_stack: &mut BTreeSet<Symbol>,
+) -> (usize, bool) {
+    if let Some(&len) = lengths.get(&sym) {
+        return (len, false);
+    }
+    if !on_stack.insert(sym) {
+        return (0, true);
+    }
+    let mut len = 0;
+    let mut cut = false;
+    for &next in units.get(&sym).into_iter().flatten() {
+        let (next_len, next_cut) = chain_len(units, next, lengths, on_stack);
+        len = len.max(1 + next_len);
+        cut |= next_cut;
+    }
+    on_stack.remove(&sym);
+    if !cut {
+        lengths.insert(sym, len);
+    }
+    (len, cut)
+}
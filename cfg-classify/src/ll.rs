@@ -2,7 +2,9 @@
 
 use std::collections::BTreeMap;
 
+use cfg_grammar::diagnostic::{ConflictKind, Diagnostic};
 use cfg_grammar::rhs_closure::RhsClosure;
+use cfg_grammar::rule::RuleRef;
 use cfg_grammar::symbol::set::SymbolBitSet;
 use cfg_grammar::RuleContainer;
 use cfg_predict::{FirstSets, FollowSets, PredictSets};
@@ -20,6 +22,35 @@ struct LlParseTableKey {
     terminal: Symbol,
 }
 
+/// A conflict in an LL parse table, where several rules are predicted by the same terminal.
+#[derive(Clone)]
+pub struct LlConflict<'a> {
+    /// The nonterminal being expanded.
+    pub nonterminal: Symbol,
+    /// The lookahead terminal.
+    pub terminal: Symbol,
+    /// The conflicting rules.
+    pub rules: Vec<RuleRef<'a>>,
+}
+
+impl LlConflict<'_> {
+    /// Returns an error for every conflicting rule, in order of `rules`. Source locations
+    /// of the rules can be found through their histories.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.rules
+            .iter()
+            .map(|rule| {
+                Diagnostic::conflict(
+                    ConflictKind::Ll(self.terminal),
+                    rule.lhs,
+                    rule.rhs,
+                    rule.history_id,
+                )
+            })
+            .collect()
+    }
+}
+
 /// Container for classifying nonterminals as LL(1) or context-free.
 #[derive(Debug, Eq, PartialEq)]
 pub struct LlClassification {
@@ -76,22 +107,35 @@ where
         this
     }
 
+    /// Returns all conflicts in the table, ordered by nonterminal and terminal.
+    pub fn conflicts(&self) -> Vec<LlConflict<'a>> {
+        let rules: Vec<_> = self.grammar.rules().collect();
+        self.map
+            .iter()
+            .filter(|(_, rule_indices)| rule_indices.len() > 1)
+            .map(|(key, rule_indices)| LlConflict {
+                nonterminal: key.nonterminal,
+                terminal: key.terminal,
+                rules: rule_indices.iter().map(|&idx| rules[idx]).collect(),
+            })
+            .collect()
+    }
+
     /// Classifies nonterminals as LL(1) or context-free.
     pub fn classify(&self) -> LlClassification {
         let mut result = LlClassification {
             classes: BTreeMap::new(),
         };
-        for (key, ref rules) in &self.map {
+        for (key, rules) in &self.map {
             if rules.len() > 1 {
                 result
                     .classes
                     .insert(key.nonterminal, LlNonterminalClass::ContextFree);
             } else {
-                if !result.classes.contains_key(&key.nonterminal) {
-                    result
-                        .classes
-                        .insert(key.nonterminal, LlNonterminalClass::Ll1);
-                }
+                result
+                    .classes
+                    .entry(key.nonterminal)
+                    .or_insert(LlNonterminalClass::Ll1);
             }
         }
         let mut closure = RhsClosure::new(self.grammar);
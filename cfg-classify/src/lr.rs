@@ -2,10 +2,12 @@
 
 #![allow(missing_docs)]
 
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::rc::Rc;
 
+use cfg_grammar::diagnostic::{ConflictKind, Diagnostic};
 use cfg_grammar::history::node::RootHistoryNode;
+use cfg_grammar::rule::RuleRef;
 use cfg_grammar::symbol::set::SymbolBitSet;
 use cfg_grammar::RuleContainer;
 use cfg_symbol::Symbol;
@@ -49,6 +51,99 @@ pub struct Lr0Node {
     pub link: BTreeMap<Symbol, SetId>,
 }
 
+/// A conflict in a state of an LR(0) Finite State Machine.
+#[derive(Clone)]
+pub struct LrConflict<'a> {
+    /// The index of the state in the FSM.
+    pub state: usize,
+    /// The kind of conflict.
+    pub kind: LrConflictKind,
+    /// The conflicting rules. Rules to reduce come first, then rules to shift, if any.
+    pub rules: Vec<RuleRef<'a>>,
+}
+
+/// The kind of an LR conflict.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LrConflictKind {
+    /// A rule can be reduced, or the terminal can be shifted.
+    ShiftReduce(Symbol),
+    /// Several rules can be reduced.
+    ReduceReduce,
+}
+
+impl LrConflict<'_> {
+    /// Returns an error for every conflicting rule, in order of `rules`. Source locations
+    /// of the rules can be found through their histories.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.rules
+            .iter()
+            .map(|rule| Diagnostic::conflict(self.kind.into(), rule.lhs, rule.rhs, rule.history_id))
+            .collect()
+    }
+}
+
+impl From<LrConflictKind> for ConflictKind {
+    fn from(kind: LrConflictKind) -> Self {
+        match kind {
+            LrConflictKind::ShiftReduce(terminal) => ConflictKind::ShiftReduce(terminal),
+            LrConflictKind::ReduceReduce => ConflictKind::ReduceReduce,
+        }
+    }
+}
+
+/// Returns all conflicts in the states of an LR(0) FSM built for the grammar, ordered by
+/// state. In every state, shift/reduce conflicts are ordered by terminal and come before the
+/// reduce/reduce conflict.
+pub fn lr0_conflicts<'a, G>(grammar: &'a G, fsm: &[Lr0Node]) -> Vec<LrConflict<'a>>
+where
+    G: RuleContainer,
+{
+    // Items are keyed by the rule IDs of `rules_for_lhs`.
+    let lhs_syms: BTreeSet<Symbol> = grammar.rules().map(|rule| rule.lhs).collect();
+    let mut rules = BTreeMap::new();
+    for lhs in lhs_syms {
+        rules.extend(
+            grammar
+                .rules_for_lhs(lhs)
+                .map(|(rule_id, rule)| (rule_id as RuleId, rule)),
+        );
+    }
+    let mut conflicts = vec![];
+    for (state, node) in fsm.iter().enumerate() {
+        let reduce: Vec<_> = node
+            .items
+            .map
+            .iter()
+            .filter(|(_, item)| item.dot as usize == item.rhs.len())
+            .map(|(rule_id, _)| rules[rule_id])
+            .collect();
+        if reduce.is_empty() {
+            continue;
+        }
+        for &terminal in node.link.keys() {
+            let shift = node
+                .items
+                .map
+                .iter()
+                .filter(|(_, item)| item.rhs.get(item.dot as usize) == Some(&terminal))
+                .map(|(rule_id, _)| rules[rule_id]);
+            conflicts.push(LrConflict {
+                state,
+                kind: LrConflictKind::ShiftReduce(terminal),
+                rules: reduce.iter().copied().chain(shift).collect(),
+            });
+        }
+        if reduce.len() > 1 {
+            conflicts.push(LrConflict {
+                state,
+                kind: LrConflictKind::ReduceReduce,
+                rules: reduce,
+            });
+        }
+    }
+    conflicts
+}
+
 impl Lr0Items {
     fn new() -> Self {
         Lr0Items {
@@ -77,19 +172,17 @@ where
 
         while let Some(item) = self.queue.pop_front() {
             if let Some(nonterminal_postdot) = self.nonterminal_postdot(&item) {
-                for (rule_idx, rule) in self.grammar.rules().enumerate() {
-                    if rule.lhs == nonterminal_postdot {
-                        let new_item = Lr0Item {
-                            rhs: rule.rhs.to_vec(),
-                            dot: 0,
-                        };
-                        if items
-                            .map
-                            .insert(rule_idx as RuleId, new_item.clone())
-                            .is_none()
-                        {
-                            self.queue.push_back(new_item);
-                        }
+                for (rule_id, rule) in self.grammar.rules_for_lhs(nonterminal_postdot) {
+                    let new_item = Lr0Item {
+                        rhs: rule.rhs.to_vec(),
+                        dot: 0,
+                    };
+                    if items
+                        .map
+                        .insert(rule_id as RuleId, new_item.clone())
+                        .is_none()
+                    {
+                        self.queue.push_back(new_item);
                     }
                 }
             }
@@ -187,7 +280,6 @@ where
 
     fn augment_grammar(&mut self, start_sym: Symbol) -> (Symbol, RuleId) {
         let new_start = self.closure.grammar.next_sym();
-        let rule_id = self.closure.grammar.rules().count() as RuleId;
         let history_id = self
             .closure
             .grammar
@@ -195,8 +287,14 @@ where
         self.closure
             .grammar
             .rule(new_start)
-            .rhs_with_history(&[start_sym], history_id);
-        (new_start, rule_id)
+            .rhs_with_history([start_sym], history_id);
+        let (rule_id, _) = self
+            .closure
+            .grammar
+            .rules_for_lhs(new_start)
+            .next()
+            .expect("missing augmented start rule");
+        (new_start, rule_id as RuleId)
     }
 
     fn id_of(&mut self, items: Rc<Lr0Items>) -> SetId {
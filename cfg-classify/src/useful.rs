@@ -7,7 +7,7 @@ use crate::derivation;
 use cfg_grammar::rhs_closure::RhsClosure;
 use cfg_grammar::rule::RuleRef;
 use cfg_grammar::symbol::set::SymbolBitSet;
-use cfg_grammar::RuleContainer;
+use cfg_grammar::{GrammarIndex, RuleContainer};
 use cfg_symbol::Symbol;
 
 /// Contains the information about usefulness of the grammar's rules.
@@ -36,32 +36,78 @@ pub struct UselessRule<R> {
     usefulness: RuleUsefulness,
 }
 
-#[derive(Copy, Clone, Debug)]
+/// Whether a rule is reachable and productive.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct RuleUsefulness {
-    /// Indicates whether the rule is unreachable.
+    /// Indicates whether the rule is reachable.
     reachable: bool,
-    /// Indicates whether the rule is unproductive.
+    /// Indicates whether the rule is productive.
     productive: bool,
 }
 
+/// Explains why a rule is useless.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UselessExplanation {
+    /// Why the rule is unproductive, if it is.
+    pub unproductive: Option<Unproductive>,
+    /// Why the rule is unreachable, if it is.
+    pub unreachable: Option<Unreachable>,
+}
+
+/// Explains why a rule is unproductive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unproductive {
+    /// The unproductive symbols on the rule's RHS, in order of first appearance.
+    pub unproductive_syms: Vec<Symbol>,
+    /// Nonterminals that need a new productive rule, such as a rule with only terminals on the
+    /// RHS, to make the rule productive. The set is minimal: no symbol can be left out.
+    pub missing_productions: Vec<Symbol>,
+}
+
+/// Explains why a rule is unreachable.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unreachable {
+    /// Symbols that refer to the rule's LHS on the RHS of their rules. These symbols are
+    /// unreachable too. Empty if nothing refers to the LHS.
+    pub referrers: Vec<Symbol>,
+    /// The nearest reachable symbol found by following references backwards from the rule's
+    /// LHS, or `None` if no reachable symbol refers to the LHS, directly or through other
+    /// symbols. When `None`, the rule becomes reachable only once a reachable rule refers to the
+    /// LHS or to a symbol that derives it.
+    pub nearest_reachable_referrer: Option<Symbol>,
+}
+
 impl<R> UselessRule<R> {
+    /// Returns the rule.
     pub fn rule(&self) -> &R {
         &self.rule
     }
 
+    /// Returns whether the rule is reachable and productive.
     pub fn usefulness(&self) -> &RuleUsefulness {
         &self.usefulness
     }
 }
 
 impl RuleUsefulness {
-    fn is_useless(&self) -> bool {
+    /// Checks whether the rule is reachable.
+    pub fn reachable(&self) -> bool {
+        self.reachable
+    }
+
+    /// Checks whether the rule is productive.
+    pub fn productive(&self) -> bool {
+        self.productive
+    }
+
+    /// Checks whether the rule is unreachable or unproductive.
+    pub fn is_useless(&self) -> bool {
         !self.reachable || !self.productive
     }
 }
 
 /// Returns the set of unused symbols.
-fn unused_syms<'a, G>(grammar: &'a G) -> BitVec
+fn unused_syms<G>(grammar: &G) -> BitVec
 where
     G: RuleContainer,
 {
@@ -71,7 +117,7 @@ where
 }
 
 /// Returns the set of used symbols.
-fn used_syms<'a, G>(grammar: &'a G) -> BitVec
+fn used_syms<G>(grammar: &G) -> BitVec
 where
     G: RuleContainer,
 {
@@ -118,10 +164,10 @@ where
         );
 
         Usefulness {
-            grammar: grammar,
-            productivity: productivity,
-            reachability: reachability,
-            reachable_syms: reachable_syms,
+            grammar,
+            productivity,
+            reachability,
+            reachable_syms,
         }
     }
 
@@ -172,6 +218,126 @@ where
         }
     }
 
+    /// Explains why a rule is unproductive or unreachable.
+    pub fn explain(&self, rule: RuleRef) -> UselessExplanation {
+        let usefulness = self.rule_usefulness(rule);
+        UselessExplanation {
+            unproductive: if usefulness.productive {
+                None
+            } else {
+                Some(self.explain_unproductive(rule))
+            },
+            unreachable: if usefulness.reachable {
+                None
+            } else {
+                Some(self.explain_unreachable(rule.lhs))
+            },
+        }
+    }
+
+    fn explain_unproductive(&self, rule: RuleRef) -> Unproductive {
+        let mut unproductive_syms = vec![];
+        for &sym in rule.rhs {
+            if !self.productivity[sym.usize()] && !unproductive_syms.contains(&sym) {
+                unproductive_syms.push(sym);
+            }
+        }
+        // Any unproductive symbol derived from the RHS may need a new rule.
+        let num_syms = self.productivity.len();
+        let candidates: Vec<Symbol> = (0..num_syms)
+            .filter(|&i| {
+                !self.productivity[i]
+                    && unproductive_syms
+                        .iter()
+                        .any(|sym| self.reachability[(sym.usize(), i)])
+            })
+            .map(Symbol::from)
+            .collect();
+        // Greedily pick symbols that fix the most RHS symbols, then drop the ones that turn
+        // out to be unnecessary.
+        let mut missing_productions = vec![];
+        let mut fixed = self.count_fixed(&unproductive_syms, &missing_productions);
+        while fixed < unproductive_syms.len() {
+            let mut best = None;
+            for &sym in &candidates {
+                if missing_productions.contains(&sym) {
+                    continue;
+                }
+                missing_productions.push(sym);
+                let sym_fixed = self.count_fixed(&unproductive_syms, &missing_productions);
+                missing_productions.pop();
+                if sym_fixed > best.map_or(fixed, |(_, best_fixed)| best_fixed) {
+                    best = Some((sym, sym_fixed));
+                }
+            }
+            match best {
+                Some((sym, sym_fixed)) => {
+                    missing_productions.push(sym);
+                    fixed = sym_fixed;
+                }
+                None => break,
+            }
+        }
+        let mut i = 0;
+        while i < missing_productions.len() {
+            let sym = missing_productions.remove(i);
+            if self.count_fixed(&unproductive_syms, &missing_productions) < fixed {
+                missing_productions.insert(i, sym);
+                i += 1;
+            }
+        }
+        Unproductive {
+            unproductive_syms,
+            missing_productions,
+        }
+    }
+
+    /// Counts the symbols in `syms` that become productive after productive rules are added
+    /// for `added`.
+    fn count_fixed(&self, syms: &[Symbol], added: &[Symbol]) -> usize {
+        let mut productivity = self.productivity.clone();
+        for &sym in added {
+            productivity.set(sym.usize(), true);
+        }
+        RhsClosure::new(&*self.grammar).rhs_closure(&mut productivity);
+        syms.iter().filter(|sym| productivity[sym.usize()]).count()
+    }
+
+    fn explain_unreachable(&self, lhs: Symbol) -> Unreachable {
+        let index = GrammarIndex::new(&*self.grammar);
+        let rule_lhs: Vec<Symbol> = self.grammar.rules().map(|rule| rule.lhs).collect();
+        let referrers = |sym: Symbol| {
+            let mut result = vec![];
+            for occurrence in index.occurrences(sym) {
+                let referrer = rule_lhs[occurrence.rule];
+                if referrer != sym && !result.contains(&referrer) {
+                    result.push(referrer);
+                }
+            }
+            result
+        };
+        // Search backwards, breadth-first, for the nearest reachable referrer.
+        let mut visited = vec![lhs];
+        let mut nearest_reachable_referrer = None;
+        let mut pos = 0;
+        while pos < visited.len() && nearest_reachable_referrer.is_none() {
+            for referrer in referrers(visited[pos]) {
+                if self.reachable_syms[referrer.usize()] {
+                    nearest_reachable_referrer = Some(referrer);
+                    break;
+                }
+                if !visited.contains(&referrer) {
+                    visited.push(referrer);
+                }
+            }
+            pos += 1;
+        }
+        Unreachable {
+            referrers: referrers(lhs),
+            nearest_reachable_referrer,
+        }
+    }
+
     /// Returns an iterator over the grammar's useless rules.
     pub fn useless_rules(&'a self) -> UselessRules<'a, G, impl Iterator<Item = RuleRef<'a>>> {
         UselessRules {
@@ -182,7 +348,7 @@ where
 }
 
 // Watch out: Normal type bounds conflict with HRTB.
-impl<'a, G> Usefulness<&'a mut G>
+impl<G> Usefulness<&mut G>
 where
     G: RuleContainer,
 {
@@ -4,6 +4,7 @@ use std::ops::{Deref, DerefMut};
 use bit_matrix::BitMatrix;
 
 use cfg_classify::useful::Usefulness;
+use cfg_grammar::diagnostic::{Diagnostic, GrammarError};
 use cfg_grammar::history::graph::HistoryGraph;
 use cfg_grammar::history::node::RootHistoryNode;
 use cfg_grammar::rule::RuleRef;
@@ -16,6 +17,10 @@ type Dot = u32;
 
 /// Drop-in replacement for `cfg::BinarizedCfg`.
 #[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(miniserde::Serialize, miniserde::Deserialize)
+)]
 pub struct BinarizedGrammar {
     pub(super) inherit: BinarizedCfg,
     pub(super) start: Option<Symbol>,
@@ -32,9 +37,19 @@ impl BinarizedGrammar {
         self.start = Some(start);
     }
 
+    /// Returns the start symbol.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start symbol was not set.
     pub fn start(&self) -> Symbol {
         self.start.unwrap()
     }
+
+    /// Returns the start symbol, or an error if it was not set.
+    pub fn try_start(&self) -> Result<Symbol, GrammarError> {
+        self.start.ok_or(GrammarError::MissingStart)
+    }
 }
 
 impl RuleContainer for BinarizedGrammar {
@@ -105,7 +120,7 @@ impl BinarizedGrammar {
             return None;
         }
         let is_start_rule = |rule: &RuleRef| rule.lhs == self.start();
-        let rhs0 = |rule: RuleRef| rule.rhs.get(0).cloned();
+        let rhs0 = |rule: RuleRef| rule.rhs.first().cloned();
         self.rules().find(is_start_rule).and_then(rhs0)
     }
 
@@ -127,20 +142,36 @@ impl BinarizedGrammar {
         self.rules().position(is_start_rule).map(as_dot)
     }
 
-    pub fn make_proper(mut self: BinarizedGrammar) -> BinarizedGrammar {
-        let start = self.start();
+    /// Removes useless rules, which are unreachable from the start symbol or unproductive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start symbol was not set.
+    pub fn make_proper(self: BinarizedGrammar) -> BinarizedGrammar {
+        match self.try_make_proper() {
+            Ok((proper, _)) => proper,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Removes useless rules, like `make_proper`. Returns a warning for every removed rule,
+    /// or an error if the start symbol was not set.
+    pub fn try_make_proper(
+        mut self: BinarizedGrammar,
+    ) -> Result<(BinarizedGrammar, Vec<Diagnostic>), GrammarError> {
+        let start = self.try_start()?;
+        let mut diagnostics = vec![];
         {
             let mut usefulness = Usefulness::new(&mut *self).reachable([start]);
             if !usefulness.all_useful() {
-                // for useless in usefulness.useless_rules() {
-                //     let rhs: Vec<_> = useless.rule.rhs().iter().map(|t| tok.get(t.usize())).collect();
-                //     println!("lhs:{:?} rhs:{:?} unreachable:{} unproductive:{}", tok.get(useless.rule.lhs().usize()), rhs, useless.unreachable, useless.unproductive);
-                // }
-                println!("warning: grammar has useless rules");
+                diagnostics.extend(usefulness.useless_rules().map(|useless| {
+                    let rule = useless.rule();
+                    Diagnostic::useless_rule(rule.lhs, rule.rhs, rule.history_id)
+                }));
                 usefulness.remove_useless_rules();
             }
         };
-        self
+        Ok((self, diagnostics))
     }
 
     pub fn eliminate_nulling(mut self: BinarizedGrammar) -> (BinarizedGrammar, BinarizedGrammar) {
@@ -1,5 +1,7 @@
 use std::ops::{Deref, DerefMut};
 
+use cfg_grammar::binarized_cfg::BinarizationStrategy;
+use cfg_grammar::diagnostic::GrammarError;
 use cfg_grammar::history::node::RootHistoryNode;
 use cfg_grammar::rule::builder::RuleBuilder;
 use cfg_grammar::{Cfg, RuleContainer};
@@ -12,6 +14,10 @@ use super::BinarizedGrammar;
 /// Drop-in replacement for `cfg::Cfg` that traces relations between user-provided
 /// and internal grammars.
 #[derive(Default)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(miniserde::Serialize, miniserde::Deserialize)
+)]
 pub struct Grammar {
     inherit: Cfg,
     start: Option<Symbol>,
@@ -29,10 +35,20 @@ impl Grammar {
         self.start = Some(start);
     }
 
+    /// Returns the start symbol.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start symbol was not set.
     pub fn start(&self) -> Symbol {
         self.start.unwrap()
     }
 
+    /// Returns the start symbol, or an error if it was not set.
+    pub fn try_start(&self) -> Result<Symbol, GrammarError> {
+        self.start.ok_or(GrammarError::MissingStart)
+    }
+
     pub fn rule(&mut self, lhs: Symbol) -> RuleBuilder<&mut Cfg> {
         // TODO: sequence rules?
         let rule_count = self.inherit.rules().count();
@@ -41,7 +57,10 @@ impl Grammar {
         self.inherit.rule(lhs).history(history_id)
     }
 
-    pub fn sequence(&mut self, lhs: Symbol) -> SequenceRuleBuilder<SequencesToProductions<Cfg>> {
+    pub fn sequence(
+        &mut self,
+        lhs: Symbol,
+    ) -> SequenceRuleBuilder<SequencesToProductions<'_, Cfg>> {
         let rule_count = self.inherit.rules().count();
         let history_id =
             self.add_history_node(RootHistoryNode::Origin { origin: rule_count }.into());
@@ -58,6 +77,14 @@ impl Grammar {
             has_wrapped_start: false,
         }
     }
+
+    pub fn binarize_with(&self, strategy: BinarizationStrategy) -> BinarizedGrammar {
+        BinarizedGrammar {
+            inherit: self.inherit.binarize_with(strategy),
+            start: self.start,
+            has_wrapped_start: false,
+        }
+    }
 }
 
 impl Deref for Grammar {
@@ -4,7 +4,8 @@ use std::rc::Rc;
 use cfg_symbol::Symbol;
 
 use cfg_grammar::{
-    history::node::{BinarizedRhsSubset, LinkedHistoryNode, RootHistoryNode},
+    history::node::{BinarizedRhsSubset, LeftRecursionPart, LinkedHistoryNode, RootHistoryNode},
+    history::{Payload, PayloadValue},
     HistoryNode, RuleContainer,
 };
 
@@ -32,6 +33,8 @@ pub struct History {
     pub origin: ExternalOrigin,
     pub nullable: NullingEliminated,
     pub weight: Option<f64>,
+    /// Payloads from the outermost to the innermost.
+    pub payloads: Vec<Payload>,
     pub sequence: Option<SequenceDetails>,
 }
 
@@ -43,9 +46,9 @@ pub struct RuleDot {
 
 #[derive(Clone, Copy, Default, Debug)]
 pub struct SequenceDetails {
-    top: bool,
-    rhs: Symbol,
-    sep: Option<Symbol>,
+    pub(crate) top: bool,
+    pub(crate) rhs: Symbol,
+    pub(crate) sep: Option<Symbol>,
 }
 
 impl BinarizedGrammar {
@@ -59,22 +62,36 @@ impl BinarizedGrammar {
 }
 
 fn process_node(node: &HistoryNode, prev_histories: &[History]) -> History {
-    match node {
-        &HistoryNode::Linked {
+    match *node {
+        HistoryNode::Linked {
             prev,
             node: ref linked_node,
         } => {
             let prev_history = prev_histories[prev.get()].clone();
-            process_linked(linked_node, prev_history)
+            process_linked(linked_node, prev_history, prev_histories)
         }
-        &HistoryNode::Root(root) => process_root(root),
+        HistoryNode::Root(ref root) => process_root(root),
     }
 }
 
-fn process_linked(linked_node: &LinkedHistoryNode, mut prev_history: History) -> History {
+fn process_linked(
+    linked_node: &LinkedHistoryNode,
+    mut prev_history: History,
+    prev_histories: &[History],
+) -> History {
     match linked_node {
         &LinkedHistoryNode::AssignPrecedence { looseness: _, .. } => prev_history,
         &LinkedHistoryNode::Binarize { depth, .. } => prev_history.binarize(depth),
+        &LinkedHistoryNode::BinarizeRight { depth } => prev_history.binarize_right(depth),
+        &LinkedHistoryNode::BinarizeShared {
+            len,
+            suffix,
+            ref rules,
+        } => {
+            // Dots of the shared part are taken from the first rule that shares it.
+            let rule_history = rules.first().and_then(|id| prev_histories.get(id.get()));
+            prev_history.binarize_shared(rule_history, len, suffix)
+        }
         &LinkedHistoryNode::EliminateNulling {
             which, rhs0, rhs1, ..
         } => prev_history.eliminate_nulling(rhs0, rhs1, which),
@@ -86,7 +103,11 @@ fn process_linked(linked_node: &LinkedHistoryNode, mut prev_history: History) ->
             prev_history.weight = Some(weight);
             prev_history
         }
-        &LinkedHistoryNode::Rhs { ref rhs, .. } => {
+        LinkedHistoryNode::Payload { payload } => {
+            prev_history.payloads.insert(0, payload.clone());
+            prev_history
+        }
+        LinkedHistoryNode::Rhs { rhs, .. } => {
             if let Some(sequence_details) = prev_history.sequence {
                 prev_history.rewrite_sequence(sequence_details, &rhs[..]);
             }
@@ -97,14 +118,69 @@ fn process_linked(linked_node: &LinkedHistoryNode, mut prev_history: History) ->
             prev_history
         }
         &LinkedHistoryNode::Distances { .. } => prev_history,
+        // The rule keeps the dots of the substituted alternative.
+        LinkedHistoryNode::EliminateUnitRules { .. } => prev_history,
+        // Helper rules for lifted terminals do not correspond to any user rule.
+        &LinkedHistoryNode::LiftTerminal { .. } => History {
+            origin: None,
+            dots: vec![RuleDot::none(); 2].into(),
+            ..prev_history
+        },
+        &LinkedHistoryNode::SubstituteLeftCorner { len, .. } => {
+            prev_history.substitute_left_corner(len as usize)
+        }
+        &LinkedHistoryNode::EliminateLeftRecursion { part } => {
+            prev_history.eliminate_left_recursion(part)
+        }
+        // Prefix rules shared by several alternatives do not correspond to any user rule.
+        &LinkedHistoryNode::FactorPrefix { len } => History {
+            origin: None,
+            dots: vec![RuleDot::none(); len as usize + 2].into(),
+            ..prev_history
+        },
+        // `A' ::= beta` keeps the dots of `A ::= alpha beta` that follow `alpha`.
+        &LinkedHistoryNode::FactorSuffix { prefix_len } => History {
+            dots: prev_history
+                .dots
+                .iter()
+                .skip(prefix_len as usize)
+                .cloned()
+                .collect::<Vec<_>>()
+                .into(),
+            ..prev_history
+        },
+        // Renaming keeps the shape of the rule.
+        &LinkedHistoryNode::RenameSymbol { .. } => prev_history,
+        &LinkedHistoryNode::Inline { pos, len, .. } => {
+            prev_history.inline(pos as usize, len as usize)
+        }
+        &LinkedHistoryNode::Extract { pos, len } => {
+            prev_history.extract(pos as usize, len as usize)
+        }
+        // Extracted rules stand for parts of many rules.
+        &LinkedHistoryNode::Extracted { len } => History {
+            origin: None,
+            dots: vec![RuleDot::none(); len as usize + 1].into(),
+            ..prev_history
+        },
+        LinkedHistoryNode::EliminateNullingSubset { nulled } => {
+            prev_history.eliminate_nulling_subset(&nulled[..])
+        }
     }
 }
 
-fn process_root(root_node: RootHistoryNode) -> History {
-    match root_node {
+fn process_root(root_node: &RootHistoryNode) -> History {
+    match *root_node {
         RootHistoryNode::NoOp => History::new(0, 0),
         RootHistoryNode::Rule { lhs: _ } => History::new(0, 0),
         RootHistoryNode::Origin { origin } => History::new(origin as u32, 0),
+        RootHistoryNode::Payload {
+            origin,
+            ref payload,
+        } => History {
+            payloads: vec![payload.clone()],
+            ..History::new(origin as u32, 0)
+        },
     }
 }
 
@@ -132,7 +208,7 @@ impl RuleDot {
     }
 
     pub fn event_without_tracing(self) -> Event {
-        (self.event.and_then(|x| x.0.into()).into(), self.distance)
+        (self.event.and_then(|x| x.0), self.distance)
     }
 
     pub fn distance(&self) -> MinimalDistance {
@@ -164,6 +240,11 @@ impl History {
         self.dots[n]
     }
 
+    /// Returns the value of the outermost payload of type `T`.
+    pub fn payload<T: PayloadValue>(&self) -> Option<&T> {
+        self.payloads.iter().find_map(|payload| payload.get::<T>())
+    }
+
     fn binarize(&self, depth: u32) -> Self {
         let none = RuleDot::none();
         let dots = if self.dots.is_empty() {
@@ -192,6 +273,49 @@ impl History {
         }
     }
 
+    /// A shared helper rule takes the dot between its two symbols from the first rule it is part
+    /// of. The other dots are those of the rules that use the helper.
+    fn binarize_shared(&self, rule_history: Option<&History>, len: u32, suffix: bool) -> Self {
+        let none = RuleDot::none();
+        let len = len as usize;
+        let dots = rule_history.map_or(&[][..], |history| &history.dots[..]);
+        let dot_len = dots.len();
+        let middle = if dot_len < len + 1 || len == 0 {
+            none
+        } else if suffix {
+            dots[dot_len - len]
+        } else {
+            dots[len - 1]
+        };
+        History {
+            origin: None,
+            dots: vec![none, middle, none].into(),
+            ..self.clone()
+        }
+    }
+
+    fn binarize_right(&self, depth: u32) -> Self {
+        let none = RuleDot::none();
+        let dot_len = self.dots.len();
+        if dot_len < 4 {
+            // Rules with up to two symbols are not split.
+            return self.binarize(depth);
+        }
+        let dots = if depth == 0 {
+            [self.dots[0], self.dots[1], self.dots[dot_len - 1]]
+        } else {
+            [none, self.dots[depth as usize + 1], none]
+        };
+
+        let origin = if depth == 0 { self.origin } else { None };
+
+        History {
+            origin,
+            dots: dots[..].to_vec().into(),
+            ..self.clone()
+        }
+    }
+
     fn eliminate_nulling(
         &self,
         rhs0: Symbol,
@@ -216,6 +340,82 @@ impl History {
         }
     }
 
+    fn eliminate_nulling_subset(&self, nulled: &[u32]) -> Self {
+        // Every remaining symbol keeps the dot that precedes it. The last dot is kept.
+        let dots: Vec<RuleDot> = self
+            .dots
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| !nulled.contains(&(i as u32)))
+            .map(|(_, &dot)| dot)
+            .collect();
+        History {
+            dots: dots.into(),
+            ..self.clone()
+        }
+    }
+
+    fn substitute_left_corner(&self, len: usize) -> Self {
+        self.inline(0, len)
+    }
+
+    fn inline(&self, pos: usize, len: usize) -> Self {
+        // The symbol at `pos` is replaced with `len` symbols, which have no dots between them.
+        let mut dots = self.dots[..self.dots.len().min(pos + 1)].to_vec();
+        dots.extend(iter::repeat_n(RuleDot::none(), len.saturating_sub(1)));
+        dots.extend(
+            self.dots
+                .iter()
+                .skip(pos + if len == 0 { 2 } else { 1 })
+                .cloned(),
+        );
+        History {
+            dots: dots.into(),
+            ..self.clone()
+        }
+    }
+
+    fn extract(&self, pos: usize, len: usize) -> Self {
+        // The `len` symbols at `pos` are replaced with one symbol, which keeps the outer dots.
+        let mut dots = self.dots[..self.dots.len().min(pos + 1)].to_vec();
+        dots.extend(self.dots.iter().skip(pos + len.max(1)).cloned());
+        History {
+            dots: dots.into(),
+            ..self.clone()
+        }
+    }
+
+    fn eliminate_left_recursion(&self, part: LeftRecursionPart) -> Self {
+        let dots = match part {
+            // `A ::= beta A'` keeps the dots of `A ::= beta`.
+            LeftRecursionPart::Base => self
+                .dots
+                .iter()
+                .cloned()
+                .chain(iter::once(RuleDot::none()))
+                .collect(),
+            // `A' ::= alpha A'` keeps the dots of `A ::= A alpha` that follow `A`.
+            LeftRecursionPart::Tail => self
+                .dots
+                .iter()
+                .skip(1)
+                .cloned()
+                .chain(iter::once(RuleDot::none()))
+                .collect(),
+            LeftRecursionPart::End => vec![RuleDot::none()],
+        };
+        let origin = if let LeftRecursionPart::End = part {
+            None
+        } else {
+            self.origin
+        };
+        History {
+            origin,
+            dots: Rc::new(dots),
+            ..self.clone()
+        }
+    }
+
     fn rewrite_sequence(&self, details: SequenceDetails, new_rhs: &[Symbol]) -> Self {
         if details.top {
             self.rewrite_sequence_top(details, new_rhs)
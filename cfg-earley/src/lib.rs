@@ -3,6 +3,8 @@
 mod binarized_grammar;
 pub mod grammar;
 pub mod history;
+pub mod precompiled;
 
 pub use self::binarized_grammar::BinarizedGrammar;
 pub use self::grammar::Grammar;
+pub use self::precompiled::PrecompiledGrammar;
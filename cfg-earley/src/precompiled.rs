@@ -0,0 +1,764 @@
+//! A compact binary format for grammars that are fully prepared for Earley parsing.
+//!
+//! The format stores the binarized grammar, its nulling grammar, the `Mapping` from
+//! `remap_symbols`, and the final `History` of every rule, so that none of these need to be
+//! recomputed on start-up.
+//!
+//! All values are stored as little-endian 32-bit words, which keeps the payload aligned for
+//! memory-mapping. The header holds a magic number, the format version, the length of the
+//! payload and its 64-bit FNV-1a checksum.
+
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use cfg_grammar::fnv::Fnv1a;
+use cfg_grammar::history::node::{
+    BinarizedRhsSubset, LeftRecursionPart, LinkedHistoryNode, RootHistoryNode,
+};
+use cfg_grammar::history::Payload;
+use cfg_grammar::rule::RuleRef;
+use cfg_grammar::{BinarizedCfg, HistoryId, HistoryNode, RuleContainer};
+use cfg_symbol::intern::Mapping;
+use cfg_symbol::Symbol;
+
+use crate::history::{History, RuleDot, SequenceDetails};
+use crate::{BinarizedGrammar, Grammar};
+
+/// The current version of the format.
+pub const FORMAT_VERSION: u32 = 3;
+
+const MAGIC: [u8; 4] = *b"CFGE";
+const HEADER_LEN: usize = 20;
+const NONE: u32 = !0;
+
+/// A binarized grammar prepared for Earley parsing, together with its nulling grammar, symbol
+/// mapping and rule histories.
+#[derive(Clone)]
+pub struct PrecompiledGrammar {
+    grammar: BinarizedGrammar,
+    nulling_grammar: BinarizedGrammar,
+    mapping: Mapping,
+    histories: Vec<History>,
+    nulling_histories: Vec<History>,
+}
+
+/// An error encountered while reading a precompiled grammar.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PrecompiledError {
+    /// The input does not start with the magic number.
+    BadMagic,
+    /// The input was written in another version of the format.
+    UnsupportedVersion(u32),
+    /// The checksum of the payload does not match.
+    ChecksumMismatch,
+    /// The input ended unexpectedly.
+    UnexpectedEof,
+    /// The payload is malformed.
+    Invalid,
+}
+
+impl PrecompiledGrammar {
+    /// Prepares a grammar by binarizing it, removing useless rules, eliminating nulling rules
+    /// and remapping symbols.
+    pub fn new(grammar: &Grammar) -> Self {
+        let (proper, nulling_grammar) = grammar.binarize().make_proper().eliminate_nulling();
+        let (proper, mapping) = proper.remap_symbols();
+        Self::from_parts(proper, nulling_grammar, mapping)
+    }
+
+    /// Creates a precompiled grammar from grammars that were prepared elsewhere. Rule
+    /// histories are computed with `final_history`.
+    pub fn from_parts(
+        grammar: BinarizedGrammar,
+        nulling_grammar: BinarizedGrammar,
+        mapping: Mapping,
+    ) -> Self {
+        PrecompiledGrammar {
+            histories: rule_histories(&grammar),
+            nulling_histories: rule_histories(&nulling_grammar),
+            grammar,
+            nulling_grammar,
+            mapping,
+        }
+    }
+
+    /// Returns the grammar.
+    pub fn grammar(&self) -> &BinarizedGrammar {
+        &self.grammar
+    }
+
+    /// Returns the nulling grammar.
+    pub fn nulling_grammar(&self) -> &BinarizedGrammar {
+        &self.nulling_grammar
+    }
+
+    /// Returns the mapping between external and internal symbols.
+    pub fn mapping(&self) -> &Mapping {
+        &self.mapping
+    }
+
+    /// Returns the history of every rule of the grammar, in the order of `rules()`.
+    pub fn histories(&self) -> &[History] {
+        &self.histories[..]
+    }
+
+    /// Returns the history of every rule of the nulling grammar, in the order of `rules()`.
+    pub fn nulling_histories(&self) -> &[History] {
+        &self.nulling_histories[..]
+    }
+
+    /// Encodes this grammar in the binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut payload = Writer { bytes: vec![] };
+        payload.grammar(&self.grammar);
+        payload.grammar(&self.nulling_grammar);
+        payload.mapping(&self.mapping);
+        payload.histories(&self.histories);
+        payload.histories(&self.nulling_histories);
+
+        let mut result = Vec::with_capacity(HEADER_LEN + payload.bytes.len());
+        result.extend_from_slice(&MAGIC);
+        result.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        result.extend_from_slice(&(payload.bytes.len() as u32).to_le_bytes());
+        result.extend_from_slice(&checksum(&payload.bytes).to_le_bytes());
+        result.extend_from_slice(&payload.bytes);
+        result
+    }
+
+    /// Decodes a grammar from the binary format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PrecompiledError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(PrecompiledError::UnexpectedEof);
+        }
+        if bytes[..4] != MAGIC {
+            return Err(PrecompiledError::BadMagic);
+        }
+        let mut header = Reader {
+            bytes: &bytes[4..HEADER_LEN],
+        };
+        let version = header.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(PrecompiledError::UnsupportedVersion(version));
+        }
+        let len = header.u32()? as usize;
+        let expected_checksum = header.u64()?;
+        let payload = bytes
+            .get(HEADER_LEN..HEADER_LEN + len)
+            .ok_or(PrecompiledError::UnexpectedEof)?;
+        if checksum(payload) != expected_checksum {
+            return Err(PrecompiledError::ChecksumMismatch);
+        }
+
+        let mut reader = Reader { bytes: payload };
+        let result = PrecompiledGrammar {
+            grammar: reader.grammar()?,
+            nulling_grammar: reader.grammar()?,
+            mapping: reader.mapping()?,
+            histories: reader.histories()?,
+            nulling_histories: reader.histories()?,
+        };
+        if !reader.bytes.is_empty() || !result.is_valid() {
+            return Err(PrecompiledError::Invalid);
+        }
+        Ok(result)
+    }
+
+    /// Checks that histories match rules and that symbols are in range. History graphs and
+    /// rule histories refer to external symbols, since they are not remapped.
+    fn is_valid(&self) -> bool {
+        let num_syms = self.grammar.num_syms();
+        let num_external = self.mapping.to_internal.len();
+        let in_range = |sym: &Symbol| sym.usize() < num_external;
+        let history_in_range = |history: &History| {
+            history.nullable.is_none_or(|(sym, _)| in_range(&sym))
+                && history.sequence.is_none_or(|sequence| {
+                    in_range(&sequence.rhs) && sequence.sep.iter().all(in_range)
+                })
+        };
+        self.histories.len() == self.grammar.rules().count()
+            && self.nulling_histories.len() == self.nulling_grammar.rules().count()
+            // Nodes may only refer to earlier nodes, such as `prev` and `inner` of `Inline`.
+            && self.grammar.history_graph().is_valid(num_external)
+            && self.nulling_grammar.history_graph().is_valid(num_external)
+            && self
+                .mapping
+                .to_internal
+                .iter()
+                .flatten()
+                .all(|sym| sym.usize() < num_syms)
+            && self.mapping.to_external.iter().all(in_range)
+            && self.histories.iter().all(history_in_range)
+            && self.nulling_histories.iter().all(history_in_range)
+    }
+}
+
+fn rule_histories(grammar: &BinarizedGrammar) -> Vec<History> {
+    let final_history = grammar.final_history();
+    grammar
+        .rules()
+        .map(|rule| final_history[rule.history_id.get()].clone())
+        .collect()
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write_bytes(bytes);
+    hasher.finish()
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u32(value as u32);
+    }
+
+    /// Writes the length of the string, then its bytes padded to whole words.
+    fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+        self.bytes.resize(self.bytes.len().next_multiple_of(4), 0);
+    }
+
+    /// Writes the key of the payload's type, then its encoded value.
+    fn payload(&mut self, payload: &Payload) {
+        self.str(payload.key());
+        self.str(&payload.encode());
+    }
+
+    fn sym(&mut self, sym: Symbol) {
+        self.u32(sym.into());
+    }
+
+    fn opt_sym(&mut self, sym: Option<Symbol>) {
+        self.u32(sym.map_or(NONE, |sym| sym.into()));
+    }
+
+    fn grammar(&mut self, grammar: &BinarizedGrammar) {
+        self.len(grammar.num_syms());
+        self.opt_sym(grammar.start);
+        self.bool(grammar.has_wrapped_start);
+        self.len(grammar.rules().count());
+        for rule in grammar.rules() {
+            self.sym(rule.lhs);
+            self.len(rule.rhs.len());
+            for &sym in rule.rhs {
+                self.sym(sym);
+            }
+            self.len(rule.history_id.get());
+        }
+        // The first node is always the `NoOp` root.
+        self.len(grammar.history_graph().len() - 1);
+        for node in grammar.history_graph().iter().skip(1) {
+            self.history_node(node);
+        }
+    }
+
+    fn history_node(&mut self, node: &HistoryNode) {
+        match node {
+            HistoryNode::Root(RootHistoryNode::NoOp) => self.u32(0),
+            &HistoryNode::Root(RootHistoryNode::Rule { lhs }) => {
+                self.u32(1);
+                self.sym(lhs);
+            }
+            &HistoryNode::Root(RootHistoryNode::Origin { origin }) => {
+                self.u32(2);
+                self.u64(origin as u64);
+            }
+            HistoryNode::Root(RootHistoryNode::Payload { origin, payload }) => {
+                self.u32(24);
+                self.u64(*origin as u64);
+                self.payload(payload);
+            }
+            HistoryNode::Linked { prev, node } => {
+                let tag = match node {
+                    LinkedHistoryNode::Rhs { .. } => 3,
+                    LinkedHistoryNode::Binarize { .. } => 4,
+                    LinkedHistoryNode::EliminateNulling { .. } => 5,
+                    LinkedHistoryNode::AssignPrecedence { .. } => 6,
+                    LinkedHistoryNode::RewriteSequence { .. } => 7,
+                    LinkedHistoryNode::Weight { .. } => 8,
+                    LinkedHistoryNode::Distances { .. } => 9,
+                    LinkedHistoryNode::EliminateUnitRules { .. } => 10,
+                    LinkedHistoryNode::LiftTerminal { .. } => 11,
+                    LinkedHistoryNode::SubstituteLeftCorner { .. } => 12,
+                    LinkedHistoryNode::EliminateLeftRecursion { .. } => 13,
+                    LinkedHistoryNode::FactorPrefix { .. } => 14,
+                    LinkedHistoryNode::FactorSuffix { .. } => 15,
+                    LinkedHistoryNode::EliminateNullingSubset { .. } => 16,
+                    LinkedHistoryNode::BinarizeRight { .. } => 17,
+                    LinkedHistoryNode::BinarizeShared { .. } => 18,
+                    LinkedHistoryNode::RenameSymbol { .. } => 19,
+                    LinkedHistoryNode::Inline { .. } => 20,
+                    LinkedHistoryNode::Extract { .. } => 21,
+                    LinkedHistoryNode::Extracted { .. } => 22,
+                    LinkedHistoryNode::Payload { .. } => 23,
+                };
+                self.u32(tag);
+                self.len(prev.get());
+                self.linked_history_node(node);
+            }
+        }
+    }
+
+    fn linked_history_node(&mut self, node: &LinkedHistoryNode) {
+        match node {
+            LinkedHistoryNode::Rhs { rhs } => {
+                self.len(rhs.len());
+                for &sym in rhs {
+                    self.sym(sym);
+                }
+            }
+            &LinkedHistoryNode::Binarize { depth } => self.u32(depth),
+            &LinkedHistoryNode::EliminateNulling { rhs0, rhs1, which } => {
+                self.sym(rhs0);
+                self.opt_sym(rhs1);
+                self.u32(match which {
+                    BinarizedRhsSubset::Left => 0,
+                    BinarizedRhsSubset::Right => 1,
+                    BinarizedRhsSubset::All => 2,
+                });
+            }
+            &LinkedHistoryNode::AssignPrecedence { looseness } => self.u32(looseness),
+            &LinkedHistoryNode::RewriteSequence { top, rhs, sep } => {
+                self.bool(top);
+                self.sym(rhs);
+                self.opt_sym(sep);
+            }
+            &LinkedHistoryNode::Weight { weight } => self.u64(weight.to_bits()),
+            LinkedHistoryNode::Distances { events } => {
+                self.len(events.len());
+                for &event in events {
+                    self.u32(event);
+                }
+            }
+            LinkedHistoryNode::EliminateUnitRules { chain } => {
+                self.len(chain.len());
+                for &id in chain {
+                    self.len(id.get());
+                }
+            }
+            &LinkedHistoryNode::LiftTerminal { terminal } => self.sym(terminal),
+            &LinkedHistoryNode::SubstituteLeftCorner { inner, len } => {
+                self.len(inner.get());
+                self.u32(len);
+            }
+            &LinkedHistoryNode::EliminateLeftRecursion { part } => self.u32(match part {
+                LeftRecursionPart::Base => 0,
+                LeftRecursionPart::Tail => 1,
+                LeftRecursionPart::End => 2,
+            }),
+            &LinkedHistoryNode::FactorPrefix { len } => self.u32(len),
+            &LinkedHistoryNode::FactorSuffix { prefix_len } => self.u32(prefix_len),
+            &LinkedHistoryNode::BinarizeRight { depth } => self.u32(depth),
+            LinkedHistoryNode::BinarizeShared { len, suffix, rules } => {
+                self.u32(*len);
+                self.bool(*suffix);
+                self.len(rules.len());
+                for &id in rules {
+                    self.len(id.get());
+                }
+            }
+            &LinkedHistoryNode::RenameSymbol { from, to } => {
+                self.sym(from);
+                self.sym(to);
+            }
+            &LinkedHistoryNode::Inline { inner, pos, len } => {
+                self.len(inner.get());
+                self.u32(pos);
+                self.u32(len);
+            }
+            &LinkedHistoryNode::Extract { pos, len } => {
+                self.u32(pos);
+                self.u32(len);
+            }
+            &LinkedHistoryNode::Extracted { len } => self.u32(len),
+            LinkedHistoryNode::Payload { payload } => self.payload(payload),
+            LinkedHistoryNode::EliminateNullingSubset { nulled } => {
+                self.len(nulled.len());
+                for &pos in nulled {
+                    self.u32(pos);
+                }
+            }
+        }
+    }
+
+    fn mapping(&mut self, mapping: &Mapping) {
+        self.len(mapping.to_internal.len());
+        for &sym in &mapping.to_internal {
+            self.opt_sym(sym);
+        }
+        self.len(mapping.to_external.len());
+        for &sym in &mapping.to_external {
+            self.sym(sym);
+        }
+    }
+
+    fn histories(&mut self, histories: &[History]) {
+        self.len(histories.len());
+        for history in histories {
+            self.len(history.dots.len());
+            for dot in history.dots.iter() {
+                match dot.event {
+                    Some((event_id, (id, pos))) => {
+                        self.bool(true);
+                        self.opt_sym(event_id);
+                        self.u32(id);
+                        self.u32(pos);
+                    }
+                    None => self.bool(false),
+                }
+                self.opt_sym(dot.distance);
+            }
+            self.opt_sym(history.origin);
+            match history.nullable {
+                Some((sym, right)) => {
+                    self.bool(true);
+                    self.sym(sym);
+                    self.bool(right);
+                }
+                None => self.bool(false),
+            }
+            match history.weight {
+                Some(weight) => {
+                    self.bool(true);
+                    self.u64(weight.to_bits());
+                }
+                None => self.bool(false),
+            }
+            self.len(history.payloads.len());
+            for payload in &history.payloads {
+                self.payload(payload);
+            }
+            match history.sequence {
+                Some(details) => {
+                    self.bool(true);
+                    self.bool(details.top);
+                    self.sym(details.rhs);
+                    self.opt_sym(details.sep);
+                }
+                None => self.bool(false),
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn u32(&mut self) -> Result<u32, PrecompiledError> {
+        if self.bytes.len() < 4 {
+            return Err(PrecompiledError::UnexpectedEof);
+        }
+        let (word, rest) = self.bytes.split_at(4);
+        self.bytes = rest;
+        Ok(u32::from_le_bytes(word.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, PrecompiledError> {
+        let low = self.u32()? as u64;
+        let high = self.u32()? as u64;
+        Ok(low | high << 32)
+    }
+
+    fn len(&mut self) -> Result<usize, PrecompiledError> {
+        let len = self.u32()? as usize;
+        // Every element takes at least one word.
+        if len > self.bytes.len() / 4 {
+            return Err(PrecompiledError::UnexpectedEof);
+        }
+        Ok(len)
+    }
+
+    fn bool(&mut self) -> Result<bool, PrecompiledError> {
+        match self.u32()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PrecompiledError::Invalid),
+        }
+    }
+
+    fn str(&mut self) -> Result<String, PrecompiledError> {
+        let len = self.u32()? as usize;
+        let padded = len.next_multiple_of(4);
+        if padded > self.bytes.len() {
+            return Err(PrecompiledError::UnexpectedEof);
+        }
+        let (bytes, rest) = self.bytes.split_at(padded);
+        self.bytes = rest;
+        String::from_utf8(bytes[..len].to_vec()).map_err(|_| PrecompiledError::Invalid)
+    }
+
+    fn payload(&mut self) -> Result<Payload, PrecompiledError> {
+        let key = self.str()?;
+        Ok(Payload::from_encoded(&key, self.str()?))
+    }
+
+    fn sym(&mut self) -> Result<Symbol, PrecompiledError> {
+        self.opt_sym()?.ok_or(PrecompiledError::Invalid)
+    }
+
+    fn opt_sym(&mut self) -> Result<Option<Symbol>, PrecompiledError> {
+        match self.u32()? {
+            NONE => Ok(None),
+            id => Ok(Some(id.into())),
+        }
+    }
+
+    fn history_id(&mut self) -> Result<HistoryId, PrecompiledError> {
+        HistoryId::new(self.u32()? as usize).ok_or(PrecompiledError::Invalid)
+    }
+
+    fn syms(&mut self) -> Result<Vec<Symbol>, PrecompiledError> {
+        (0..self.len()?).map(|_| self.sym()).collect()
+    }
+
+    fn grammar(&mut self) -> Result<BinarizedGrammar, PrecompiledError> {
+        let mut inherit = BinarizedCfg::new();
+        // The symbol mapping that follows takes a word for every symbol.
+        for _ in 0..self.len()? {
+            inherit.next_sym();
+        }
+        let start = self.opt_sym()?;
+        let has_wrapped_start = self.bool()?;
+        let mut rules = vec![];
+        for _ in 0..self.len()? {
+            rules.push((self.sym()?, self.syms()?, self.history_id()?));
+        }
+        let num_syms = inherit.num_syms();
+        let num_nodes = self.len()? + 1;
+        for _ in 1..num_nodes {
+            // Nodes are checked in `is_valid`, once the number of external symbols is known.
+            inherit.add_history_node(self.history_node()?);
+        }
+
+        let in_range = |sym: &Symbol| sym.usize() < num_syms;
+        for (lhs, rhs, history_id) in rules {
+            if rhs.len() > 2
+                || !in_range(&lhs)
+                || !rhs.iter().all(in_range)
+                || history_id.get() >= num_nodes
+                || rhs.is_empty()
+                    && inherit
+                        .rules()
+                        .any(|rule| rule.lhs == lhs && rule.rhs.is_empty())
+            {
+                return Err(PrecompiledError::Invalid);
+            }
+            inherit.add_binarized_rule(RuleRef {
+                lhs,
+                rhs: &rhs[..],
+                history_id,
+            });
+        }
+        Ok(BinarizedGrammar {
+            inherit,
+            start,
+            has_wrapped_start,
+        })
+    }
+
+    fn history_node(&mut self) -> Result<HistoryNode, PrecompiledError> {
+        let tag = self.u32()?;
+        let root = match tag {
+            0 => RootHistoryNode::NoOp,
+            1 => RootHistoryNode::Rule { lhs: self.sym()? },
+            2 => RootHistoryNode::Origin {
+                origin: self.u64()? as usize,
+            },
+            24 => RootHistoryNode::Payload {
+                origin: self.u64()? as usize,
+                payload: self.payload()?,
+            },
+            _ => {
+                let prev = self.history_id()?;
+                return Ok(HistoryNode::Linked {
+                    prev,
+                    node: self.linked_history_node(tag)?,
+                });
+            }
+        };
+        Ok(root.into())
+    }
+
+    fn linked_history_node(&mut self, tag: u32) -> Result<LinkedHistoryNode, PrecompiledError> {
+        let node = match tag {
+            3 => LinkedHistoryNode::Rhs { rhs: self.syms()? },
+            4 => LinkedHistoryNode::Binarize { depth: self.u32()? },
+            5 => LinkedHistoryNode::EliminateNulling {
+                rhs0: self.sym()?,
+                rhs1: self.opt_sym()?,
+                which: match self.u32()? {
+                    0 => BinarizedRhsSubset::Left,
+                    1 => BinarizedRhsSubset::Right,
+                    2 => BinarizedRhsSubset::All,
+                    _ => return Err(PrecompiledError::Invalid),
+                },
+            },
+            6 => LinkedHistoryNode::AssignPrecedence {
+                looseness: self.u32()?,
+            },
+            7 => LinkedHistoryNode::RewriteSequence {
+                top: self.bool()?,
+                rhs: self.sym()?,
+                sep: self.opt_sym()?,
+            },
+            8 => LinkedHistoryNode::Weight {
+                weight: f64::from_bits(self.u64()?),
+            },
+            9 => LinkedHistoryNode::Distances {
+                events: (0..self.len()?)
+                    .map(|_| self.u32())
+                    .collect::<Result<_, _>>()?,
+            },
+            10 => LinkedHistoryNode::EliminateUnitRules {
+                chain: (0..self.len()?)
+                    .map(|_| self.history_id())
+                    .collect::<Result<_, _>>()?,
+            },
+            11 => LinkedHistoryNode::LiftTerminal {
+                terminal: self.sym()?,
+            },
+            12 => LinkedHistoryNode::SubstituteLeftCorner {
+                inner: self.history_id()?,
+                len: self.u32()?,
+            },
+            13 => LinkedHistoryNode::EliminateLeftRecursion {
+                part: match self.u32()? {
+                    0 => LeftRecursionPart::Base,
+                    1 => LeftRecursionPart::Tail,
+                    2 => LeftRecursionPart::End,
+                    _ => return Err(PrecompiledError::Invalid),
+                },
+            },
+            14 => LinkedHistoryNode::FactorPrefix { len: self.u32()? },
+            15 => LinkedHistoryNode::FactorSuffix {
+                prefix_len: self.u32()?,
+            },
+            16 => LinkedHistoryNode::EliminateNullingSubset {
+                nulled: (0..self.len()?)
+                    .map(|_| self.u32())
+                    .collect::<Result<_, _>>()?,
+            },
+            17 => LinkedHistoryNode::BinarizeRight { depth: self.u32()? },
+            18 => LinkedHistoryNode::BinarizeShared {
+                len: self.u32()?,
+                suffix: self.bool()?,
+                rules: (0..self.len()?)
+                    .map(|_| self.history_id())
+                    .collect::<Result<_, _>>()?,
+            },
+            19 => LinkedHistoryNode::RenameSymbol {
+                from: self.sym()?,
+                to: self.sym()?,
+            },
+            20 => LinkedHistoryNode::Inline {
+                inner: self.history_id()?,
+                pos: self.u32()?,
+                len: self.u32()?,
+            },
+            21 => LinkedHistoryNode::Extract {
+                pos: self.u32()?,
+                len: self.u32()?,
+            },
+            22 => LinkedHistoryNode::Extracted { len: self.u32()? },
+            23 => LinkedHistoryNode::Payload {
+                payload: self.payload()?,
+            },
+            _ => return Err(PrecompiledError::Invalid),
+        };
+        Ok(node)
+    }
+
+    fn mapping(&mut self) -> Result<Mapping, PrecompiledError> {
+        let to_internal = (0..self.len()?)
+            .map(|_| self.opt_sym())
+            .collect::<Result<_, _>>()?;
+        let to_external = self.syms()?;
+        Ok(Mapping {
+            to_internal,
+            to_external,
+        })
+    }
+
+    fn histories(&mut self) -> Result<Vec<History>, PrecompiledError> {
+        (0..self.len()?).map(|_| self.history()).collect()
+    }
+
+    fn history(&mut self) -> Result<History, PrecompiledError> {
+        let mut dots = vec![];
+        for _ in 0..self.len()? {
+            let event = if self.bool()? {
+                Some((self.opt_sym()?, (self.u32()?, self.u32()?)))
+            } else {
+                None
+            };
+            let distance = self.opt_sym()?;
+            dots.push(RuleDot { event, distance });
+        }
+        let origin = self.opt_sym()?;
+        let nullable = if self.bool()? {
+            Some((self.sym()?, self.bool()?))
+        } else {
+            None
+        };
+        let weight = if self.bool()? {
+            Some(f64::from_bits(self.u64()?))
+        } else {
+            None
+        };
+        let payloads = (0..self.len()?)
+            .map(|_| self.payload())
+            .collect::<Result<_, _>>()?;
+        let sequence = if self.bool()? {
+            Some(SequenceDetails {
+                top: self.bool()?,
+                rhs: self.sym()?,
+                sep: self.opt_sym()?,
+            })
+        } else {
+            None
+        };
+        Ok(History {
+            dots: Rc::new(dots),
+            origin,
+            nullable,
+            weight,
+            payloads,
+            sequence,
+        })
+    }
+}
+
+impl fmt::Display for PrecompiledError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrecompiledError::BadMagic => write!(f, "not a precompiled grammar"),
+            PrecompiledError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            PrecompiledError::ChecksumMismatch => write!(f, "checksum mismatch"),
+            PrecompiledError::UnexpectedEof => write!(f, "unexpected end of input"),
+            PrecompiledError::Invalid => write!(f, "malformed grammar"),
+        }
+    }
+}
+
+impl Error for PrecompiledError {}
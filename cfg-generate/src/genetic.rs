@@ -32,21 +32,21 @@ struct RuleDescription {
 
 impl PartialOrd for RuleDescription {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let ordering = (&self.rule_kind, &self.rhs).cmp(&(&other.rule_kind, &other.rhs));
-        let ordering = ordering
-            .then(self.amount.start.cmp(&other.amount.start))
-            .then(self.amount.end.cmp(&other.amount.end));
-        Some(ordering)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for RuleDescription {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        let ordering = (&self.rule_kind, &self.rhs).cmp(&(&other.rule_kind, &other.rhs));
+        ordering
+            .then(self.amount.start.cmp(&other.amount.start))
+            .then(self.amount.end.cmp(&other.amount.end))
     }
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[allow(clippy::enum_variant_names)]
 enum RuleKind {
     Cyclical,
     LeftRecursive,
@@ -61,6 +61,7 @@ enum RuleKind {
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[allow(clippy::enum_variant_names)]
 enum Rhs {
     Unary {
         rhs0: Symbol,
@@ -115,15 +116,15 @@ impl RuleDescription {
 
 impl Rhs {
     fn mutate(&self, _mutation: Mutation) -> Self {
-        match self {
-            &Unary {
+        match *self {
+            Unary {
                 rhs0,
                 rhs0_is_transparent,
             } => Unary {
                 rhs0,
                 rhs0_is_transparent,
             },
-            &Binary {
+            Binary {
                 rhs0,
                 rhs0_is_transparent,
                 rhs1,
@@ -134,7 +135,7 @@ impl Rhs {
                 rhs1,
                 rhs1_is_transparent,
             },
-            &AnyRhs => AnyRhs,
+            AnyRhs => AnyRhs,
         }
     }
 }
@@ -163,6 +164,6 @@ fn test_zero_mutation() {
     };
     target.population.mutate(mutation);
     for description in &target.population.rule_descriptions {
-        assert!(rule_descriptions.contains(&description));
+        assert!(rule_descriptions.contains(description));
     }
 }
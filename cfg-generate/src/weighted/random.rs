@@ -155,7 +155,7 @@ impl Random for BinarizedCfg {
                 }
                 if let Some(back) = backtracking.get_mut(&string.len()) {
                     for state in back.iter_mut() {
-                        if string.ends_with(&state.forbidden[..]) {
+                        if string.ends_with(state.forbidden) {
                             *rng = state.rng.clone();
                             string.truncate(string.len() - state.forbidden.len());
                             result.truncate(state.result_len);
@@ -2,13 +2,14 @@
 //! A binarized grammar contains only such rules.
 
 use std::cmp::{self, Ord, Ordering};
+use std::collections::HashMap;
 use std::iter;
-use std::mem;
 
 use bit_vec::BitVec;
 
 use crate::history::node::{
-    BinarizedRhsSubset::*, HistoryNodeBinarize, HistoryNodeEliminateNulling,
+    BinarizedRhsSubset::*, HistoryNodeBinarize, HistoryNodeBinarizeRight,
+    HistoryNodeBinarizeShared, HistoryNodeEliminateNulling, RootHistoryNode,
 };
 use crate::history::{HistoryGraph, HistoryId, HistoryNode};
 use crate::local_prelude::*;
@@ -48,6 +49,25 @@ pub enum BinarizedRuleRhs {
     Two([Symbol; 2]),
 }
 
+/// The shape of rules produced by binarization. Below, `A B … Y Z` is a rule's RHS and `S0`,
+/// `S1`, … are helper nonterminals.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BinarizationStrategy {
+    /// `LHS ::= S0 Z`, `S0 ::= S1 Y`, …, `Sn ::= A B`. Every rule gets its own helpers, which
+    /// are left children. Histories are `LinkedHistoryNode::Binarize`.
+    #[default]
+    LeftBranching,
+    /// `LHS ::= A S0`, `S0 ::= B S1`, …, `Sn ::= Y Z`. Every rule gets its own helpers, which
+    /// are right children. Histories are `LinkedHistoryNode::BinarizeRight`.
+    RightBranching,
+    /// Left-branching, with one helper for every distinct prefix, shared by all rules that
+    /// begin with it.
+    SharedPrefix,
+    /// Right-branching, with one helper for every distinct suffix, shared by all rules that
+    /// end with it.
+    SharedSuffix,
+}
+
 impl Default for BinarizedCfg {
     fn default() -> Self {
         Self::with_sym_source(SymbolSource::new())
@@ -63,7 +83,7 @@ impl BinarizedCfg {
     /// Creates an empty BinarizedCfg with the given symbol source.
     pub fn with_sym_source(sym_source: SymbolSource) -> Self {
         BinarizedCfg {
-            sym_source: sym_source,
+            sym_source,
             rules: vec![],
             nulling: vec![],
             history_graph: HistoryGraph::new(),
@@ -72,6 +92,19 @@ impl BinarizedCfg {
 
     /// Creates a BinarizedCfg by binarizing a context-free grammar.
     pub fn from_context_free<G>(this: &G) -> BinarizedCfg
+    where
+        G: RuleContainer + Default,
+    {
+        Self::from_context_free_with(this, BinarizationStrategy::default())
+    }
+
+    /// Creates a BinarizedCfg by binarizing a context-free grammar with the given strategy.
+    ///
+    /// With a shared strategy, the top rule of every split keeps its history as with the
+    /// corresponding unshared strategy. Shared helper rules stand for parts of many rules, so
+    /// they have `LinkedHistoryNode::BinarizeShared` on top of a new root, with the number of
+    /// symbols they derive and the histories of the rules they are part of.
+    pub fn from_context_free_with<G>(this: &G, strategy: BinarizationStrategy) -> BinarizedCfg
     where
         G: RuleContainer + Default,
     {
@@ -88,13 +121,131 @@ impl BinarizedCfg {
         grammar.history_graph = this.history_graph().clone();
         grammar.rules = Vec::with_capacity(new_rule_count);
         // Insert all rules from one grammar into the other.
+        let prefix = strategy == BinarizationStrategy::SharedPrefix;
+        // Histories of the rules that share each part, so that every helper's history node
+        // is complete when it's added.
+        let mut sharers: HashMap<&[Symbol], Vec<HistoryId>> = HashMap::new();
+        if let BinarizationStrategy::SharedPrefix | BinarizationStrategy::SharedSuffix = strategy {
+            for rule in this.rules() {
+                for part in shared_parts(rule.rhs, prefix) {
+                    sharers.entry(part).or_default().push(rule.history_id);
+                }
+            }
+        }
+        let mut helpers = HashMap::new();
+        let mut shared_root = None;
         for rule in this.rules() {
-            grammar.add_rule(rule);
+            if rule.rhs.len() <= 2 {
+                grammar.add_rule(rule);
+                continue;
+            }
+            match strategy {
+                BinarizationStrategy::LeftBranching => grammar.add_rule(rule),
+                BinarizationStrategy::RightBranching => grammar.add_right_branching(rule),
+                BinarizationStrategy::SharedPrefix | BinarizationStrategy::SharedSuffix => {
+                    let root = *shared_root.get_or_insert_with(|| {
+                        grammar.add_history_node(RootHistoryNode::NoOp.into())
+                    });
+                    grammar.add_shared(rule, prefix, root, &sharers, &mut helpers);
+                }
+            }
         }
 
         grammar
     }
 
+    /// Splits a rule with at least three symbols into right-branching rules.
+    fn add_right_branching(&mut self, rule: RuleRef) {
+        let len = rule.rhs.len();
+        let helpers: Vec<Symbol> = self.sym_source.generate().take(len - 2).collect();
+        for depth in 0..len - 1 {
+            let lhs = if depth == 0 {
+                rule.lhs
+            } else {
+                helpers[depth - 1]
+            };
+            let right = helpers.get(depth).cloned().unwrap_or(rule.rhs[len - 1]);
+            let history_id = self.add_history_node(
+                HistoryNodeBinarizeRight {
+                    prev: rule.history_id,
+                    depth: depth as u32,
+                }
+                .into(),
+            );
+            self.rules.push(BinarizedRule::new(RuleRef {
+                lhs,
+                rhs: &[rule.rhs[depth], right],
+                history_id,
+            }));
+        }
+    }
+
+    /// Splits a rule with at least three symbols, reusing helpers for shared prefixes or
+    /// suffixes.
+    fn add_shared(
+        &mut self,
+        rule: RuleRef,
+        prefix: bool,
+        root: HistoryId,
+        sharers: &HashMap<&[Symbol], Vec<HistoryId>>,
+        helpers: &mut HashMap<Vec<Symbol>, Symbol>,
+    ) {
+        let len = rule.rhs.len();
+        // The symbol that derives the part of the RHS covered so far.
+        let mut covered = if prefix {
+            rule.rhs[0]
+        } else {
+            rule.rhs[len - 1]
+        };
+        for (part, covered_len) in shared_parts(rule.rhs, prefix).zip(2..) {
+            if let Some(&helper) = helpers.get(part) {
+                covered = helper;
+                continue;
+            }
+            let helper = self.next_sym();
+            let rhs = if prefix {
+                [covered, part[covered_len - 1]]
+            } else {
+                [part[0], covered]
+            };
+            let history_id = self.add_history_node(
+                HistoryNodeBinarizeShared {
+                    prev: root,
+                    len: covered_len as u32,
+                    suffix: !prefix,
+                    rules: sharers[part].clone(),
+                }
+                .into(),
+            );
+            self.rules.push(BinarizedRule::new(RuleRef {
+                lhs: helper,
+                rhs: &rhs[..],
+                history_id,
+            }));
+            helpers.insert(part.to_vec(), helper);
+            covered = helper;
+        }
+        let (rhs, history) = if prefix {
+            let history = HistoryNodeBinarize {
+                prev: rule.history_id,
+                depth: 0,
+            };
+            ([covered, rule.rhs[len - 1]], history.into())
+        } else {
+            let history = HistoryNodeBinarizeRight {
+                prev: rule.history_id,
+                depth: 0,
+            };
+            ([rule.rhs[0], covered], history.into())
+        };
+        let history_id = self.add_history_node(history);
+        self.rules.push(BinarizedRule::new(RuleRef {
+            lhs: rule.lhs,
+            rhs: &rhs[..],
+            history_id,
+        }));
+    }
+
     /// Sorts the rule array.
     pub fn sort(&mut self) {
         self.rules.sort();
@@ -113,6 +264,42 @@ impl BinarizedCfg {
         self.rules.dedup();
     }
 
+    /// Removes history nodes that are unreachable from the histories of rules and nulling
+    /// rules, and renumbers the rest. Returns the new ID of every old history node. See
+    /// `HistoryGraph::compact`.
+    pub fn compact_history(&mut self) -> Vec<Option<HistoryId>> {
+        let roots = self
+            .rules
+            .iter()
+            .map(|rule| rule.history_id)
+            .chain(self.nulling.iter().flatten().cloned());
+        let new_ids = self.history_graph.compact(roots);
+        for rule in &mut self.rules {
+            rule.history_id = new_ids[rule.history_id.get()].unwrap();
+        }
+        for history_id in self.nulling.iter_mut().flatten() {
+            *history_id = new_ids[history_id.get()].unwrap();
+        }
+        new_ids
+    }
+
+    /// Adds a rule that is already binarized, keeping its history ID as is. Unlike `add_rule`,
+    /// this adds no history nodes. A rule with an empty RHS becomes a nulling rule.
+    pub fn add_binarized_rule(&mut self, rule: RuleRef) {
+        if rule.rhs.is_empty() {
+            while self.nulling.len() <= rule.lhs.usize() {
+                self.nulling.push(None);
+            }
+            assert!(
+                self.nulling[rule.lhs.usize()].is_none(),
+                "Duplicate nulling rule"
+            );
+            self.nulling[rule.lhs.usize()] = Some(rule.history_id);
+        } else {
+            self.rules.push(BinarizedRule::new(rule));
+        }
+    }
+
     /// Returns generated symbols.
     pub fn sym<const N: usize>(&mut self) -> [Symbol; N] {
         self.sym_source_mut().sym()
@@ -136,11 +323,16 @@ impl BinarizedCfg {
     /// the empty string. Unproductive rules aren't preserved.
     pub fn eliminate_nulling_rules(&mut self) -> BinarizedCfg {
         let mut nulling_grammar = BinarizedCfg::with_sym_source(self.sym_source.clone());
+        // History IDs of nulling rules refer to this grammar's history graph.
+        nulling_grammar.history_graph = self.history_graph.clone();
 
         if self.nulling.iter().any(|h| h.is_some()) {
-            let mut nulling = mem::replace(&mut self.nulling, vec![]);
+            let mut nulling = std::mem::take(&mut self.nulling);
             let nulling_len = nulling.len();
-            nulling.extend(iter::repeat(None).take(self.sym_source().num_syms() - nulling_len));
+            nulling.extend(iter::repeat_n(
+                None,
+                self.sym_source().num_syms() - nulling_len,
+            ));
             let mut nullable: BitVec = nulling.iter().map(|n| n.is_some()).collect();
             let mut productive: BitVec = BitVec::from_elem(self.sym_source().num_syms(), true);
             // All rules of the form `A ::= ε` go to the nulling grammar.
@@ -150,7 +342,7 @@ impl BinarizedCfg {
             let mut rewritten_rules = Vec::new();
             for rule in self.rules() {
                 let left_nullable = nullable[rule.rhs[0].into()];
-                let right_nullable = rule.rhs.get(1).map_or(true, |s| nullable[s.usize()]);
+                let right_nullable = rule.rhs.get(1).is_none_or(|s| nullable[s.usize()]);
                 if left_nullable && right_nullable {
                     // The rule is copied to the nulling grammar.
                     let prev = rule.history_id;
@@ -211,7 +403,7 @@ impl BinarizedCfg {
                 // that were made unproductive as a result of `A ::= epsilon` rule elimination.
                 // Otherwise, some of our nonterminal symbols might be terminal.
                 let left_productive = productive[rule.rhs0().into()];
-                let right_productive = rule.rhs1().map_or(true, |s| productive[s.into()]);
+                let right_productive = rule.rhs1().is_none_or(|s| productive[s.into()]);
                 left_productive && right_productive
             });
         }
@@ -291,7 +483,7 @@ impl RuleContainer for BinarizedCfg {
                 let lhs = next_lhs;
                 next_lhs = left;
                 BinarizedRule {
-                    lhs: lhs,
+                    lhs,
                     rhs: if let Some(right) = right {
                         Two([left, right])
                     } else {
@@ -331,7 +523,7 @@ impl RuleContainer for BinarizedCfg {
 }
 
 impl AsRuleRef for BinarizedRule {
-    fn as_rule_ref(&self) -> RuleRef {
+    fn as_rule_ref(&self) -> RuleRef<'_> {
         RuleRef {
             lhs: self.lhs,
             rhs: match self.rhs {
@@ -389,7 +581,7 @@ impl PartialEq for BinarizedRule {
 
 impl PartialOrd for BinarizedRule {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        (self.lhs, &self.rhs).partial_cmp(&(other.lhs, &other.rhs))
+        Some(self.cmp(other))
     }
 }
 
@@ -400,3 +592,175 @@ impl Ord for BinarizedRule {
 }
 
 impl Eq for BinarizedRule {}
+
+/// Iterates over the prefixes or suffixes of `rhs` that shared helpers derive, from the
+/// shortest one with two symbols to the longest one without the last or first symbol.
+fn shared_parts(rhs: &[Symbol], prefix: bool) -> impl Iterator<Item = &[Symbol]> {
+    let len = rhs.len();
+    (2..len).map(move |covered_len| {
+        if prefix {
+            &rhs[..covered_len]
+        } else {
+            &rhs[len - covered_len..]
+        }
+    })
+}
+
+#[cfg(feature = "serialize")]
+mod serialize {
+    use miniserde::de::{Deserialize, Map, Visitor};
+    use miniserde::ser::{Fragment, Serialize};
+    use miniserde::{make_place, Error, Result};
+
+    use super::{BinarizedCfg, BinarizedRule, BinarizedRuleRhs::*};
+    use crate::history::HistoryGraph;
+    use crate::local_prelude::*;
+    use crate::rule::AsRuleRef;
+    use crate::serialize::{history_id, is_valid_rule, take, Fields};
+    use crate::RuleContainer;
+
+    make_place!(Place);
+
+    impl Serialize for BinarizedCfg {
+        fn begin(&self) -> Fragment<'_> {
+            let nulling: Vec<Option<usize>> = self
+                .nulling
+                .iter()
+                .map(|history_id| history_id.map(|id| id.get()))
+                .collect();
+            Fields::new()
+                .field("sym_source", &self.sym_source)
+                .field("rules", &self.rules)
+                .field("nulling", nulling)
+                .field("history_graph", &self.history_graph)
+                .into_fragment()
+        }
+    }
+
+    impl Deserialize for BinarizedCfg {
+        fn begin(out: &mut Option<Self>) -> &mut dyn Visitor {
+            Place::new(out)
+        }
+    }
+
+    impl Visitor for Place<BinarizedCfg> {
+        fn map(&mut self) -> Result<Box<dyn Map + '_>> {
+            Ok(Box::new(BinarizedCfgBuilder {
+                sym_source: None,
+                rules: None,
+                nulling: None,
+                history_graph: None,
+                out: &mut self.out,
+            }))
+        }
+    }
+
+    struct BinarizedCfgBuilder<'a> {
+        sym_source: Option<SymbolSource>,
+        rules: Option<Vec<BinarizedRule>>,
+        nulling: Option<Vec<Option<usize>>>,
+        history_graph: Option<HistoryGraph>,
+        out: &'a mut Option<BinarizedCfg>,
+    }
+
+    impl Map for BinarizedCfgBuilder<'_> {
+        fn key(&mut self, k: &str) -> Result<&mut dyn Visitor> {
+            match k {
+                "sym_source" => Ok(Deserialize::begin(&mut self.sym_source)),
+                "rules" => Ok(Deserialize::begin(&mut self.rules)),
+                "nulling" => Ok(Deserialize::begin(&mut self.nulling)),
+                "history_graph" => Ok(Deserialize::begin(&mut self.history_graph)),
+                _ => Ok(<dyn Visitor>::ignore()),
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            let nulling: Vec<_> = take(&mut self.nulling)?
+                .into_iter()
+                .map(|id| id.map(history_id).transpose())
+                .collect::<Result<_>>()?;
+            let grammar = BinarizedCfg {
+                sym_source: take(&mut self.sym_source)?,
+                rules: take(&mut self.rules)?,
+                nulling,
+                history_graph: take(&mut self.history_graph)?,
+            };
+            let num_syms = grammar.num_syms();
+            let num_nodes = grammar.history_graph.len();
+            if !grammar.history_graph.is_valid(num_syms)
+                || !grammar
+                    .rules()
+                    .all(|rule| is_valid_rule(rule, num_syms, num_nodes))
+                || grammar.nulling.len() > num_syms
+                || grammar
+                    .nulling
+                    .iter()
+                    .flatten()
+                    .any(|id| id.get() >= num_nodes)
+            {
+                return Err(Error);
+            }
+            *self.out = Some(grammar);
+            Ok(())
+        }
+    }
+
+    impl Serialize for BinarizedRule {
+        fn begin(&self) -> Fragment<'_> {
+            Fields::new()
+                .field("lhs", self.lhs)
+                .field("rhs", self.as_rule_ref().rhs)
+                .field("history_id", self.history_id.get())
+                .into_fragment()
+        }
+    }
+
+    impl Deserialize for BinarizedRule {
+        fn begin(out: &mut Option<Self>) -> &mut dyn Visitor {
+            Place::new(out)
+        }
+    }
+
+    impl Visitor for Place<BinarizedRule> {
+        fn map(&mut self) -> Result<Box<dyn Map + '_>> {
+            Ok(Box::new(BinarizedRuleBuilder {
+                lhs: None,
+                rhs: None,
+                history_id: None,
+                out: &mut self.out,
+            }))
+        }
+    }
+
+    struct BinarizedRuleBuilder<'a> {
+        lhs: Option<Symbol>,
+        rhs: Option<Vec<Symbol>>,
+        history_id: Option<usize>,
+        out: &'a mut Option<BinarizedRule>,
+    }
+
+    impl Map for BinarizedRuleBuilder<'_> {
+        fn key(&mut self, k: &str) -> Result<&mut dyn Visitor> {
+            match k {
+                "lhs" => Ok(Deserialize::begin(&mut self.lhs)),
+                "rhs" => Ok(Deserialize::begin(&mut self.rhs)),
+                "history_id" => Ok(Deserialize::begin(&mut self.history_id)),
+                _ => Ok(<dyn Visitor>::ignore()),
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            let rhs = match take(&mut self.rhs)?[..] {
+                [rhs0] => One([rhs0]),
+                [rhs0, rhs1] => Two([rhs0, rhs1]),
+                _ => return Err(Error),
+            };
+            *self.out = Some(BinarizedRule {
+                lhs: take(&mut self.lhs)?,
+                rhs,
+                history_id: history_id(take(&mut self.history_id)?)?,
+            });
+            Ok(())
+        }
+    }
+}
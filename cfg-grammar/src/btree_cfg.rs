@@ -1,60 +1,215 @@
+//! A grammar representation that keeps rules ordered by their LHS.
+
 use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
 
-use crate::symbol::SymbolSource;
-use crate::history::HistoryGraph;
-use crate::rule::Rule;
+use crate::history::{HistoryGraph, HistoryId, HistoryNode};
+use crate::rule::cfg_rule::CfgRule;
+use crate::rule::RuleRef;
 use crate::sequence::Sequence;
-use crate::{Cfg, GrammarRule, Symbol};
-
-use super::rule_container::RuleContainer;
+use crate::{local_prelude::*, AsRuleRef};
 
+/// Identifies a rule within a `BTreeCfg`. IDs are assigned in order of insertion and are never
+/// reused.
 pub type RuleId = usize;
 
-/// Basic representation of context-free grammars.
+/// Representation of context-free grammars with rules indexed by their LHS.
+///
+/// Looking up the rules for a given LHS is a range query. Sequence rules are stored alongside
+/// plain rules and can be rewritten later.
 #[derive(Clone)]
 pub struct BTreeCfg {
     /// The symbol source.
     sym_source: SymbolSource,
-    /// The array of rules.
+    /// The map of rules.
     rules: BTreeMap<(Symbol, RuleId), BTreeRule>,
+    /// The ID of the next inserted rule.
+    next_rule_id: RuleId,
     /// History container.
     history_graph: HistoryGraph,
 }
 
+/// A rule stored in a `BTreeCfg`.
+#[derive(Clone, Debug)]
 pub enum BTreeRule {
-    Rule(Rule),
+    /// A plain rule.
+    Rule(CfgRule),
+    /// A sequence rule.
     Sequence(Sequence),
 }
 
-pub struct RuleRef<'a> {
-    lhs: Symbol,
-    rule: &'a BTreeRule,
+impl Default for BTreeCfg {
+    fn default() -> Self {
+        Self::with_sym_source(SymbolSource::new())
+    }
 }
 
-impl<'a> GrammarRule for RuleRef<'a> {
-    fn lhs(&self) -> Symbol {
-        self.lhs
+impl BTreeCfg {
+    /// Creates an empty context-free grammar.
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn rhs(&self) -> &[Symbol] {
-        match self.rule {
-            &BTreeRule::Rule(Rule {  }) => {
-                
-            }
+    /// Creates an empty context-free grammar with the given symbol source.
+    pub fn with_sym_source(sym_source: SymbolSource) -> Self {
+        BTreeCfg {
+            sym_source,
+            rules: BTreeMap::new(),
+            next_rule_id: 0,
+            history_graph: HistoryGraph::new(),
         }
     }
+
+    /// Returns generated symbols.
+    pub fn sym<const N: usize>(&mut self) -> [Symbol; N] {
+        self.sym_source_mut().sym()
+    }
+
+    /// Generates a new unique symbol.
+    pub fn next_sym(&mut self) -> Symbol {
+        self.sym_source_mut().next_sym()
+    }
+
+    /// Returns the number of symbols in use.
+    pub fn num_syms(&self) -> usize {
+        self.sym_source().num_syms()
+    }
+
+    /// Returns the rule with the given LHS and ID.
+    pub fn get(&self, lhs: Symbol, id: RuleId) -> Option<&BTreeRule> {
+        self.rules.get(&(lhs, id))
+    }
+
+    /// Removes the rule with the given LHS and ID.
+    pub fn remove(&mut self, lhs: Symbol, id: RuleId) -> Option<BTreeRule> {
+        self.rules.remove(&(lhs, id))
+    }
+
+    /// Iterates over all plain and sequence rules with the given LHS, in order of insertion.
+    pub fn entries_for_lhs(&self, lhs: Symbol) -> impl Iterator<Item = (RuleId, &BTreeRule)> {
+        self.rules
+            .range(Self::key_range(lhs))
+            .map(|(&(_, id), rule)| (id, rule))
+    }
+
+    /// Inserts a sequence rule.
+    pub fn add_sequence(&mut self, sequence: Sequence) -> RuleId {
+        self.insert(sequence.lhs, BTreeRule::Sequence(sequence))
+    }
+
+    /// Iterates over sequence rules, ordered by their LHS.
+    pub fn sequences(&self) -> impl Iterator<Item = &Sequence> {
+        self.rules.values().filter_map(BTreeRule::as_sequence)
+    }
+
+    /// Iterates over sequence rules with the given LHS.
+    pub fn sequences_for_lhs(&self, lhs: Symbol) -> impl Iterator<Item = &Sequence> {
+        self.rules
+            .range(Self::key_range(lhs))
+            .filter_map(|(_, rule)| rule.as_sequence())
+    }
+
+    /// Retains only the sequence rules specified by the predicate.
+    pub fn retain_sequences<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Sequence) -> bool,
+    {
+        self.rules.retain(|_, rule| match rule {
+            BTreeRule::Rule(_) => true,
+            BTreeRule::Sequence(sequence) => f(sequence),
+        });
+    }
+
+    /// Removes all sequence rules and returns them, for example to rewrite them into plain
+    /// rules with `SequencesToProductions`.
+    pub fn take_sequences(&mut self) -> Vec<Sequence> {
+        let mut sequences = vec![];
+        self.retain_sequences(|sequence| {
+            sequences.push(*sequence);
+            false
+        });
+        sequences
+    }
+
+    /// Removes history nodes that are unreachable from the histories of plain and sequence
+    /// rules, and renumbers the rest. Returns the new ID of every old history node. See
+    /// `HistoryGraph::compact`.
+    pub fn compact_history(&mut self) -> Vec<Option<HistoryId>> {
+        let roots = self.rules.values().filter_map(|rule| match rule {
+            BTreeRule::Rule(rule) => Some(rule.history_id),
+            BTreeRule::Sequence(sequence) => sequence.history_id,
+        });
+        let new_ids = self.history_graph.compact(roots);
+        for rule in self.rules.values_mut() {
+            let history_id = match rule {
+                BTreeRule::Rule(rule) => &mut rule.history_id,
+                BTreeRule::Sequence(Sequence {
+                    history_id: Some(history_id),
+                    ..
+                }) => history_id,
+                BTreeRule::Sequence(_) => continue,
+            };
+            *history_id = new_ids[history_id.get()].unwrap();
+        }
+        new_ids
+    }
+
+    fn insert(&mut self, lhs: Symbol, rule: BTreeRule) -> RuleId {
+        let id = self.next_rule_id;
+        self.next_rule_id += 1;
+        self.rules.insert((lhs, id), rule);
+        id
+    }
+
+    fn key_range(lhs: Symbol) -> RangeInclusive<(Symbol, RuleId)> {
+        (lhs, 0)..=(lhs, RuleId::MAX)
+    }
 }
 
-impl RuleContainer for BTreeCfg {
-    type Rule<'a> = RuleRef<'a>;
-    type Rules<'a> = slice::Iter<'a, Rule>;
+impl BTreeRule {
+    /// Returns the rule's LHS.
+    pub fn lhs(&self) -> Symbol {
+        match self {
+            BTreeRule::Rule(rule) => rule.lhs,
+            BTreeRule::Sequence(sequence) => sequence.lhs,
+        }
+    }
+
+    /// Returns a reference to the plain rule, or `None` for a sequence rule.
+    pub fn as_rule_ref(&self) -> Option<RuleRef<'_>> {
+        match self {
+            BTreeRule::Rule(rule) => Some(rule.as_rule_ref()),
+            BTreeRule::Sequence(_) => None,
+        }
+    }
 
-    fn rules<'a>(&'a self) -> Self::Rules<'a> {
-        self.rules.iter().map(|(&key, val)| {
-            BTreeRuleRef {
+    /// Returns a reference to the sequence rule, or `None` for a plain rule.
+    pub fn as_sequence(&self) -> Option<&Sequence> {
+        match self {
+            BTreeRule::Rule(_) => None,
+            BTreeRule::Sequence(sequence) => Some(sequence),
+        }
+    }
+}
 
-            }
-        })
+/// Only plain rules are visible through this trait. Sequence rules are accessed with
+/// `sequences` and `take_sequences`.
+impl RuleContainer for BTreeCfg {
+    fn rules<'a>(&'a self) -> impl Iterator<Item = RuleRef<'a>>
+    where
+        Self: 'a,
+    {
+        self.rules.values().filter_map(BTreeRule::as_rule_ref)
+    }
+
+    /// Returns plain rules with the given LHS, paired with their `RuleId`s.
+    fn rules_for_lhs<'a>(&'a self, lhs: Symbol) -> impl Iterator<Item = (usize, RuleRef<'a>)>
+    where
+        Self: 'a,
+    {
+        self.rules
+            .range(Self::key_range(lhs))
+            .filter_map(|(&(_, id), rule)| rule.as_rule_ref().map(|rule_ref| (id, rule_ref)))
     }
 
     fn history_graph(&self) -> &HistoryGraph {
@@ -71,21 +226,18 @@ impl RuleContainer for BTreeCfg {
 
     fn retain<F>(&mut self, mut f: F)
     where
-        F: FnMut(Symbol, &[Symbol], 
-        ) -> bool,
+        F: FnMut(RuleRef) -> bool,
     {
         self.rules
-            .retain(|&(lhs, id), rule| f(lhs, rule.rhs(), rule.history_id()));
+            .retain(|_, rule| rule.as_rule_ref().is_none_or(&mut f));
     }
 
-    fn add_rule(&mut self, lhs: Symbol, rhs: &[Symbol], history_id: HistoryId) {
-        let next_id = self.rules.range((lhs, 0) .. (lhs, usize::MAX)).last().map(|(&(_, num), _val)| num + 1).unwrap_or(0);
-        self.rules.insert((lhs, next_id), BTreeRule::Rule(Rule { rhs: rhs.to_vec(), history_id }));
+    fn add_rule(&mut self, rule_ref: RuleRef) {
+        let rule = CfgRule::new(rule_ref.lhs, rule_ref.rhs.to_vec(), rule_ref.history_id);
+        self.insert(rule_ref.lhs, BTreeRule::Rule(rule));
     }
 
     fn add_history_node(&mut self, node: HistoryNode) -> HistoryId {
-        let result = self.history_graph.next_id();
-        self.history_graph.push(node);
-        result
+        self.history_graph.add_history_node(node)
     }
 }
@@ -1,11 +1,23 @@
+use std::mem;
+
+use bit_vec::BitVec;
+use cfg_symbol::intern::Mapping;
+
+use crate::binarized_cfg::BinarizationStrategy;
+use crate::diagnostic::GrammarError;
+use crate::fnv::Fnv1a;
+use crate::history::node::{HistoryNodeEliminateNullingSubset, RootHistoryNode};
 use crate::history::{HistoryGraph, HistoryId, HistoryNode};
+use crate::rhs_closure::RhsClosure;
 use crate::rule::cfg_rule::CfgRule;
 use crate::rule::RuleRef;
+use crate::symbol::remap::Remap;
 use crate::BinarizedCfg;
 use crate::{local_prelude::*, AsRuleRef};
 
 /// Basic representation of context-free grammars.
 #[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(miniserde::Serialize))]
 pub struct Cfg {
     /// The symbol source.
     sym_source: SymbolSource,
@@ -22,6 +34,10 @@ impl Default for Cfg {
 }
 
 impl Cfg {
+    /// The maximal number of variants of a rule that `eliminate_nulling_rules` makes. See
+    /// `try_eliminate_nulling_rules`.
+    pub const DEFAULT_MAX_NULLING_VARIANTS: usize = 1 << 16;
+
     /// Creates an empty context-free grammar.
     pub fn new() -> Self {
         Self::default()
@@ -30,7 +46,7 @@ impl Cfg {
     /// Creates an empty context-free grammar with the given symbol source.
     pub fn with_sym_source(sym_source: SymbolSource) -> Self {
         Cfg {
-            sym_source: sym_source,
+            sym_source,
             rules: vec![],
             history_graph: HistoryGraph::new(),
         }
@@ -53,10 +69,279 @@ impl Cfg {
         self.sym_source().num_syms()
     }
 
+    /// Sorts the rule array in place by LHS, then by RHS. Rules with equal sides keep their
+    /// relative order.
+    pub fn sort(&mut self) {
+        self.rules
+            .sort_by(|a, b| (a.lhs, &a.rhs).cmp(&(b.lhs, &b.rhs)));
+    }
+
+    /// Removes consecutive duplicate rules. Rules are duplicates if they have equal sides,
+    /// regardless of their histories. The first rule of each run is kept.
+    pub fn dedup(&mut self) {
+        self.rules.dedup_by(|b, a| a.lhs == b.lhs && a.rhs == b.rhs);
+    }
+
+    /// Brings this grammar into a canonical form: symbols are renumbered in the order of a
+    /// traversal from `start`, symbols and rules unreachable from `start` are removed, and
+    /// rules are sorted and deduplicated. Returns the mapping from old to new symbols.
+    ///
+    /// Grammars that differ only in the order of rules, duplicate rules, unreachable rules or
+    /// the numbering of symbols usually have equal canonical forms. See
+    /// `Remap::canonicalize_symbols` for the exception.
+    pub fn canonicalize(&mut self, start: Symbol) -> Mapping {
+        let mut remap = Remap::new(self);
+        remap.canonicalize_symbols(start);
+        let mapping = remap.get_mapping();
+        self.sort();
+        self.dedup();
+        mapping
+    }
+
+    /// Returns a hash of the rules and the number of symbols. Histories are ignored, and so
+    /// are the order of rules and duplicate rules.
+    ///
+    /// The hash is stable across runs, platforms and versions of this library. It is meant to
+    /// key caches by grammar content, so call `canonicalize` first for grammars that may
+    /// number their symbols differently.
+    pub fn fingerprint(&self) -> u64 {
+        let mut rules: Vec<(Symbol, &[Symbol])> = self
+            .rules
+            .iter()
+            .map(|rule| (rule.lhs, &rule.rhs[..]))
+            .collect();
+        rules.sort();
+        rules.dedup();
+        let mut hasher = Fnv1a::new();
+        hasher.write(self.num_syms() as u64);
+        hasher.write(rules.len() as u64);
+        for (lhs, rhs) in rules {
+            hasher.write(lhs.usize() as u64);
+            hasher.write(rhs.len() as u64);
+            for &sym in rhs {
+                hasher.write(sym.usize() as u64);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Removes history nodes that are unreachable from the rules' histories and renumbers the
+    /// rest. Returns the new ID of every old history node. See `HistoryGraph::compact`.
+    pub fn compact_history(&mut self) -> Vec<Option<HistoryId>> {
+        let new_ids = self
+            .history_graph
+            .compact(self.rules.iter().map(|rule| rule.history_id));
+        for rule in &mut self.rules {
+            rule.history_id = new_ids[rule.history_id.get()].unwrap();
+        }
+        new_ids
+    }
+
     /// Returns a binarized grammar which is weakly equivalent to this grammar.
     pub fn binarize(&self) -> BinarizedCfg {
         BinarizedCfg::from_context_free(self)
     }
+
+    /// Returns a binarized grammar which is weakly equivalent to this grammar, using the
+    /// given strategy.
+    pub fn binarize_with(&self, strategy: BinarizationStrategy) -> BinarizedCfg {
+        BinarizedCfg::from_context_free_with(self, strategy)
+    }
+
+    /// Copies the rules of another grammar into this grammar. Returns a mapping between the
+    /// symbols of `inner`, seen as internal, and the symbols of this grammar, seen as
+    /// external.
+    ///
+    /// Every pair `(inner_sym, outer_sym)` in `bindings` binds a symbol of `inner` to an
+    /// existing symbol of this grammar. For example, binding the start symbol of an embedded
+    /// language makes its rules define a nonterminal of the host language. Other symbols of
+    /// `inner` get fresh symbols. The history graph of `inner` is appended to this grammar's,
+    /// with history IDs and symbols rewritten. Origins of `inner` are moved past the largest
+    /// origin of this grammar, so that they stay distinct: each one is increased by one more
+    /// than the largest origin found in this grammar before the call.
+    ///
+    /// In the returned mapping, `to_external` holds the symbol of this grammar for every
+    /// symbol of `inner`, and `to_internal` holds the symbol of `inner` for every symbol of
+    /// this grammar, or `None` for symbols that don't come from `inner`. If several symbols
+    /// of `inner` are bound to one symbol, `to_internal` holds the first of them.
+    ///
+    /// Returns `GrammarError::InvalidBinding` if a binding refers to a symbol that doesn't
+    /// exist in its grammar. This grammar is unchanged in that case.
+    pub fn import<G>(
+        &mut self,
+        inner: &G,
+        bindings: &[(Symbol, Symbol)],
+    ) -> Result<Mapping, GrammarError>
+    where
+        G: RuleContainer,
+    {
+        let mut bound = vec![None; inner.num_syms()];
+        for &(inner_sym, outer_sym) in bindings {
+            if inner_sym.usize() >= inner.num_syms() || outer_sym.usize() >= self.num_syms() {
+                return Err(GrammarError::InvalidBinding(inner_sym, outer_sym));
+            }
+            bound[inner_sym.usize()] = Some(outer_sym);
+        }
+        let syms: Vec<Symbol> = bound
+            .into_iter()
+            .map(|outer_sym| outer_sym.unwrap_or_else(|| self.next_sym()))
+            .collect();
+        let sym = |sym: Symbol| syms[sym.usize()];
+
+        let origin_offset = self
+            .history_graph
+            .iter()
+            .filter_map(|node| match *node {
+                HistoryNode::Root(
+                    RootHistoryNode::Origin { origin } | RootHistoryNode::Payload { origin, .. },
+                ) => Some(origin + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        // Node 0 is the graph's root, which no ID points to.
+        let offset = self.history_graph.len() - 1;
+        let id = |id: HistoryId| id.checked_add(offset).unwrap();
+        for node in inner.history_graph().iter().skip(1) {
+            let node = match node {
+                &HistoryNode::Root(RootHistoryNode::Origin { origin }) => RootHistoryNode::Origin {
+                    origin: origin + origin_offset,
+                }
+                .into(),
+                HistoryNode::Root(RootHistoryNode::Payload { origin, payload }) => {
+                    RootHistoryNode::Payload {
+                        origin: origin + origin_offset,
+                        payload: payload.clone(),
+                    }
+                    .into()
+                }
+                node => node.translate(id, sym),
+            };
+            self.history_graph.push(node);
+        }
+        for rule in inner.rules() {
+            self.rules.push(CfgRule::new(
+                sym(rule.lhs),
+                rule.rhs.iter().cloned().map(sym).collect(),
+                id(rule.history_id),
+            ));
+        }
+
+        let mut to_internal = vec![None; self.num_syms()];
+        for (inner_sym, &outer_sym) in syms.iter().enumerate().rev() {
+            to_internal[outer_sym.usize()] = Some(Symbol::from(inner_sym));
+        }
+        Ok(Mapping {
+            to_internal,
+            to_external: syms,
+        })
+    }
+
+    /// Eliminates all rules of the form `A ::= epsilon`. Unlike
+    /// `BinarizedCfg::eliminate_nulling_rules`, rules may have any number of symbols.
+    ///
+    /// Every rule is accompanied by a variant for each nonempty subset of its nullable
+    /// symbols, with these symbols removed. A variant has
+    /// `LinkedHistoryNode::EliminateNullingSubset` on top of the rule's history, which lists
+    /// the nulled positions in ascending order. Variants with no symbols left are omitted.
+    ///
+    /// Note that a rule with `n` nullable symbols has `2^n - 1` variants. A rule whose `n`
+    /// symbols are all nullable has `2^n - 2` variants, so it is replaced by `2^n - 1` rules.
+    ///
+    /// Returns the nulling grammar, which holds every rule whose symbols are all nullable. It
+    /// describes how nullable symbols derive the empty string. The language represented by the
+    /// grammar is preserved, except for the possible lack of the empty string. Unproductive
+    /// rules aren't preserved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a rule has more than `DEFAULT_MAX_NULLING_VARIANTS` variants, which happens
+    /// with 17 or more nullable symbols. See `try_eliminate_nulling_rules`.
+    pub fn eliminate_nulling_rules(&mut self) -> Cfg {
+        match self.try_eliminate_nulling_rules(Self::DEFAULT_MAX_NULLING_VARIANTS) {
+            Ok(nulling_grammar) => nulling_grammar,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Eliminates all rules of the form `A ::= epsilon`, like `eliminate_nulling_rules`.
+    /// Returns `GrammarError::TooManyNullableSymbols` if a rule would have more than
+    /// `max_variants` variants, in which case the grammar is unchanged.
+    pub fn try_eliminate_nulling_rules(
+        &mut self,
+        max_variants: usize,
+    ) -> Result<Cfg, GrammarError> {
+        let num_syms = self.num_syms();
+        let mut nullable = BitVec::from_elem(num_syms, false);
+        for rule in &self.rules {
+            if rule.rhs.is_empty() {
+                nullable.set(rule.lhs.usize(), true);
+            }
+        }
+        RhsClosure::new(self).rhs_closure(&mut nullable);
+        for rule in &self.rules {
+            let num_nullable = rule.rhs.iter().filter(|sym| nullable[sym.usize()]).count();
+            // Subsets of nullable positions are enumerated as bit sets.
+            let num_variants = 1usize.checked_shl(num_nullable as u32).map(|num_subsets| {
+                // Variants with no symbols left are omitted.
+                (num_subsets - 1).saturating_sub((num_nullable == rule.rhs.len()) as usize)
+            });
+            if num_variants.is_none_or(|num| num > max_variants) {
+                return Err(GrammarError::TooManyNullableSymbols(rule.lhs));
+            }
+        }
+
+        let mut nulling_grammar = Cfg::with_sym_source(self.sym_source.clone());
+        let mut productive = BitVec::from_elem(num_syms, true);
+        let rules = mem::take(&mut self.rules);
+        for rule in &rules {
+            productive.set(rule.lhs.usize(), false);
+        }
+        for rule in rules {
+            if rule.rhs.iter().all(|sym| nullable[sym.usize()]) {
+                nulling_grammar.rules.push(rule.clone());
+            }
+            let mut variants = vec![];
+            let positions: Vec<u32> = (0..rule.rhs.len() as u32)
+                .filter(|&pos| nullable[rule.rhs[pos as usize].usize()])
+                .collect();
+            for subset in 1..1usize << positions.len() {
+                let nulled: Vec<u32> = positions
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| subset & (1 << i) != 0)
+                    .map(|(_, &pos)| pos)
+                    .collect();
+                if nulled.len() == rule.rhs.len() {
+                    continue;
+                }
+                let rhs = rule
+                    .rhs
+                    .iter()
+                    .enumerate()
+                    .filter(|&(pos, _)| !nulled.contains(&(pos as u32)))
+                    .map(|(_, &sym)| sym)
+                    .collect();
+                let history = HistoryNodeEliminateNullingSubset {
+                    prev: rule.history_id,
+                    nulled,
+                };
+                let history_id = self.add_history_node(history.into());
+                variants.push(CfgRule::new(rule.lhs, rhs, history_id));
+            }
+            if !rule.rhs.is_empty() {
+                self.rules.push(rule);
+            }
+            self.rules.extend(variants);
+        }
+
+        RhsClosure::new(self).rhs_closure(&mut productive);
+        self.rules
+            .retain(|rule| rule.rhs.iter().all(|sym| productive[sym.usize()]));
+        // History IDs of nulling rules refer to this grammar's history graph.
+        nulling_grammar.history_graph = self.history_graph.clone();
+        Ok(nulling_grammar)
+    }
 }
 
 impl RuleContainer for Cfg {
@@ -100,3 +385,72 @@ impl RuleContainer for Cfg {
         result
     }
 }
+
+#[cfg(feature = "serialize")]
+mod serialize {
+    use miniserde::de::{Deserialize, Map, Visitor};
+    use miniserde::{make_place, Error, Result};
+
+    use super::Cfg;
+    use crate::history::HistoryGraph;
+    use crate::local_prelude::*;
+    use crate::rule::cfg_rule::CfgRule;
+    use crate::serialize::{is_valid_rule, take};
+    use crate::RuleContainer;
+
+    make_place!(Place);
+
+    impl Deserialize for Cfg {
+        fn begin(out: &mut Option<Self>) -> &mut dyn Visitor {
+            Place::new(out)
+        }
+    }
+
+    impl Visitor for Place<Cfg> {
+        fn map(&mut self) -> Result<Box<dyn Map + '_>> {
+            Ok(Box::new(CfgBuilder {
+                sym_source: None,
+                rules: None,
+                history_graph: None,
+                out: &mut self.out,
+            }))
+        }
+    }
+
+    struct CfgBuilder<'a> {
+        sym_source: Option<SymbolSource>,
+        rules: Option<Vec<CfgRule>>,
+        history_graph: Option<HistoryGraph>,
+        out: &'a mut Option<Cfg>,
+    }
+
+    impl Map for CfgBuilder<'_> {
+        fn key(&mut self, k: &str) -> Result<&mut dyn Visitor> {
+            match k {
+                "sym_source" => Ok(Deserialize::begin(&mut self.sym_source)),
+                "rules" => Ok(Deserialize::begin(&mut self.rules)),
+                "history_graph" => Ok(Deserialize::begin(&mut self.history_graph)),
+                _ => Ok(<dyn Visitor>::ignore()),
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            let grammar = Cfg {
+                sym_source: take(&mut self.sym_source)?,
+                rules: take(&mut self.rules)?,
+                history_graph: take(&mut self.history_graph)?,
+            };
+            let num_syms = grammar.num_syms();
+            let num_nodes = grammar.history_graph.len();
+            if !grammar.history_graph.is_valid(num_syms)
+                || !grammar
+                    .rules()
+                    .all(|rule| is_valid_rule(rule, num_syms, num_nodes))
+            {
+                return Err(Error);
+            }
+            *self.out = Some(grammar);
+            Ok(())
+        }
+    }
+}
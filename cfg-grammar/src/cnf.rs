@@ -0,0 +1,146 @@
+//! Conversion to Chomsky Normal Form.
+//!
+//! In CNF, every rule has the form `A ::= B C` or `A ::= a`, where `B` and `C` are
+//! nonterminals and `a` is a terminal. Only the start symbol may derive the empty string, and
+//! it never occurs on the RHS of a rule.
+
+use std::collections::BTreeMap;
+
+use crate::binarized_cfg::BinarizedCfg;
+use crate::history::node::{
+    BinarizedRhsSubset, HistoryNodeEliminateNulling, HistoryNodeLiftTerminal, RootHistoryNode,
+};
+use crate::index::GrammarIndex;
+use crate::local_prelude::*;
+use crate::rule::cfg_rule::CfgRule;
+use crate::rule::{AsRuleRef, RuleRef};
+use crate::unit_rules::eliminate_unit_rules_with;
+
+/// A grammar in Chomsky Normal Form.
+pub struct ChomskyNormalForm {
+    grammar: BinarizedCfg,
+    nulling_grammar: BinarizedCfg,
+    start: Symbol,
+}
+
+impl ChomskyNormalForm {
+    /// Converts a grammar to Chomsky Normal Form.
+    ///
+    /// The grammar is binarized, its start symbol is wrapped in a new start symbol, nulling
+    /// rules are eliminated, unit rules are eliminated, and terminals in rules with two
+    /// symbols on the RHS are lifted into rules of their own. Every step records history,
+    /// so that rules of the result can be mapped back to the original rules.
+    pub fn new<G>(grammar: &G, start: Symbol) -> Self
+    where
+        G: RuleContainer + Default,
+    {
+        let mut grammar = BinarizedCfg::from_context_free(grammar);
+        let new_start = grammar.next_sym();
+        let wrap_history = grammar.add_history_node(RootHistoryNode::NoOp.into());
+        grammar.add_binarized_rule(RuleRef {
+            lhs: new_start,
+            rhs: &[start],
+            history_id: wrap_history,
+        });
+
+        let nulling_grammar = grammar.eliminate_nulling_rules();
+        let index = GrammarIndex::new(&grammar);
+        eliminate_unit_rules_with(&mut grammar, &index, |grammar, rule| {
+            grammar.add_binarized_rule(rule)
+        });
+        // Symbols that had only unit rules are no longer nonterminals.
+        let index = GrammarIndex::new(&grammar);
+        lift_terminals(&mut grammar, &index);
+
+        if nulling_grammar.rules().any(|rule| rule.lhs == new_start) {
+            let history_id = grammar.add_history_node(
+                HistoryNodeEliminateNulling {
+                    prev: wrap_history,
+                    rhs0: start,
+                    rhs1: None,
+                    which: BinarizedRhsSubset::All,
+                }
+                .into(),
+            );
+            grammar.add_binarized_rule(RuleRef {
+                lhs: new_start,
+                rhs: &[],
+                history_id,
+            });
+        }
+
+        ChomskyNormalForm {
+            grammar,
+            nulling_grammar,
+            start: new_start,
+        }
+    }
+
+    /// Returns the grammar in CNF.
+    pub fn grammar(&self) -> &BinarizedCfg {
+        &self.grammar
+    }
+
+    /// Returns the grammar in CNF.
+    pub fn into_grammar(self) -> BinarizedCfg {
+        self.grammar
+    }
+
+    /// Returns the grammar of nulling rules, which describes how nullable symbols derive the
+    /// empty string.
+    pub fn nulling_grammar(&self) -> &BinarizedCfg {
+        &self.nulling_grammar
+    }
+
+    /// Returns the new start symbol.
+    pub fn start(&self) -> Symbol {
+        self.start
+    }
+}
+
+/// Replaces terminals in rules of the form `A ::= a B`, `A ::= B a` and `A ::= a b` with
+/// new nonterminals that derive these terminals. Nonterminals are taken from the index, which
+/// must describe the current rules of the grammar.
+fn lift_terminals(grammar: &mut BinarizedCfg, index: &GrammarIndex) {
+    let needs_lifting = |rule: &RuleRef| {
+        rule.rhs.len() == 2 && !rule.rhs.iter().all(|&sym| index.is_nonterminal(sym))
+    };
+    let lifted_rules: Vec<CfgRule> = grammar
+        .rules()
+        .filter(needs_lifting)
+        .map(|rule| CfgRule::new(rule.lhs, rule.rhs.to_vec(), rule.history_id))
+        .collect();
+    if lifted_rules.is_empty() {
+        return;
+    }
+    grammar.retain(|rule| !needs_lifting(&rule));
+
+    let root = grammar.add_history_node(RootHistoryNode::NoOp.into());
+    let mut lifted = BTreeMap::new();
+    for mut rule in lifted_rules {
+        for sym in rule
+            .rhs
+            .iter_mut()
+            .filter(|sym| !index.is_nonterminal(**sym))
+        {
+            let terminal = *sym;
+            *sym = *lifted.entry(terminal).or_insert_with(|| {
+                let helper = grammar.next_sym();
+                let history_id = grammar.add_history_node(
+                    HistoryNodeLiftTerminal {
+                        prev: root,
+                        terminal,
+                    }
+                    .into(),
+                );
+                grammar.add_binarized_rule(RuleRef {
+                    lhs: helper,
+                    rhs: &[terminal],
+                    history_id,
+                });
+                helper
+            });
+        }
+        grammar.add_binarized_rule(rule.as_rule_ref());
+    }
+}
@@ -0,0 +1,329 @@
+//! Errors and diagnostics reported by grammar APIs.
+//!
+//! Fallible APIs return a `GrammarError`. Analyses that find problems in a grammar without
+//! failing, such as removal of useless rules, return a list of `Diagnostic`s, so that callers
+//! decide how to report them. Lints, which are configurable checks of a grammar's health,
+//! also report diagnostics.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::local_prelude::*;
+
+/// An error in a grammar or in the use of a grammar API.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GrammarError {
+    /// The grammar has no start symbol.
+    MissingStart,
+    /// A rule was added before its LHS was given.
+    MissingLhs,
+    /// A sequence rule was added before its range was given, for example with `inclusive`.
+    MissingRange,
+    /// The FIRST set of a nonterminal is missing.
+    MissingFirstSet(Symbol),
+    /// A rule of the nonterminal has too many nullable symbols. Eliminating nulling rules would
+    /// make more variants of the rule than allowed.
+    TooManyNullableSymbols(Symbol),
+    /// A binding passed to `Cfg::import` refers to a symbol that doesn't exist in its
+    /// grammar. Holds the symbol of the imported grammar and the symbol it is bound to.
+    InvalidBinding(Symbol, Symbol),
+}
+
+/// An error returned by a builder, together with the builder. The builder is unchanged by
+/// the failed call, so it can be used to recover.
+pub struct BuilderError<B> {
+    /// The error.
+    pub error: GrammarError,
+    /// The builder.
+    pub builder: B,
+}
+
+/// The severity of a diagnostic.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// The grammar may not describe what was intended.
+    Warning,
+    /// The grammar can't be used.
+    Error,
+}
+
+/// A problem found in a grammar.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// The severity.
+    pub severity: Severity,
+    /// The kind of problem.
+    pub kind: DiagnosticKind,
+    /// The history of the rule the problem is about, if any. Source locations can be found
+    /// through the history.
+    pub history_id: Option<HistoryId>,
+}
+
+/// The kind of a diagnostic.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DiagnosticKind {
+    /// The rule is unreachable or unproductive, and was removed.
+    UselessRule {
+        /// The LHS of the rule.
+        lhs: Symbol,
+        /// The RHS of the rule.
+        rhs: Vec<Symbol>,
+    },
+    /// The rule takes part in a cycle of unit rules, such as `A ::= B` with `B ::= A`.
+    Cycle {
+        /// The LHS of the rule.
+        lhs: Symbol,
+        /// The RHS of the rule.
+        rhs: Vec<Symbol>,
+    },
+    /// The rule takes part in a conflict in a parse table.
+    Conflict {
+        /// The kind of conflict.
+        conflict: ConflictKind,
+        /// The LHS of the rule.
+        lhs: Symbol,
+        /// The RHS of the rule.
+        rhs: Vec<Symbol>,
+    },
+    /// A lint found a problem with a rule or a symbol.
+    Lint {
+        /// The lint.
+        lint: Lint,
+        /// The symbol the problem is about. For rules, this is the LHS.
+        sym: Symbol,
+    },
+    /// An error.
+    Error(GrammarError),
+}
+
+/// The kind of a conflict in a parse table.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ConflictKind {
+    /// Several rules of an LL(1) parse table are predicted by the terminal.
+    Ll(Symbol),
+    /// In an LR parse table, a rule can be reduced, or the terminal can be shifted.
+    ShiftReduce(Symbol),
+    /// In an LR parse table, several rules can be reduced.
+    ReduceReduce,
+}
+
+/// A check of a grammar's health.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Lint {
+    /// A rule is identical to an earlier rule.
+    DuplicateRule,
+    /// A rule has a trivially ambiguous shape, such as `A ::= A A`, or it is part of a cycle of
+    /// unit rules, such as `A ::= B` with `B ::= A`.
+    AmbiguousShape,
+    /// A nonterminal is used, but has no rules.
+    MissingRules,
+    /// A terminal is declared, but never used.
+    UnusedTerminal,
+    /// A chain of unit rules, such as `A ::= B`, `B ::= C`, is longer than allowed.
+    LongUnitChain,
+    /// A rule is reachable only through cycles of unit rules.
+    ReachableOnlyThroughCycles,
+    /// A nullable nonterminal is recursive.
+    NullableRecursive,
+}
+
+impl Diagnostic {
+    /// Creates a warning about a useless rule.
+    pub fn useless_rule(lhs: Symbol, rhs: &[Symbol], history_id: HistoryId) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            kind: DiagnosticKind::UselessRule {
+                lhs,
+                rhs: rhs.to_vec(),
+            },
+            history_id: Some(history_id),
+        }
+    }
+
+    /// Creates a warning about a rule that takes part in a cycle.
+    pub fn cycle(lhs: Symbol, rhs: &[Symbol], history_id: HistoryId) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            kind: DiagnosticKind::Cycle {
+                lhs,
+                rhs: rhs.to_vec(),
+            },
+            history_id: Some(history_id),
+        }
+    }
+
+    /// Creates an error about a rule that takes part in a conflict in a parse table.
+    pub fn conflict(
+        conflict: ConflictKind,
+        lhs: Symbol,
+        rhs: &[Symbol],
+        history_id: HistoryId,
+    ) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            kind: DiagnosticKind::Conflict {
+                conflict,
+                lhs,
+                rhs: rhs.to_vec(),
+            },
+            history_id: Some(history_id),
+        }
+    }
+}
+
+impl DiagnosticKind {
+    /// Returns the LHS and RHS of the rule the problem is about, if any.
+    pub fn rule(&self) -> Option<(Symbol, &[Symbol])> {
+        match self {
+            DiagnosticKind::UselessRule { lhs, rhs }
+            | DiagnosticKind::Cycle { lhs, rhs }
+            | DiagnosticKind::Conflict { lhs, rhs, .. } => Some((*lhs, &rhs[..])),
+            DiagnosticKind::Lint { .. } | DiagnosticKind::Error(_) => None,
+        }
+    }
+}
+
+impl Lint {
+    /// All lints.
+    pub const ALL: [Lint; 7] = [
+        Lint::DuplicateRule,
+        Lint::AmbiguousShape,
+        Lint::MissingRules,
+        Lint::UnusedTerminal,
+        Lint::LongUnitChain,
+        Lint::ReachableOnlyThroughCycles,
+        Lint::NullableRecursive,
+    ];
+
+    /// Returns the severity of the lint, unless configured otherwise.
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Lint::MissingRules => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl From<GrammarError> for Diagnostic {
+    fn from(error: GrammarError) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            kind: DiagnosticKind::Error(error),
+            history_id: None,
+        }
+    }
+}
+
+impl fmt::Display for GrammarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GrammarError::MissingStart => write!(f, "grammar has no start symbol"),
+            GrammarError::MissingLhs => write!(f, "rule has no LHS"),
+            GrammarError::MissingRange => write!(f, "sequence rule has no range"),
+            GrammarError::MissingFirstSet(sym) => {
+                write!(f, "missing FIRST set for symbol {}", sym.usize())
+            }
+            GrammarError::TooManyNullableSymbols(sym) => {
+                write!(
+                    f,
+                    "too many nullable symbols in a rule for symbol {}",
+                    sym.usize()
+                )
+            }
+            GrammarError::InvalidBinding(inner, outer) => {
+                write!(
+                    f,
+                    "invalid binding of imported symbol {} to symbol {}",
+                    inner.usize(),
+                    outer.usize()
+                )
+            }
+        }
+    }
+}
+
+impl Error for GrammarError {}
+
+impl<B> From<BuilderError<B>> for GrammarError {
+    fn from(error: BuilderError<B>) -> Self {
+        error.error
+    }
+}
+
+impl<B> fmt::Debug for BuilderError<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BuilderError")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B> fmt::Display for BuilderError<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl<B> Error for BuilderError<B> {}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.kind)
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticKind::UselessRule { lhs, .. } => {
+                write!(f, "useless rule for symbol {}", lhs.usize())
+            }
+            DiagnosticKind::Cycle { lhs, .. } => {
+                write!(f, "rule in a cycle for symbol {}", lhs.usize())
+            }
+            DiagnosticKind::Conflict { conflict, lhs, .. } => {
+                write!(f, "{} for symbol {}", conflict, lhs.usize())
+            }
+            DiagnosticKind::Lint { lint, sym } => write!(f, "{} for symbol {}", lint, sym.usize()),
+            DiagnosticKind::Error(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConflictKind::Ll(terminal) => {
+                write!(f, "LL(1) conflict on symbol {}", terminal.usize())
+            }
+            ConflictKind::ShiftReduce(terminal) => {
+                write!(f, "shift/reduce conflict on symbol {}", terminal.usize())
+            }
+            ConflictKind::ReduceReduce => write!(f, "reduce/reduce conflict"),
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Lint::DuplicateRule => "duplicate rule",
+            Lint::AmbiguousShape => "ambiguous rule",
+            Lint::MissingRules => "nonterminal with no rules",
+            Lint::UnusedTerminal => "unused terminal",
+            Lint::LongUnitChain => "long chain of unit rules",
+            Lint::ReachableOnlyThroughCycles => "rule reachable only through cycles",
+            Lint::NullableRecursive => "nullable recursive nonterminal",
+        };
+        f.write_str(description)
+    }
+}
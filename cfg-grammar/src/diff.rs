@@ -0,0 +1,392 @@
+//! Structural comparison of two grammars.
+//!
+//! Symbols of the old grammar are aligned with symbols of the new grammar. Rules are compared
+//! per LHS, and cheap language-level properties are compared per aligned symbol.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use bit_vec::BitVec;
+use cfg_symbol::intern::Mapping;
+
+use crate::first::FirstSets;
+use crate::index::GrammarIndex;
+use crate::local_prelude::*;
+use crate::rhs_closure::RhsClosure;
+
+/// Alignment of symbols of an old grammar with symbols of a new grammar.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolAlignment {
+    to_new: BTreeMap<Symbol, Symbol>,
+}
+
+impl SymbolAlignment {
+    /// Creates an empty alignment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aligns symbols with equal IDs, for the first `num_syms` symbols.
+    pub fn identity(num_syms: usize) -> Self {
+        SymbolAlignment {
+            to_new: (0..num_syms)
+                .map(|i| (Symbol::from(i), Symbol::from(i)))
+                .collect(),
+        }
+    }
+
+    /// Aligns symbols that have equal names.
+    pub fn by_names(
+        mut self,
+        old_names: &BTreeMap<Symbol, String>,
+        new_names: &BTreeMap<Symbol, String>,
+    ) -> Self {
+        let new_syms: HashMap<&str, Symbol> = new_names
+            .iter()
+            .map(|(&sym, name)| (&name[..], sym))
+            .collect();
+        for (&old, name) in old_names {
+            if let Some(&new) = new_syms.get(&name[..]) {
+                self.to_new.insert(old, new);
+            }
+        }
+        self
+    }
+
+    /// Aligns symbols through `mapping.to_internal`, which is indexed by old symbols. Symbols
+    /// that are already aligned, for example by name, are kept.
+    pub fn by_mapping(mut self, mapping: &Mapping) -> Self {
+        for (i, &new) in mapping.to_internal.iter().enumerate() {
+            if let Some(new) = new {
+                self.to_new.entry(Symbol::from(i)).or_insert(new);
+            }
+        }
+        self
+    }
+
+    /// Aligns a pair of symbols.
+    pub fn align(&mut self, old: Symbol, new: Symbol) {
+        self.to_new.insert(old, new);
+    }
+
+    /// Returns the symbol of the new grammar aligned with `old`.
+    pub fn get(&self, old: Symbol) -> Option<Symbol> {
+        self.to_new.get(&old).cloned()
+    }
+}
+
+/// Rules of one LHS that differ between the grammars.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LhsDiff {
+    /// The LHS in the old grammar, if it has rules there.
+    pub old_lhs: Option<Symbol>,
+    /// The LHS in the new grammar, if it has rules there.
+    pub new_lhs: Option<Symbol>,
+    /// Right-hand sides of removed rules, with symbols of the old grammar.
+    pub removed: Vec<Vec<Symbol>>,
+    /// Right-hand sides of added rules, with symbols of the new grammar.
+    pub added: Vec<Vec<Symbol>>,
+}
+
+/// A change of a boolean property of an aligned symbol.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PropertyChange {
+    /// The symbol in the old grammar.
+    pub old_sym: Symbol,
+    /// The symbol in the new grammar.
+    pub new_sym: Symbol,
+    /// Whether the new grammar's symbol has the property.
+    pub now: bool,
+}
+
+/// A change of the FIRST set of an aligned nonterminal.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FirstSetChange {
+    /// The symbol in the old grammar.
+    pub old_sym: Symbol,
+    /// The symbol in the new grammar.
+    pub new_sym: Symbol,
+    /// Terminals of the old grammar that no longer begin the symbol's strings.
+    pub removed: Vec<Symbol>,
+    /// Terminals of the new grammar that now begin the symbol's strings.
+    pub added: Vec<Symbol>,
+}
+
+/// Differences between two grammars.
+#[derive(Clone, Debug, Default)]
+pub struct GrammarDiff {
+    rules: Vec<LhsDiff>,
+    nullable: Vec<PropertyChange>,
+    productive: Vec<PropertyChange>,
+    reachable: Vec<PropertyChange>,
+    first_sets: Vec<FirstSetChange>,
+}
+
+impl GrammarDiff {
+    /// Compares two grammars. Reachability is not compared; see `reachable`.
+    pub fn new<G, H>(old: &G, new: &H, alignment: &SymbolAlignment) -> Self
+    where
+        G: RuleContainer,
+        H: RuleContainer,
+    {
+        let old_analysis = Analysis::new(old);
+        let new_analysis = Analysis::new(new);
+        let pairs = aligned_nonterminals(&old_analysis, &new_analysis, alignment);
+        let property_changes = |old_set: &BitVec, new_set: &BitVec| {
+            pairs
+                .iter()
+                .filter(|&&(old_sym, new_sym)| old_set[old_sym.usize()] != new_set[new_sym.usize()])
+                .map(|&(old_sym, new_sym)| PropertyChange {
+                    old_sym,
+                    new_sym,
+                    now: new_set[new_sym.usize()],
+                })
+                .collect()
+        };
+
+        GrammarDiff {
+            rules: rule_diffs(old, new, alignment),
+            nullable: property_changes(&old_analysis.nullable, &new_analysis.nullable),
+            productive: property_changes(&old_analysis.productive, &new_analysis.productive),
+            reachable: vec![],
+            first_sets: first_set_changes(&old_analysis, &new_analysis, &pairs, alignment),
+        }
+    }
+
+    /// Compares reachability from the given start symbols of each grammar.
+    pub fn reachable<G, H>(
+        mut self,
+        old: &G,
+        old_starts: &[Symbol],
+        new: &H,
+        new_starts: &[Symbol],
+        alignment: &SymbolAlignment,
+    ) -> Self
+    where
+        G: RuleContainer,
+        H: RuleContainer,
+    {
+        let old_reachable = reachable_syms(old, old_starts);
+        let new_reachable = reachable_syms(new, new_starts);
+        self.reachable = (0..old.num_syms())
+            .map(Symbol::from)
+            .filter_map(|old_sym| Some((old_sym, alignment.get(old_sym)?)))
+            .filter(|&(_, new_sym)| new_sym.usize() < new.num_syms())
+            .filter(|&(old_sym, new_sym)| {
+                old_reachable[old_sym.usize()] != new_reachable[new_sym.usize()]
+            })
+            .map(|(old_sym, new_sym)| PropertyChange {
+                old_sym,
+                new_sym,
+                now: new_reachable[new_sym.usize()],
+            })
+            .collect();
+        self
+    }
+
+    /// Returns rule differences per LHS. Every LHS with changes appears once.
+    pub fn rules(&self) -> &[LhsDiff] {
+        &self.rules[..]
+    }
+
+    /// Returns aligned nonterminals that became nullable or stopped being nullable.
+    pub fn nullable(&self) -> &[PropertyChange] {
+        &self.nullable[..]
+    }
+
+    /// Returns aligned nonterminals that became productive or unproductive.
+    pub fn productive(&self) -> &[PropertyChange] {
+        &self.productive[..]
+    }
+
+    /// Returns aligned symbols that became reachable or unreachable.
+    pub fn reachable_changes(&self) -> &[PropertyChange] {
+        &self.reachable[..]
+    }
+
+    /// Returns aligned nonterminals whose FIRST sets changed.
+    pub fn first_sets(&self) -> &[FirstSetChange] {
+        &self.first_sets[..]
+    }
+
+    /// Checks whether the grammars have equal rules and properties.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+            && self.nullable.is_empty()
+            && self.productive.is_empty()
+            && self.reachable.is_empty()
+            && self.first_sets.is_empty()
+    }
+}
+
+/// Properties of one grammar.
+struct Analysis {
+    index: GrammarIndex,
+    first_sets: FirstSets,
+    nullable: BitVec,
+    productive: BitVec,
+}
+
+impl Analysis {
+    fn new<G>(grammar: &G) -> Self
+    where
+        G: RuleContainer,
+    {
+        let index = GrammarIndex::new(grammar);
+        let first_sets = FirstSets::new(grammar, &index);
+        let num_syms = grammar.num_syms();
+        let nullable = (0..num_syms)
+            .map(|i| first_sets.is_nullable(Symbol::from(i)))
+            .collect();
+        let mut productive: BitVec = (0..num_syms)
+            .map(|i| !index.is_nonterminal(Symbol::from(i)))
+            .collect();
+        RhsClosure::new(grammar).rhs_closure(&mut productive);
+        Analysis {
+            index,
+            first_sets,
+            nullable,
+            productive,
+        }
+    }
+}
+
+/// Returns aligned pairs of symbols that are nonterminals in either grammar.
+fn aligned_nonterminals(
+    old: &Analysis,
+    new: &Analysis,
+    alignment: &SymbolAlignment,
+) -> Vec<(Symbol, Symbol)> {
+    (0..old.nullable.len())
+        .map(Symbol::from)
+        .filter_map(|old_sym| Some((old_sym, alignment.get(old_sym)?)))
+        .filter(|&(_, new_sym)| new_sym.usize() < new.nullable.len())
+        .filter(|&(old_sym, new_sym)| {
+            old.index.is_nonterminal(old_sym) || new.index.is_nonterminal(new_sym)
+        })
+        .collect()
+}
+
+fn rule_diffs<G, H>(old: &G, new: &H, alignment: &SymbolAlignment) -> Vec<LhsDiff>
+where
+    G: RuleContainer,
+    H: RuleContainer,
+{
+    let mut old_rules: BTreeMap<Symbol, Vec<Vec<Symbol>>> = BTreeMap::new();
+    for rule in old.rules() {
+        old_rules
+            .entry(rule.lhs)
+            .or_default()
+            .push(rule.rhs.to_vec());
+    }
+    let mut new_rules: BTreeMap<Symbol, Vec<Vec<Symbol>>> = BTreeMap::new();
+    for rule in new.rules() {
+        new_rules
+            .entry(rule.lhs)
+            .or_default()
+            .push(rule.rhs.to_vec());
+    }
+
+    let mut result = vec![];
+    for (old_lhs, old_alternatives) in old_rules {
+        let new_lhs = alignment.get(old_lhs);
+        let mut added = new_lhs
+            .and_then(|new_lhs| new_rules.remove(&new_lhs))
+            .unwrap_or_default();
+        let new_lhs = new_lhs.filter(|_| !added.is_empty());
+        let mut removed = vec![];
+        for rhs in old_alternatives {
+            let translated: Option<Vec<Symbol>> =
+                rhs.iter().map(|&sym| alignment.get(sym)).collect();
+            let position = translated
+                .and_then(|translated| added.iter().position(|new_rhs| *new_rhs == translated));
+            match position {
+                Some(position) => {
+                    added.remove(position);
+                }
+                None => removed.push(rhs),
+            }
+        }
+        if !removed.is_empty() || !added.is_empty() {
+            result.push(LhsDiff {
+                old_lhs: Some(old_lhs),
+                new_lhs,
+                removed,
+                added,
+            });
+        }
+    }
+    for (new_lhs, added) in new_rules {
+        result.push(LhsDiff {
+            old_lhs: None,
+            new_lhs: Some(new_lhs),
+            removed: vec![],
+            added,
+        });
+    }
+    result
+}
+
+fn first_set_changes(
+    old: &Analysis,
+    new: &Analysis,
+    pairs: &[(Symbol, Symbol)],
+    alignment: &SymbolAlignment,
+) -> Vec<FirstSetChange> {
+    let terminals = |set: &BitVec| -> Vec<Symbol> {
+        set.iter()
+            .enumerate()
+            .filter(|&(_, present)| present)
+            .map(|(i, _)| Symbol::from(i))
+            .collect()
+    };
+    let mut result = vec![];
+    for &(old_sym, new_sym) in pairs {
+        let old_first = terminals(old.first_sets.first(old_sym));
+        let new_first: BTreeSet<Symbol> = terminals(new.first_sets.first(new_sym))
+            .into_iter()
+            .collect();
+        let translated: BTreeSet<Symbol> = old_first
+            .iter()
+            .filter_map(|&terminal| alignment.get(terminal))
+            .collect();
+        let removed: Vec<Symbol> = old_first
+            .iter()
+            .cloned()
+            .filter(|&terminal| {
+                alignment
+                    .get(terminal)
+                    .is_none_or(|terminal| !new_first.contains(&terminal))
+            })
+            .collect();
+        let added: Vec<Symbol> = new_first.difference(&translated).cloned().collect();
+        if !removed.is_empty() || !added.is_empty() {
+            result.push(FirstSetChange {
+                old_sym,
+                new_sym,
+                removed,
+                added,
+            });
+        }
+    }
+    result
+}
+
+fn reachable_syms<G>(grammar: &G, starts: &[Symbol]) -> BitVec
+where
+    G: RuleContainer,
+{
+    let index = GrammarIndex::new(grammar);
+    let rules: Vec<_> = grammar.rules().collect();
+    let mut reachable = BitVec::from_elem(grammar.num_syms(), false);
+    let mut stack = starts.to_vec();
+    while let Some(sym) = stack.pop() {
+        if reachable[sym.usize()] {
+            continue;
+        }
+        reachable.set(sym.usize(), true);
+        for &rule_idx in index.rules_for_lhs(sym) {
+            stack.extend(rules[rule_idx].rhs.iter().cloned());
+        }
+    }
+    reachable
+}
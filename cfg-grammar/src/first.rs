@@ -0,0 +1,74 @@
+//! Nullable symbols and FIRST sets, for transformations and reports that need them without
+//! depending on `cfg-predict`.
+
+use bit_vec::BitVec;
+
+use crate::index::GrammarIndex;
+use crate::local_prelude::*;
+use crate::rhs_closure::RhsClosure;
+
+/// Nullable symbols and FIRST sets of a grammar. The FIRST set of a terminal is the terminal
+/// itself.
+pub(crate) struct FirstSets {
+    nullable: BitVec,
+    first: Vec<BitVec>,
+}
+
+impl FirstSets {
+    pub(crate) fn new<G>(grammar: &G, index: &GrammarIndex) -> Self
+    where
+        G: RuleContainer,
+    {
+        let num_syms = grammar.num_syms();
+        let mut nullable = BitVec::from_elem(num_syms, false);
+        for rule in grammar.rules() {
+            if rule.rhs.is_empty() {
+                nullable.set(rule.lhs.usize(), true);
+            }
+        }
+        RhsClosure::new(grammar).rhs_closure(&mut nullable);
+
+        let mut result = FirstSets {
+            nullable,
+            first: vec![BitVec::from_elem(num_syms, false); num_syms],
+        };
+        for (sym, set) in result.first.iter_mut().enumerate() {
+            if !index.is_nonterminal(Symbol::from(sym)) {
+                set.set(sym, true);
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in grammar.rules() {
+                let set = result.first_of_rhs(rule.rhs);
+                changed |= result.first[rule.lhs.usize()].or(&set);
+            }
+        }
+        result
+    }
+
+    pub(crate) fn is_nullable(&self, sym: Symbol) -> bool {
+        self.nullable[sym.usize()]
+    }
+
+    pub(crate) fn is_rhs_nullable(&self, rhs: &[Symbol]) -> bool {
+        rhs.iter().all(|&sym| self.is_nullable(sym))
+    }
+
+    pub(crate) fn first(&self, sym: Symbol) -> &BitVec {
+        &self.first[sym.usize()]
+    }
+
+    /// Returns the set of terminals that may begin a string derived from `rhs`.
+    pub(crate) fn first_of_rhs(&self, rhs: &[Symbol]) -> BitVec {
+        let mut set = BitVec::from_elem(self.first.len(), false);
+        for &sym in rhs {
+            set.or(&self.first[sym.usize()]);
+            if !self.is_nullable(sym) {
+                break;
+            }
+        }
+        set
+    }
+}
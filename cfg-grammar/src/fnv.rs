@@ -0,0 +1,40 @@
+//! The 64-bit FNV-1a hash, used where hashes must not change between runs and platforms.
+
+/// The 64-bit FNV-1a hash. Unlike `DefaultHasher`, its output is fixed.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv1a {
+    state: u64,
+}
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fnv1a {
+    /// Creates a hasher with the FNV offset basis.
+    pub fn new() -> Self {
+        Fnv1a {
+            state: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+
+    /// Hashes bytes.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= byte as u64;
+            self.state = self.state.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Hashes a number as little-endian bytes.
+    pub fn write(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    /// Returns the hash of everything written so far.
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
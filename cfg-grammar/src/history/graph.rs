@@ -1,10 +1,19 @@
-use super::node::{HistoryId, HistoryNode, RootHistoryNode};
+use super::node::{HistoryId, HistoryNode, LinkedHistoryNode, RootHistoryNode};
+use super::payload::PayloadValue;
+use super::provenance::Provenance;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(miniserde::Serialize))]
 pub struct HistoryGraph {
     nodes: Vec<HistoryNode>,
 }
 
+impl Default for HistoryGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HistoryGraph {
     pub fn new() -> Self {
         Self {
@@ -24,6 +33,93 @@ impl HistoryGraph {
         self.push(node);
         result
     }
+
+    /// Checks that node 0 is a root and that every node is valid, as described in
+    /// `HistoryNode::is_valid`.
+    pub fn is_valid(&self, num_syms: usize) -> bool {
+        matches!(self.nodes.first(), Some(HistoryNode::Root(_)))
+            && self
+                .nodes
+                .iter()
+                .enumerate()
+                .all(|(id, node)| node.is_valid(id, num_syms))
+    }
+
+    /// Returns the value of the nearest `LinkedHistoryNode::Payload` of type `T` found by
+    /// following `prev` links from `id`, or of the `RootHistoryNode::Payload` at the end.
+    /// Returns `None` if there is no such payload on the way. Payloads of other types are
+    /// skipped.
+    ///
+    /// Links to other rules' histories, such as `inner` of `LinkedHistoryNode::Inline`, are
+    /// not followed.
+    pub fn nearest_payload<T: PayloadValue>(&self, mut id: HistoryId) -> Option<&T> {
+        loop {
+            let (payload, prev) = match &self.nodes[id.get()] {
+                HistoryNode::Linked {
+                    prev,
+                    node: LinkedHistoryNode::Payload { payload },
+                } => (Some(payload), Some(*prev)),
+                &HistoryNode::Linked { prev, .. } => (None, Some(prev)),
+                HistoryNode::Root(RootHistoryNode::Payload { payload, .. }) => {
+                    (Some(payload), None)
+                }
+                HistoryNode::Root(_) => (None, None),
+            };
+            if let Some(value) = payload.and_then(|payload| payload.get::<T>()) {
+                return Some(value);
+            }
+            id = prev?;
+        }
+    }
+
+    /// Returns the provenance of the rule with the given history. See `Provenance`.
+    pub fn provenance(&self, id: HistoryId) -> Provenance {
+        Provenance::new(self, id)
+    }
+
+    /// Keeps only the nodes reachable from `roots` through `prev` links and other history IDs
+    /// held by nodes, such as `inner` of `LinkedHistoryNode::Inline`. Kept nodes retain their
+    /// relative order and are renumbered, with their links rewritten.
+    ///
+    /// Returns the new ID of every old node, indexed by old ID, or `None` for removed nodes.
+    /// Index 0 is always `None`.
+    pub fn compact<I>(&mut self, roots: I) -> Vec<Option<HistoryId>>
+    where
+        I: IntoIterator<Item = HistoryId>,
+    {
+        let mut reachable = vec![false; self.nodes.len()];
+        let mut stack: Vec<HistoryId> = roots.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            if !reachable[id.get()] {
+                reachable[id.get()] = true;
+                self.nodes[id.get()].translate(
+                    |linked| {
+                        stack.push(linked);
+                        linked
+                    },
+                    |sym| sym,
+                );
+            }
+        }
+
+        let mut new_ids = vec![None; self.nodes.len()];
+        let mut next = 1;
+        for (old, new_id) in new_ids.iter_mut().enumerate().skip(1) {
+            if reachable[old] {
+                *new_id = HistoryId::new(next);
+                next += 1;
+            }
+        }
+        let nodes = std::mem::take(&mut self.nodes);
+        self.nodes = vec![RootHistoryNode::NoOp.into()];
+        for (old, node) in nodes.iter().enumerate().skip(1) {
+            if reachable[old] {
+                let node = node.translate(|id| new_ids[id.get()].unwrap(), |sym| sym);
+                self.nodes.push(node);
+            }
+        }
+        new_ids
+    }
 }
 
 impl ::std::ops::Deref for HistoryGraph {
@@ -39,3 +135,56 @@ impl ::std::ops::DerefMut for HistoryGraph {
         &mut self.nodes
     }
 }
+
+#[cfg(feature = "serialize")]
+mod serialize {
+    use miniserde::de::{Deserialize, Map, Visitor};
+    use miniserde::{make_place, Error, Result};
+
+    use super::HistoryGraph;
+    use crate::history::HistoryNode;
+    use crate::serialize::take;
+
+    make_place!(Place);
+
+    impl Deserialize for HistoryGraph {
+        fn begin(out: &mut Option<Self>) -> &mut dyn Visitor {
+            Place::new(out)
+        }
+    }
+
+    impl Visitor for Place<HistoryGraph> {
+        fn map(&mut self) -> Result<Box<dyn Map + '_>> {
+            Ok(Box::new(HistoryGraphBuilder {
+                nodes: None,
+                out: &mut self.out,
+            }))
+        }
+    }
+
+    struct HistoryGraphBuilder<'a> {
+        nodes: Option<Vec<HistoryNode>>,
+        out: &'a mut Option<HistoryGraph>,
+    }
+
+    impl Map for HistoryGraphBuilder<'_> {
+        fn key(&mut self, k: &str) -> Result<&mut dyn Visitor> {
+            match k {
+                "nodes" => Ok(Deserialize::begin(&mut self.nodes)),
+                _ => Ok(<dyn Visitor>::ignore()),
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            let graph = HistoryGraph {
+                nodes: take(&mut self.nodes)?,
+            };
+            // Symbols are checked by the grammar, which knows their number.
+            if !graph.is_valid(usize::MAX) {
+                return Err(Error);
+            }
+            *self.out = Some(graph);
+            Ok(())
+        }
+    }
+}
@@ -1,5 +1,9 @@
 pub mod graph;
 pub mod node;
+pub mod payload;
+pub mod provenance;
 
 pub use self::graph::HistoryGraph;
 pub use self::node::{HistoryId, HistoryNode};
+pub use self::payload::{Payload, PayloadValue};
+pub use self::provenance::Provenance;
@@ -1,13 +1,15 @@
 //! Any data carried alongside a grammar rule can be its _history_. Rule histories may contain
 //! more than semantic actions.
 
+use std::cell::Cell;
 use std::num::NonZeroUsize;
 
+use super::payload::Payload;
 use crate::Symbol;
 
 pub type HistoryId = NonZeroUsize;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum HistoryNode {
     Linked {
         prev: HistoryId,
@@ -16,7 +18,7 @@ pub enum HistoryNode {
     Root(RootHistoryNode),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum LinkedHistoryNode {
     Rhs {
         rhs: Vec<Symbol>,
@@ -43,13 +45,78 @@ pub enum LinkedHistoryNode {
     Distances {
         events: Vec<u32>,
     },
+    EliminateUnitRules {
+        chain: Vec<HistoryId>,
+    },
+    LiftTerminal {
+        terminal: Symbol,
+    },
+    SubstituteLeftCorner {
+        inner: HistoryId,
+        len: u32,
+    },
+    EliminateLeftRecursion {
+        part: LeftRecursionPart,
+    },
+    FactorPrefix {
+        len: u32,
+    },
+    FactorSuffix {
+        prefix_len: u32,
+    },
+    EliminateNullingSubset {
+        nulled: Vec<u32>,
+    },
+    BinarizeRight {
+        depth: u32,
+    },
+    /// A helper rule shared by several rules. It derives `len` symbols at the start of the RHS
+    /// of every rule in `rules`, or at the end if `suffix` is true. `rules` holds the histories
+    /// of these rules, in order of their binarization.
+    BinarizeShared {
+        len: u32,
+        suffix: bool,
+        rules: Vec<HistoryId>,
+    },
+    RenameSymbol {
+        from: Symbol,
+        to: Symbol,
+    },
+    Inline {
+        inner: HistoryId,
+        pos: u32,
+        len: u32,
+    },
+    Extract {
+        pos: u32,
+        len: u32,
+    },
+    Extracted {
+        len: u32,
+    },
+    /// User-defined data, such as a semantic action ID, a source span or documentation. Like
+    /// `Weight`, it is kept on top of the history of every rule that a transformation derives
+    /// from the rule that carries it. See `HistoryGraph::nearest_payload`.
+    Payload {
+        payload: Payload,
+    },
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Debug)]
 pub enum RootHistoryNode {
     NoOp,
-    Rule { lhs: Symbol },
-    Origin { origin: usize },
+    Rule {
+        lhs: Symbol,
+    },
+    Origin {
+        origin: usize,
+    },
+    /// Like `Origin`, with user-defined data about the original rule, such as its source
+    /// span. See `HistoryGraph::nearest_payload`.
+    Payload {
+        origin: usize,
+        payload: Payload,
+    },
 }
 
 impl From<RootHistoryNode> for HistoryNode {
@@ -58,6 +125,111 @@ impl From<RootHistoryNode> for HistoryNode {
     }
 }
 
+impl HistoryNode {
+    /// Checks that this node, placed at `id` in a history graph, refers only to earlier nodes
+    /// and to symbols less than `num_syms`, as nodes added to a graph in order do. A
+    /// `LinkedHistoryNode::BinarizeShared` must list at least one rule. Loaded graphs are
+    /// checked with this method.
+    pub fn is_valid(&self, id: usize, num_syms: usize) -> bool {
+        if let HistoryNode::Linked {
+            node: LinkedHistoryNode::BinarizeShared { rules, .. },
+            ..
+        } = self
+        {
+            if rules.is_empty() {
+                return false;
+            }
+        }
+        let valid = Cell::new(true);
+        self.translate(
+            |linked| {
+                valid.set(valid.get() && linked.get() < id);
+                linked
+            },
+            |sym| {
+                valid.set(valid.get() && sym.usize() < num_syms);
+                sym
+            },
+        );
+        valid.get()
+    }
+
+    /// Returns a copy of this node, with history IDs and symbols translated by the given
+    /// functions.
+    pub fn translate<F, G>(&self, mut id: F, mut sym: G) -> HistoryNode
+    where
+        F: FnMut(HistoryId) -> HistoryId,
+        G: FnMut(Symbol) -> Symbol,
+    {
+        match self {
+            HistoryNode::Root(RootHistoryNode::Rule { lhs }) => {
+                RootHistoryNode::Rule { lhs: sym(*lhs) }.into()
+            }
+            HistoryNode::Root(root) => root.clone().into(),
+            HistoryNode::Linked { prev, node } => {
+                let node = match node.clone() {
+                    LinkedHistoryNode::Rhs { rhs } => LinkedHistoryNode::Rhs {
+                        rhs: rhs.into_iter().map(&mut sym).collect(),
+                    },
+                    LinkedHistoryNode::EliminateNulling { rhs0, rhs1, which } => {
+                        LinkedHistoryNode::EliminateNulling {
+                            rhs0: sym(rhs0),
+                            rhs1: rhs1.map(&mut sym),
+                            which,
+                        }
+                    }
+                    LinkedHistoryNode::RewriteSequence { top, rhs, sep } => {
+                        LinkedHistoryNode::RewriteSequence {
+                            top,
+                            rhs: sym(rhs),
+                            sep: sep.map(&mut sym),
+                        }
+                    }
+                    LinkedHistoryNode::EliminateUnitRules { chain } => {
+                        LinkedHistoryNode::EliminateUnitRules {
+                            chain: chain.into_iter().map(&mut id).collect(),
+                        }
+                    }
+                    LinkedHistoryNode::LiftTerminal { terminal } => {
+                        LinkedHistoryNode::LiftTerminal {
+                            terminal: sym(terminal),
+                        }
+                    }
+                    LinkedHistoryNode::SubstituteLeftCorner { inner, len } => {
+                        LinkedHistoryNode::SubstituteLeftCorner {
+                            inner: id(inner),
+                            len,
+                        }
+                    }
+                    LinkedHistoryNode::RenameSymbol { from, to } => {
+                        LinkedHistoryNode::RenameSymbol {
+                            from: sym(from),
+                            to: sym(to),
+                        }
+                    }
+                    LinkedHistoryNode::BinarizeShared { len, suffix, rules } => {
+                        LinkedHistoryNode::BinarizeShared {
+                            len,
+                            suffix,
+                            rules: rules.into_iter().map(&mut id).collect(),
+                        }
+                    }
+                    LinkedHistoryNode::Inline { inner, pos, len } => LinkedHistoryNode::Inline {
+                        inner: id(inner),
+                        pos,
+                        len,
+                    },
+                    other => other,
+                };
+                HistoryNode::Linked {
+                    prev: id(*prev),
+                    node,
+                }
+            }
+        }
+    }
+}
+
 pub struct HistoryNodeRhs {
     pub prev: HistoryId,
     pub rhs: Vec<Symbol>,
@@ -97,6 +269,95 @@ pub struct HistoryNodeRewriteSequence {
     pub sep: Option<Symbol>,
 }
 
+pub struct HistoryNodeEliminateUnitRules {
+    pub prev: HistoryId,
+    pub chain: Vec<HistoryId>,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeLiftTerminal {
+    pub prev: HistoryId,
+    pub terminal: Symbol,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeSubstituteLeftCorner {
+    pub prev: HistoryId,
+    pub inner: HistoryId,
+    pub len: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeEliminateLeftRecursion {
+    pub prev: HistoryId,
+    pub part: LeftRecursionPart,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeFactorPrefix {
+    pub prev: HistoryId,
+    pub len: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeFactorSuffix {
+    pub prev: HistoryId,
+    pub prefix_len: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeBinarizeRight {
+    pub prev: HistoryId,
+    pub depth: u32,
+}
+
+#[derive(Clone)]
+pub struct HistoryNodeBinarizeShared {
+    pub prev: HistoryId,
+    pub len: u32,
+    pub suffix: bool,
+    pub rules: Vec<HistoryId>,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeRenameSymbol {
+    pub prev: HistoryId,
+    pub from: Symbol,
+    pub to: Symbol,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeInline {
+    pub prev: HistoryId,
+    pub inner: HistoryId,
+    pub pos: u32,
+    pub len: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeExtract {
+    pub prev: HistoryId,
+    pub pos: u32,
+    pub len: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct HistoryNodeExtracted {
+    pub prev: HistoryId,
+    pub len: u32,
+}
+
+#[derive(Clone)]
+pub struct HistoryNodePayload {
+    pub prev: HistoryId,
+    pub payload: Payload,
+}
+
+pub struct HistoryNodeEliminateNullingSubset {
+    pub prev: HistoryId,
+    pub nulled: Vec<u32>,
+}
+
 impl From<HistoryNodeRhs> for HistoryNode {
     fn from(value: HistoryNodeRhs) -> Self {
         HistoryNode::Linked {
@@ -163,8 +424,163 @@ impl From<HistoryNodeRewriteSequence> for HistoryNode {
     }
 }
 
+impl From<HistoryNodeEliminateUnitRules> for HistoryNode {
+    fn from(value: HistoryNodeEliminateUnitRules) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::EliminateUnitRules { chain: value.chain },
+        }
+    }
+}
+
+impl From<HistoryNodeLiftTerminal> for HistoryNode {
+    fn from(value: HistoryNodeLiftTerminal) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::LiftTerminal {
+                terminal: value.terminal,
+            },
+        }
+    }
+}
+
+impl From<HistoryNodeSubstituteLeftCorner> for HistoryNode {
+    fn from(value: HistoryNodeSubstituteLeftCorner) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::SubstituteLeftCorner {
+                inner: value.inner,
+                len: value.len,
+            },
+        }
+    }
+}
+
+impl From<HistoryNodeEliminateLeftRecursion> for HistoryNode {
+    fn from(value: HistoryNodeEliminateLeftRecursion) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::EliminateLeftRecursion { part: value.part },
+        }
+    }
+}
+
+impl From<HistoryNodeFactorPrefix> for HistoryNode {
+    fn from(value: HistoryNodeFactorPrefix) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::FactorPrefix { len: value.len },
+        }
+    }
+}
+
+impl From<HistoryNodeFactorSuffix> for HistoryNode {
+    fn from(value: HistoryNodeFactorSuffix) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::FactorSuffix {
+                prefix_len: value.prefix_len,
+            },
+        }
+    }
+}
+
+impl From<HistoryNodeEliminateNullingSubset> for HistoryNode {
+    fn from(value: HistoryNodeEliminateNullingSubset) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::EliminateNullingSubset {
+                nulled: value.nulled,
+            },
+        }
+    }
+}
+
+impl From<HistoryNodeBinarizeRight> for HistoryNode {
+    fn from(value: HistoryNodeBinarizeRight) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::BinarizeRight { depth: value.depth },
+        }
+    }
+}
+
+impl From<HistoryNodeBinarizeShared> for HistoryNode {
+    fn from(value: HistoryNodeBinarizeShared) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::BinarizeShared {
+                len: value.len,
+                suffix: value.suffix,
+                rules: value.rules,
+            },
+        }
+    }
+}
+
+impl From<HistoryNodeRenameSymbol> for HistoryNode {
+    fn from(value: HistoryNodeRenameSymbol) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::RenameSymbol {
+                from: value.from,
+                to: value.to,
+            },
+        }
+    }
+}
+
+impl From<HistoryNodeInline> for HistoryNode {
+    fn from(value: HistoryNodeInline) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::Inline {
+                inner: value.inner,
+                pos: value.pos,
+                len: value.len,
+            },
+        }
+    }
+}
+
+impl From<HistoryNodeExtract> for HistoryNode {
+    fn from(value: HistoryNodeExtract) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::Extract {
+                pos: value.pos,
+                len: value.len,
+            },
+        }
+    }
+}
+
+impl From<HistoryNodeExtracted> for HistoryNode {
+    fn from(value: HistoryNodeExtracted) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::Extracted { len: value.len },
+        }
+    }
+}
+
+impl From<HistoryNodePayload> for HistoryNode {
+    fn from(value: HistoryNodePayload) -> Self {
+        HistoryNode::Linked {
+            prev: value.prev,
+            node: LinkedHistoryNode::Payload {
+                payload: value.payload,
+            },
+        }
+    }
+}
+
 /// Used to inform which symbols on a rule'Symbol RHS are nullable, and will be eliminated.
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(miniserde::Serialize, miniserde::Deserialize)
+)]
 pub enum BinarizedRhsSubset {
     /// The first of two symbols.
     Left,
@@ -173,3 +589,604 @@ pub enum BinarizedRhsSubset {
     /// All 1 or 2 symbols. The rule is nullable.
     All,
 }
+
+/// Informs which rule of a rewritten left-recursive nonterminal `A` a history node belongs to.
+/// Rules `A ::= A alpha` and `A ::= beta` are rewritten into `A ::= beta A'`,
+/// `A' ::= alpha A'` and `A' ::= ε`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(miniserde::Serialize, miniserde::Deserialize)
+)]
+pub enum LeftRecursionPart {
+    /// The rule `A ::= beta A'`, which comes from `A ::= beta`.
+    Base,
+    /// The rule `A' ::= alpha A'`, which comes from `A ::= A alpha`.
+    Tail,
+    /// The rule `A' ::= ε`.
+    End,
+}
+
+#[cfg(feature = "serialize")]
+mod serialize {
+    use miniserde::de::{Map, Visitor};
+    use miniserde::ser::{Fragment, Serialize};
+    use miniserde::{make_place, Deserialize, Error, Result};
+
+    use super::{
+        BinarizedRhsSubset, HistoryNode, LeftRecursionPart, LinkedHistoryNode, RootHistoryNode,
+    };
+    use crate::serialize::{history_id, Fields};
+    use crate::Symbol;
+
+    make_place!(Place);
+
+    impl Serialize for HistoryNode {
+        fn begin(&self) -> Fragment<'_> {
+            match self {
+                HistoryNode::Linked { prev, node } => {
+                    Fields::new().field("prev", prev.get()).field("node", node)
+                }
+                HistoryNode::Root(root) => Fields::new().field("root", root),
+            }
+            .into_fragment()
+        }
+    }
+
+    impl Serialize for LinkedHistoryNode {
+        fn begin(&self) -> Fragment<'_> {
+            let (variant, fields) = match self {
+                LinkedHistoryNode::Rhs { rhs } => ("Rhs", Fields::new().field("rhs", rhs)),
+                LinkedHistoryNode::Binarize { depth } => {
+                    ("Binarize", Fields::new().field("depth", depth))
+                }
+                LinkedHistoryNode::EliminateNulling { rhs0, rhs1, which } => (
+                    "EliminateNulling",
+                    Fields::new()
+                        .field("rhs0", rhs0)
+                        .field("rhs1", rhs1)
+                        .field("which", which),
+                ),
+                LinkedHistoryNode::AssignPrecedence { looseness } => (
+                    "AssignPrecedence",
+                    Fields::new().field("looseness", looseness),
+                ),
+                LinkedHistoryNode::RewriteSequence { top, rhs, sep } => (
+                    "RewriteSequence",
+                    Fields::new()
+                        .field("top", top)
+                        .field("rhs", rhs)
+                        .field("sep", sep),
+                ),
+                LinkedHistoryNode::Weight { weight } => {
+                    ("Weight", Fields::new().field("weight", weight))
+                }
+                LinkedHistoryNode::Distances { events } => {
+                    ("Distances", Fields::new().field("events", events))
+                }
+                LinkedHistoryNode::EliminateUnitRules { chain } => {
+                    let chain: Vec<usize> = chain.iter().map(|id| id.get()).collect();
+                    ("EliminateUnitRules", Fields::new().field("chain", chain))
+                }
+                LinkedHistoryNode::LiftTerminal { terminal } => {
+                    ("LiftTerminal", Fields::new().field("terminal", terminal))
+                }
+                LinkedHistoryNode::SubstituteLeftCorner { inner, len } => (
+                    "SubstituteLeftCorner",
+                    Fields::new().field("inner", inner.get()).field("len", len),
+                ),
+                LinkedHistoryNode::EliminateLeftRecursion { part } => {
+                    ("EliminateLeftRecursion", Fields::new().field("part", part))
+                }
+                LinkedHistoryNode::FactorPrefix { len } => {
+                    ("FactorPrefix", Fields::new().field("len", len))
+                }
+                LinkedHistoryNode::FactorSuffix { prefix_len } => (
+                    "FactorSuffix",
+                    Fields::new().field("prefix_len", prefix_len),
+                ),
+                LinkedHistoryNode::EliminateNullingSubset { nulled } => (
+                    "EliminateNullingSubset",
+                    Fields::new().field("nulled", nulled),
+                ),
+                LinkedHistoryNode::BinarizeRight { depth } => {
+                    ("BinarizeRight", Fields::new().field("depth", depth))
+                }
+                LinkedHistoryNode::BinarizeShared { len, suffix, rules } => {
+                    let rules: Vec<usize> = rules.iter().map(|id| id.get()).collect();
+                    (
+                        "BinarizeShared",
+                        Fields::new()
+                            .field("len", len)
+                            .field("suffix", suffix)
+                            .field("rules", rules),
+                    )
+                }
+                LinkedHistoryNode::RenameSymbol { from, to } => (
+                    "RenameSymbol",
+                    Fields::new().field("from", from).field("to", to),
+                ),
+                LinkedHistoryNode::Inline { inner, pos, len } => (
+                    "Inline",
+                    Fields::new()
+                        .field("inner", inner.get())
+                        .field("pos", pos)
+                        .field("len", len),
+                ),
+                LinkedHistoryNode::Extract { pos, len } => {
+                    ("Extract", Fields::new().field("pos", pos).field("len", len))
+                }
+                LinkedHistoryNode::Extracted { len } => {
+                    ("Extracted", Fields::new().field("len", len))
+                }
+                LinkedHistoryNode::Payload { payload } => (
+                    "Payload",
+                    Fields::new()
+                        .field("key", payload.key())
+                        .field("value", payload.encode()),
+                ),
+            };
+            Fields::new().field(variant, fields).into_fragment()
+        }
+    }
+
+    impl Serialize for RootHistoryNode {
+        fn begin(&self) -> Fragment<'_> {
+            let (variant, fields) = match self {
+                RootHistoryNode::NoOp => ("NoOp", Fields::new()),
+                RootHistoryNode::Rule { lhs } => ("Rule", Fields::new().field("lhs", lhs)),
+                RootHistoryNode::Origin { origin } => {
+                    ("Origin", Fields::new().field("origin", origin))
+                }
+                RootHistoryNode::Payload { origin, payload } => (
+                    "Payload",
+                    Fields::new()
+                        .field("origin", origin)
+                        .field("key", payload.key())
+                        .field("value", payload.encode()),
+                ),
+            };
+            Fields::new().field(variant, fields).into_fragment()
+        }
+    }
+
+    impl Deserialize for HistoryNode {
+        fn begin(out: &mut Option<Self>) -> &mut dyn Visitor {
+            Place::new(out)
+        }
+    }
+
+    impl Visitor for Place<HistoryNode> {
+        fn map(&mut self) -> Result<Box<dyn Map + '_>> {
+            Ok(Box::new(HistoryNodeBuilder {
+                prev: None,
+                node: None,
+                root: None,
+                out: &mut self.out,
+            }))
+        }
+    }
+
+    struct HistoryNodeBuilder<'a> {
+        prev: Option<usize>,
+        node: Option<LinkedHistoryNode>,
+        root: Option<RootHistoryNode>,
+        out: &'a mut Option<HistoryNode>,
+    }
+
+    impl Map for HistoryNodeBuilder<'_> {
+        fn key(&mut self, k: &str) -> Result<&mut dyn Visitor> {
+            match k {
+                "prev" => Ok(Deserialize::begin(&mut self.prev)),
+                "node" => Ok(Deserialize::begin(&mut self.node)),
+                "root" => Ok(Deserialize::begin(&mut self.root)),
+                _ => Ok(<dyn Visitor>::ignore()),
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            let node = match (self.prev.take(), self.node.take(), self.root.take()) {
+                (Some(prev), Some(node), None) => HistoryNode::Linked {
+                    prev: history_id(prev)?,
+                    node,
+                },
+                (None, None, Some(root)) => HistoryNode::Root(root),
+                _ => return Err(Error),
+            };
+            *self.out = Some(node);
+            Ok(())
+        }
+    }
+
+    // Variants are deserialized through these structs.
+
+    #[derive(Deserialize)]
+    struct Rhs {
+        rhs: Vec<Symbol>,
+    }
+
+    #[derive(Deserialize)]
+    struct Binarize {
+        depth: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct EliminateNulling {
+        rhs0: Symbol,
+        rhs1: Option<Symbol>,
+        which: BinarizedRhsSubset,
+    }
+
+    #[derive(Deserialize)]
+    struct AssignPrecedence {
+        looseness: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct RewriteSequence {
+        top: bool,
+        rhs: Symbol,
+        sep: Option<Symbol>,
+    }
+
+    #[derive(Deserialize)]
+    struct Weight {
+        weight: f64,
+    }
+
+    #[derive(Deserialize)]
+    struct Distances {
+        events: Vec<u32>,
+    }
+
+    #[derive(Deserialize)]
+    struct EliminateUnitRules {
+        chain: Vec<usize>,
+    }
+
+    #[derive(Deserialize)]
+    struct LiftTerminal {
+        terminal: Symbol,
+    }
+
+    #[derive(Deserialize)]
+    struct SubstituteLeftCorner {
+        inner: usize,
+        len: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct EliminateLeftRecursion {
+        part: LeftRecursionPart,
+    }
+
+    #[derive(Deserialize)]
+    struct FactorPrefix {
+        len: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct FactorSuffix {
+        prefix_len: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct EliminateNullingSubset {
+        nulled: Vec<u32>,
+    }
+
+    #[derive(Deserialize)]
+    struct BinarizeRight {
+        depth: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct BinarizeShared {
+        len: u32,
+        suffix: bool,
+        rules: Vec<usize>,
+    }
+
+    #[derive(Deserialize)]
+    struct RenameSymbol {
+        from: Symbol,
+        to: Symbol,
+    }
+
+    #[derive(Deserialize)]
+    struct Inline {
+        inner: usize,
+        pos: u32,
+        len: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct Extract {
+        pos: u32,
+        len: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct Extracted {
+        len: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct Payload {
+        key: String,
+        value: String,
+    }
+
+    #[derive(Deserialize)]
+    struct NoOp {}
+
+    #[derive(Deserialize)]
+    struct Rule {
+        lhs: Symbol,
+    }
+
+    #[derive(Deserialize)]
+    struct Origin {
+        origin: usize,
+    }
+
+    #[derive(Deserialize)]
+    struct RootPayload {
+        origin: usize,
+        key: String,
+        value: String,
+    }
+
+    impl Deserialize for LinkedHistoryNode {
+        fn begin(out: &mut Option<Self>) -> &mut dyn Visitor {
+            Place::new(out)
+        }
+    }
+
+    impl Visitor for Place<LinkedHistoryNode> {
+        fn map(&mut self) -> Result<Box<dyn Map + '_>> {
+            Ok(Box::new(LinkedHistoryNodeBuilder {
+                rhs: None,
+                binarize: None,
+                eliminate_nulling: None,
+                assign_precedence: None,
+                rewrite_sequence: None,
+                weight: None,
+                distances: None,
+                eliminate_unit_rules: None,
+                lift_terminal: None,
+                substitute_left_corner: None,
+                eliminate_left_recursion: None,
+                factor_prefix: None,
+                factor_suffix: None,
+                eliminate_nulling_subset: None,
+                binarize_right: None,
+                binarize_shared: None,
+                rename_symbol: None,
+                inline: None,
+                extract: None,
+                extracted: None,
+                payload: None,
+                out: &mut self.out,
+            }))
+        }
+    }
+
+    struct LinkedHistoryNodeBuilder<'a> {
+        rhs: Option<Rhs>,
+        binarize: Option<Binarize>,
+        eliminate_nulling: Option<EliminateNulling>,
+        assign_precedence: Option<AssignPrecedence>,
+        rewrite_sequence: Option<RewriteSequence>,
+        weight: Option<Weight>,
+        distances: Option<Distances>,
+        eliminate_unit_rules: Option<EliminateUnitRules>,
+        lift_terminal: Option<LiftTerminal>,
+        substitute_left_corner: Option<SubstituteLeftCorner>,
+        eliminate_left_recursion: Option<EliminateLeftRecursion>,
+        factor_prefix: Option<FactorPrefix>,
+        factor_suffix: Option<FactorSuffix>,
+        eliminate_nulling_subset: Option<EliminateNullingSubset>,
+        binarize_right: Option<BinarizeRight>,
+        binarize_shared: Option<BinarizeShared>,
+        rename_symbol: Option<RenameSymbol>,
+        inline: Option<Inline>,
+        extract: Option<Extract>,
+        extracted: Option<Extracted>,
+        payload: Option<Payload>,
+        out: &'a mut Option<LinkedHistoryNode>,
+    }
+
+    impl Map for LinkedHistoryNodeBuilder<'_> {
+        fn key(&mut self, k: &str) -> Result<&mut dyn Visitor> {
+            match k {
+                "Rhs" => Ok(Deserialize::begin(&mut self.rhs)),
+                "Binarize" => Ok(Deserialize::begin(&mut self.binarize)),
+                "EliminateNulling" => Ok(Deserialize::begin(&mut self.eliminate_nulling)),
+                "AssignPrecedence" => Ok(Deserialize::begin(&mut self.assign_precedence)),
+                "RewriteSequence" => Ok(Deserialize::begin(&mut self.rewrite_sequence)),
+                "Weight" => Ok(Deserialize::begin(&mut self.weight)),
+                "Distances" => Ok(Deserialize::begin(&mut self.distances)),
+                "EliminateUnitRules" => Ok(Deserialize::begin(&mut self.eliminate_unit_rules)),
+                "LiftTerminal" => Ok(Deserialize::begin(&mut self.lift_terminal)),
+                "SubstituteLeftCorner" => Ok(Deserialize::begin(&mut self.substitute_left_corner)),
+                "EliminateLeftRecursion" => {
+                    Ok(Deserialize::begin(&mut self.eliminate_left_recursion))
+                }
+                "FactorPrefix" => Ok(Deserialize::begin(&mut self.factor_prefix)),
+                "FactorSuffix" => Ok(Deserialize::begin(&mut self.factor_suffix)),
+                "EliminateNullingSubset" => {
+                    Ok(Deserialize::begin(&mut self.eliminate_nulling_subset))
+                }
+                "BinarizeRight" => Ok(Deserialize::begin(&mut self.binarize_right)),
+                "BinarizeShared" => Ok(Deserialize::begin(&mut self.binarize_shared)),
+                "RenameSymbol" => Ok(Deserialize::begin(&mut self.rename_symbol)),
+                "Inline" => Ok(Deserialize::begin(&mut self.inline)),
+                "Extract" => Ok(Deserialize::begin(&mut self.extract)),
+                "Extracted" => Ok(Deserialize::begin(&mut self.extracted)),
+                "Payload" => Ok(Deserialize::begin(&mut self.payload)),
+                _ => Err(Error),
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            let variants = [
+                self.rhs.is_some(),
+                self.binarize.is_some(),
+                self.eliminate_nulling.is_some(),
+                self.assign_precedence.is_some(),
+                self.rewrite_sequence.is_some(),
+                self.weight.is_some(),
+                self.distances.is_some(),
+                self.eliminate_unit_rules.is_some(),
+                self.lift_terminal.is_some(),
+                self.substitute_left_corner.is_some(),
+                self.eliminate_left_recursion.is_some(),
+                self.factor_prefix.is_some(),
+                self.factor_suffix.is_some(),
+                self.eliminate_nulling_subset.is_some(),
+                self.binarize_right.is_some(),
+                self.binarize_shared.is_some(),
+                self.rename_symbol.is_some(),
+                self.inline.is_some(),
+                self.extract.is_some(),
+                self.extracted.is_some(),
+                self.payload.is_some(),
+            ];
+            if variants.iter().filter(|&&present| present).count() != 1 {
+                return Err(Error);
+            }
+            let node = if let Some(Rhs { rhs }) = self.rhs.take() {
+                LinkedHistoryNode::Rhs { rhs }
+            } else if let Some(Binarize { depth }) = self.binarize.take() {
+                LinkedHistoryNode::Binarize { depth }
+            } else if let Some(EliminateNulling { rhs0, rhs1, which }) =
+                self.eliminate_nulling.take()
+            {
+                LinkedHistoryNode::EliminateNulling { rhs0, rhs1, which }
+            } else if let Some(AssignPrecedence { looseness }) = self.assign_precedence.take() {
+                LinkedHistoryNode::AssignPrecedence { looseness }
+            } else if let Some(RewriteSequence { top, rhs, sep }) = self.rewrite_sequence.take() {
+                LinkedHistoryNode::RewriteSequence { top, rhs, sep }
+            } else if let Some(Weight { weight }) = self.weight.take() {
+                LinkedHistoryNode::Weight { weight }
+            } else if let Some(Distances { events }) = self.distances.take() {
+                LinkedHistoryNode::Distances { events }
+            } else if let Some(EliminateUnitRules { chain }) = self.eliminate_unit_rules.take() {
+                LinkedHistoryNode::EliminateUnitRules {
+                    chain: chain.into_iter().map(history_id).collect::<Result<_>>()?,
+                }
+            } else if let Some(LiftTerminal { terminal }) = self.lift_terminal.take() {
+                LinkedHistoryNode::LiftTerminal { terminal }
+            } else if let Some(SubstituteLeftCorner { inner, len }) =
+                self.substitute_left_corner.take()
+            {
+                LinkedHistoryNode::SubstituteLeftCorner {
+                    inner: history_id(inner)?,
+                    len,
+                }
+            } else if let Some(EliminateLeftRecursion { part }) =
+                self.eliminate_left_recursion.take()
+            {
+                LinkedHistoryNode::EliminateLeftRecursion { part }
+            } else if let Some(FactorPrefix { len }) = self.factor_prefix.take() {
+                LinkedHistoryNode::FactorPrefix { len }
+            } else if let Some(FactorSuffix { prefix_len }) = self.factor_suffix.take() {
+                LinkedHistoryNode::FactorSuffix { prefix_len }
+            } else if let Some(EliminateNullingSubset { nulled }) =
+                self.eliminate_nulling_subset.take()
+            {
+                LinkedHistoryNode::EliminateNullingSubset { nulled }
+            } else if let Some(BinarizeRight { depth }) = self.binarize_right.take() {
+                LinkedHistoryNode::BinarizeRight { depth }
+            } else if let Some(BinarizeShared { len, suffix, rules }) = self.binarize_shared.take()
+            {
+                LinkedHistoryNode::BinarizeShared {
+                    len,
+                    suffix,
+                    rules: rules.into_iter().map(history_id).collect::<Result<_>>()?,
+                }
+            } else if let Some(RenameSymbol { from, to }) = self.rename_symbol.take() {
+                LinkedHistoryNode::RenameSymbol { from, to }
+            } else if let Some(Inline { inner, pos, len }) = self.inline.take() {
+                LinkedHistoryNode::Inline {
+                    inner: history_id(inner)?,
+                    pos,
+                    len,
+                }
+            } else if let Some(Extract { pos, len }) = self.extract.take() {
+                LinkedHistoryNode::Extract { pos, len }
+            } else if let Some(Extracted { len }) = self.extracted.take() {
+                LinkedHistoryNode::Extracted { len }
+            } else if let Some(Payload { key, value }) = self.payload.take() {
+                LinkedHistoryNode::Payload {
+                    payload: crate::history::Payload::from_encoded(&key, value),
+                }
+            } else {
+                return Err(Error);
+            };
+            *self.out = Some(node);
+            Ok(())
+        }
+    }
+
+    impl Deserialize for RootHistoryNode {
+        fn begin(out: &mut Option<Self>) -> &mut dyn Visitor {
+            Place::new(out)
+        }
+    }
+
+    impl Visitor for Place<RootHistoryNode> {
+        fn map(&mut self) -> Result<Box<dyn Map + '_>> {
+            Ok(Box::new(RootHistoryNodeBuilder {
+                no_op: None,
+                rule: None,
+                origin: None,
+                payload: None,
+                out: &mut self.out,
+            }))
+        }
+    }
+
+    struct RootHistoryNodeBuilder<'a> {
+        no_op: Option<NoOp>,
+        rule: Option<Rule>,
+        origin: Option<Origin>,
+        payload: Option<RootPayload>,
+        out: &'a mut Option<RootHistoryNode>,
+    }
+
+    impl Map for RootHistoryNodeBuilder<'_> {
+        fn key(&mut self, k: &str) -> Result<&mut dyn Visitor> {
+            match k {
+                "NoOp" => Ok(Deserialize::begin(&mut self.no_op)),
+                "Rule" => Ok(Deserialize::begin(&mut self.rule)),
+                "Origin" => Ok(Deserialize::begin(&mut self.origin)),
+                "Payload" => Ok(Deserialize::begin(&mut self.payload)),
+                _ => Err(Error),
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            let node = match (
+                self.no_op.take(),
+                self.rule.take(),
+                self.origin.take(),
+                self.payload.take(),
+            ) {
+                (Some(NoOp {}), None, None, None) => RootHistoryNode::NoOp,
+                (None, Some(Rule { lhs }), None, None) => RootHistoryNode::Rule { lhs },
+                (None, None, Some(Origin { origin }), None) => RootHistoryNode::Origin { origin },
+                (None, None, None, Some(RootPayload { origin, key, value })) => {
+                    RootHistoryNode::Payload {
+                        origin,
+                        payload: crate::history::Payload::from_encoded(&key, value),
+                    }
+                }
+                _ => return Err(Error),
+            };
+            *self.out = Some(node);
+            Ok(())
+        }
+    }
+}
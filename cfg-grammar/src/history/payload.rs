@@ -0,0 +1,141 @@
+//! Typed user data carried in rule histories.
+//!
+//! A `Payload` holds a value of any type that implements `PayloadValue`, such as a semantic
+//! action ID or a source span. Payloads of different types do not hide each other:
+//! `HistoryGraph::nearest_payload` finds the nearest payload of the requested type.
+//!
+//! Saved grammars store every payload as the `KEY` of its type and its value encoded as a
+//! string. Payloads of loaded grammars are decoded on first access.
+
+use std::any::Any;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+type AnyValue = dyn Any + Send + Sync;
+
+/// A type of data that can be carried in rule histories.
+pub trait PayloadValue: Any + Send + Sync {
+    /// Identifies the type in saved grammars. It must be unique among payload types.
+    const KEY: &'static str;
+
+    /// Encodes the value as a string.
+    fn encode(&self) -> String;
+
+    /// Decodes a value encoded with `encode`. Returns `None` if the text is malformed.
+    fn decode(text: &str) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+/// A value of some `PayloadValue` type. Cloning is cheap.
+#[derive(Clone)]
+pub struct Payload {
+    key: Arc<str>,
+    value: Arc<Value>,
+}
+
+enum Value {
+    Typed {
+        value: Box<AnyValue>,
+        encode: fn(&AnyValue) -> String,
+    },
+    Encoded {
+        text: String,
+        decoded: OnceLock<Option<Box<AnyValue>>>,
+    },
+}
+
+impl Payload {
+    /// Creates a payload holding the given value.
+    pub fn new<T: PayloadValue>(value: T) -> Self {
+        Payload {
+            key: T::KEY.into(),
+            value: Arc::new(Value::Typed {
+                value: Box::new(value),
+                encode: encode::<T>,
+            }),
+        }
+    }
+
+    /// Creates a payload from a key and a value encoded as a string, as found in a saved
+    /// grammar. The value is decoded when it is first accessed with `get`.
+    pub fn from_encoded(key: &str, text: String) -> Self {
+        Payload {
+            key: key.into(),
+            value: Arc::new(Value::Encoded {
+                text,
+                decoded: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// Returns the key of the value's type.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the value, or `None` if it has another type or can't be decoded.
+    pub fn get<T: PayloadValue>(&self) -> Option<&T> {
+        if *self.key != *T::KEY {
+            return None;
+        }
+        let value = match &*self.value {
+            Value::Typed { value, .. } => value,
+            Value::Encoded { text, decoded } => decoded
+                .get_or_init(|| {
+                    let value: Box<AnyValue> = Box::new(T::decode(text)?);
+                    Some(value)
+                })
+                .as_ref()?,
+        };
+        (**value).downcast_ref()
+    }
+
+    /// Returns the value encoded as a string.
+    pub fn encode(&self) -> String {
+        match &*self.value {
+            Value::Typed { value, encode } => encode(&**value),
+            Value::Encoded { text, .. } => text.clone(),
+        }
+    }
+}
+
+fn encode<T: PayloadValue>(value: &AnyValue) -> String {
+    value
+        .downcast_ref::<T>()
+        .expect("payload of the wrong type")
+        .encode()
+}
+
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Payload")
+            .field("key", &self.key)
+            .field("value", &self.encode())
+            .finish()
+    }
+}
+
+impl PayloadValue for String {
+    const KEY: &'static str = "String";
+
+    fn encode(&self) -> String {
+        self.clone()
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        Some(text.to_string())
+    }
+}
+
+impl PayloadValue for u64 {
+    const KEY: &'static str = "u64";
+
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        text.parse().ok()
+    }
+}
@@ -0,0 +1,214 @@
+//! Provenance of rules in transformed grammars.
+//!
+//! Every transformation puts a linked node on top of the history of the rule it rewrites.
+//! Following `prev` links from a rule's history leads to the root of the rule it comes from.
+//! `Provenance` collects the nodes on the way and replays them to find which symbols of the
+//! original RHS ended up where.
+
+use std::iter;
+
+use super::graph::HistoryGraph;
+use super::node::{
+    BinarizedRhsSubset, HistoryId, HistoryNode, LeftRecursionPart, LinkedHistoryNode,
+    RootHistoryNode,
+};
+use crate::local_prelude::*;
+
+/// Describes where a rule comes from.
+#[derive(Clone, Debug)]
+pub struct Provenance {
+    root_id: HistoryId,
+    root: RootHistoryNode,
+    steps: Vec<(HistoryId, LinkedHistoryNode)>,
+    original_rhs: Option<Vec<Symbol>>,
+    positions: Option<Vec<Option<usize>>>,
+}
+
+impl Provenance {
+    /// Follows `prev` links from `history_id` to the root and replays the nodes on the way.
+    pub fn new(graph: &HistoryGraph, history_id: HistoryId) -> Self {
+        let mut steps = vec![];
+        let mut id = history_id;
+        let root = loop {
+            match graph[id.get()] {
+                HistoryNode::Linked { prev, ref node } => {
+                    steps.push((id, node.clone()));
+                    id = prev;
+                }
+                HistoryNode::Root(ref root) => break root.clone(),
+            }
+        };
+        steps.reverse();
+
+        let mut original_rhs = None;
+        let mut positions: Option<Vec<Option<usize>>> = None;
+        let mut sequence = None;
+        for (_, node) in &steps {
+            if let LinkedHistoryNode::Rhs { rhs } = node {
+                if original_rhs.is_none() {
+                    original_rhs = Some(rhs.clone());
+                }
+                positions = Some(match sequence {
+                    Some((elem, sep)) => rhs
+                        .iter()
+                        .map(|&sym| {
+                            if sym == elem {
+                                Some(0)
+                            } else if Some(sym) == sep {
+                                Some(1)
+                            } else {
+                                None
+                            }
+                        })
+                        .collect(),
+                    None => (0..rhs.len()).map(Some).collect(),
+                });
+            } else if let &LinkedHistoryNode::RewriteSequence { rhs, sep, .. } = node {
+                sequence = Some((rhs, sep));
+            } else if let Some(prev) = positions {
+                positions = Some(replay(node, prev));
+            }
+        }
+
+        Provenance {
+            root_id: id,
+            root,
+            steps,
+            original_rhs,
+            positions,
+        }
+    }
+
+    /// Returns the ID of the root node.
+    pub fn root_id(&self) -> HistoryId {
+        self.root_id
+    }
+
+    /// Returns the root node, which identifies the original rule.
+    pub fn root(&self) -> &RootHistoryNode {
+        &self.root
+    }
+
+    /// Returns the LHS of the original rule, if the root records it.
+    pub fn lhs(&self) -> Option<Symbol> {
+        match self.root {
+            RootHistoryNode::Rule { lhs } => Some(lhs),
+            _ => None,
+        }
+    }
+
+    /// Returns the external origin of the original rule, if the root records it.
+    pub fn origin(&self) -> Option<usize> {
+        match self.root {
+            RootHistoryNode::Origin { origin } | RootHistoryNode::Payload { origin, .. } => {
+                Some(origin)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the linked nodes between the root and the rule, in order of application. Every
+    /// node is paired with its ID.
+    pub fn steps(&self) -> &[(HistoryId, LinkedHistoryNode)] {
+        &self.steps[..]
+    }
+
+    /// Returns the RHS of the original rule, as recorded by its first
+    /// `LinkedHistoryNode::Rhs`.
+    pub fn original_rhs(&self) -> Option<&[Symbol]> {
+        self.original_rhs.as_deref()
+    }
+
+    /// Returns the position in the original RHS of every symbol of the rule's RHS, or `None`
+    /// for symbols introduced by transformations, such as helpers of binarization. Returns
+    /// `None` if the history records no RHS.
+    ///
+    /// For rules rewritten from sequence rules, elements are at position 0 and separators
+    /// are at position 1.
+    pub fn positions(&self) -> Option<&[Option<usize>]> {
+        self.positions.as_deref()
+    }
+}
+
+/// Applies one transformation to the positions of a rule's RHS.
+fn replay(node: &LinkedHistoryNode, prev: Vec<Option<usize>>) -> Vec<Option<usize>> {
+    let len = prev.len();
+    match *node {
+        LinkedHistoryNode::Binarize { depth } => {
+            let depth = depth as usize;
+            if depth + 2 >= len {
+                prev[..len.min(2)].to_vec()
+            } else {
+                vec![None, prev[len - 1 - depth]]
+            }
+        }
+        LinkedHistoryNode::BinarizeRight { depth } => {
+            let depth = depth as usize;
+            if depth + 2 >= len {
+                prev[len.saturating_sub(2)..].to_vec()
+            } else {
+                vec![prev[depth], None]
+            }
+        }
+        LinkedHistoryNode::BinarizeShared { .. } => vec![None; 2],
+        LinkedHistoryNode::EliminateNulling { which, .. } => match which {
+            BinarizedRhsSubset::Left => prev.into_iter().skip(1).collect(),
+            BinarizedRhsSubset::Right => prev.into_iter().take(1).collect(),
+            BinarizedRhsSubset::All => vec![],
+        },
+        LinkedHistoryNode::EliminateNullingSubset { ref nulled } => prev
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| !nulled.contains(&(i as u32)))
+            .map(|(_, pos)| pos)
+            .collect(),
+        LinkedHistoryNode::LiftTerminal { .. } => vec![None],
+        LinkedHistoryNode::SubstituteLeftCorner { len: inner_len, .. } => {
+            splice(&prev, 0, 1, inner_len as usize)
+        }
+        LinkedHistoryNode::Inline {
+            pos,
+            len: inner_len,
+            ..
+        } => splice(&prev, pos as usize, 1, inner_len as usize),
+        LinkedHistoryNode::Extract {
+            pos,
+            len: outer_len,
+        } => splice(&prev, pos as usize, outer_len as usize, 1),
+        LinkedHistoryNode::Extracted { len } => vec![None; len as usize],
+        LinkedHistoryNode::EliminateLeftRecursion { part } => match part {
+            LeftRecursionPart::Base => prev.into_iter().chain(Some(None)).collect(),
+            LeftRecursionPart::Tail => prev.into_iter().skip(1).chain(Some(None)).collect(),
+            LeftRecursionPart::End => vec![],
+        },
+        LinkedHistoryNode::FactorPrefix { len } => vec![None; len as usize + 1],
+        LinkedHistoryNode::FactorSuffix { prefix_len } => {
+            prev.into_iter().skip(prefix_len as usize).collect()
+        }
+        LinkedHistoryNode::Rhs { .. }
+        | LinkedHistoryNode::AssignPrecedence { .. }
+        | LinkedHistoryNode::RewriteSequence { .. }
+        | LinkedHistoryNode::Weight { .. }
+        | LinkedHistoryNode::Distances { .. }
+        | LinkedHistoryNode::EliminateUnitRules { .. }
+        | LinkedHistoryNode::RenameSymbol { .. }
+        | LinkedHistoryNode::Payload { .. } => prev,
+    }
+}
+
+/// Replaces `removed` positions at `pos` with `inserted` positions of new symbols.
+fn splice(
+    prev: &[Option<usize>],
+    pos: usize,
+    removed: usize,
+    inserted: usize,
+) -> Vec<Option<usize>> {
+    let pos = pos.min(prev.len());
+    let end = (pos + removed).min(prev.len());
+    prev[..pos]
+        .iter()
+        .cloned()
+        .chain(iter::repeat_n(None, inserted))
+        .chain(prev[end..].iter().cloned())
+        .collect()
+}
@@ -0,0 +1,111 @@
+//! Reverse indexes of a grammar's rules.
+
+use crate::local_prelude::*;
+use crate::symbol::set::{self, SymbolBitSet};
+
+/// An occurrence of a symbol on the RHS of a rule.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Occurrence {
+    /// The index of the rule, as given by `rules().enumerate()`.
+    pub rule: usize,
+    /// The position of the symbol on the rule's RHS.
+    pub pos: usize,
+}
+
+/// Reverse indexes of a grammar. Answers which rules have a given LHS, where a given symbol
+/// appears on a RHS, and which symbols are nonterminals.
+///
+/// Rules are identified by their indices in `rules().enumerate()`. The index must be rebuilt
+/// after the grammar is modified.
+pub struct GrammarIndex {
+    /// Offsets into `rules_by_lhs`, one range per symbol.
+    lhs_offsets: Vec<usize>,
+    /// Rule indices grouped by LHS.
+    rules_by_lhs: Vec<usize>,
+    /// Offsets into `occurrences`, one range per symbol.
+    rhs_offsets: Vec<usize>,
+    /// RHS occurrences grouped by symbol.
+    occurrences: Vec<Occurrence>,
+    /// The set of symbols that appear on the LHS of a rule.
+    nonterminals: SymbolBitSet,
+}
+
+impl GrammarIndex {
+    /// Builds indexes for the given grammar in O(n) time.
+    pub fn new<G>(grammar: &G) -> Self
+    where
+        G: RuleContainer,
+    {
+        let num_syms = grammar.num_syms();
+        let mut lhs_offsets = vec![0; num_syms + 1];
+        let mut rhs_offsets = vec![0; num_syms + 1];
+        let mut nonterminals = SymbolBitSet::new(grammar, false);
+        for rule in grammar.rules() {
+            lhs_offsets[rule.lhs.usize() + 1] += 1;
+            nonterminals.set(rule.lhs, true);
+            for &sym in rule.rhs {
+                rhs_offsets[sym.usize() + 1] += 1;
+            }
+        }
+        for i in 0..num_syms {
+            lhs_offsets[i + 1] += lhs_offsets[i];
+            rhs_offsets[i + 1] += rhs_offsets[i];
+        }
+
+        // Fill both groupings in order, so that each group is sorted by rule index.
+        let mut lhs_fill = lhs_offsets.clone();
+        let mut rhs_fill = rhs_offsets.clone();
+        let mut rules_by_lhs = vec![0; lhs_offsets[num_syms]];
+        let mut occurrences = vec![Occurrence { rule: 0, pos: 0 }; rhs_offsets[num_syms]];
+        for (rule_idx, rule) in grammar.rules().enumerate() {
+            rules_by_lhs[lhs_fill[rule.lhs.usize()]] = rule_idx;
+            lhs_fill[rule.lhs.usize()] += 1;
+            for (pos, &sym) in rule.rhs.iter().enumerate() {
+                occurrences[rhs_fill[sym.usize()]] = Occurrence {
+                    rule: rule_idx,
+                    pos,
+                };
+                rhs_fill[sym.usize()] += 1;
+            }
+        }
+
+        GrammarIndex {
+            lhs_offsets,
+            rules_by_lhs,
+            rhs_offsets,
+            occurrences,
+            nonterminals,
+        }
+    }
+
+    /// Returns the indices of rules with the given LHS, in ascending order.
+    pub fn rules_for_lhs(&self, lhs: Symbol) -> &[usize] {
+        &self.rules_by_lhs[self.lhs_offsets[lhs.usize()]..self.lhs_offsets[lhs.usize() + 1]]
+    }
+
+    /// Returns all occurrences of the given symbol on the RHS of rules, ordered by rule index
+    /// and position.
+    pub fn occurrences(&self, sym: Symbol) -> &[Occurrence] {
+        &self.occurrences[self.rhs_offsets[sym.usize()]..self.rhs_offsets[sym.usize() + 1]]
+    }
+
+    /// Checks whether the given symbol is the LHS of any rule.
+    pub fn is_nonterminal(&self, sym: Symbol) -> bool {
+        self.nonterminals.has_sym(sym)
+    }
+
+    /// Returns the set of nonterminals.
+    pub fn nonterminal_set(&self) -> &SymbolBitSet {
+        &self.nonterminals
+    }
+
+    /// Iterates over nonterminals.
+    pub fn nonterminals(&self) -> set::Iter<'_> {
+        self.nonterminals.iter()
+    }
+
+    /// Returns the number of indexed rules.
+    pub fn num_rules(&self) -> usize {
+        self.rules_by_lhs.len()
+    }
+}
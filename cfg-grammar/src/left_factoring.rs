@@ -0,0 +1,177 @@
+//! Left factoring.
+//!
+//! Alternatives of a nonterminal that share a common prefix, such as `A ::= alpha beta1` and
+//! `A ::= alpha beta2`, are rewritten into `A ::= alpha A'`, `A' ::= beta1` and `A' ::= beta2`.
+//! Factoring is a common step towards LL(1) grammars.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use bit_vec::BitVec;
+
+use crate::first::FirstSets;
+use crate::history::node::{HistoryNodeFactorPrefix, HistoryNodeFactorSuffix, RootHistoryNode};
+use crate::index::GrammarIndex;
+use crate::local_prelude::*;
+use crate::rule::RuleRef;
+
+/// The outcome of left factoring.
+#[derive(Clone, Debug, Default)]
+pub struct LeftFactoring {
+    new_syms: Vec<Symbol>,
+    unfixed: Vec<Symbol>,
+}
+
+impl LeftFactoring {
+    /// Returns the nonterminals introduced for factored suffixes, in order of creation.
+    pub fn new_syms(&self) -> &[Symbol] {
+        &self.new_syms[..]
+    }
+
+    /// Returns the nonterminals whose alternatives still begin with the same terminal, or
+    /// which have more than one nullable alternative. Such conflicts come from distinct
+    /// leftmost symbols, so they cannot be removed by factoring alone.
+    pub fn unfixed(&self) -> &[Symbol] {
+        &self.unfixed[..]
+    }
+}
+
+/// Factors out common prefixes of alternatives until no two alternatives of the same
+/// nonterminal begin with the same symbol. The language represented by the grammar is
+/// preserved.
+///
+/// Alternatives that begin with the same symbol are grouped and their longest common prefix
+/// `alpha` is factored out. The rule `A ::= alpha A'` has `LinkedHistoryNode::FactorPrefix`
+/// on top of a new root, since it stands for the whole group. Every rule `A' ::= beta` has
+/// `LinkedHistoryNode::FactorSuffix` on top of the history of `A ::= alpha beta`. The new
+/// nonterminals are factored in turn.
+///
+/// Conflicts between alternatives that begin with distinct symbols are reported through
+/// `LeftFactoring::unfixed`. Conflicts between nullable alternatives and the symbols that
+/// follow a nonterminal are not examined.
+pub fn left_factor<G>(grammar: &mut G) -> LeftFactoring
+where
+    G: RuleContainer,
+{
+    let mut alternatives: BTreeMap<Symbol, Vec<(Vec<Symbol>, HistoryId)>> = BTreeMap::new();
+    for rule in grammar.rules() {
+        let alternative = (rule.rhs.to_vec(), rule.history_id);
+        alternatives.entry(rule.lhs).or_default().push(alternative);
+    }
+
+    let mut result = LeftFactoring::default();
+    let mut modified = BTreeSet::new();
+    let mut queue: VecDeque<Symbol> = alternatives.keys().cloned().collect();
+    let mut prefix_root = None;
+    while let Some(lhs) = queue.pop_front() {
+        let mut current = alternatives.remove(&lhs).unwrap_or_default();
+        while let Some(group) = first_shared_group(&current) {
+            let first_rhs = &current[group[0]].0;
+            let len = group[1..]
+                .iter()
+                .map(|&i| common_prefix_len(first_rhs, &current[i].0))
+                .min()
+                .unwrap();
+            let suffix_sym = grammar.next_sym();
+            let mut prefix_rhs = first_rhs[..len].to_vec();
+            prefix_rhs.push(suffix_sym);
+
+            let mut suffixes = vec![];
+            for &i in &group {
+                let (ref rhs, prev) = current[i];
+                let history = HistoryNodeFactorSuffix {
+                    prev,
+                    prefix_len: len as u32,
+                };
+                let history_id = grammar.add_history_node(history.into());
+                suffixes.push((rhs[len..].to_vec(), history_id));
+            }
+            let root = *prefix_root
+                .get_or_insert_with(|| grammar.add_history_node(RootHistoryNode::NoOp.into()));
+            let history = HistoryNodeFactorPrefix {
+                prev: root,
+                len: len as u32,
+            };
+            current[group[0]] = (prefix_rhs, grammar.add_history_node(history.into()));
+            let mut i = 0;
+            current.retain(|_| {
+                i += 1;
+                !group[1..].contains(&(i - 1))
+            });
+
+            alternatives.insert(suffix_sym, suffixes);
+            queue.push_back(suffix_sym);
+            modified.insert(suffix_sym);
+            modified.insert(lhs);
+            result.new_syms.push(suffix_sym);
+        }
+        alternatives.insert(lhs, current);
+    }
+
+    grammar.retain(|rule| !modified.contains(&rule.lhs));
+    for (&lhs, rules) in &alternatives {
+        if modified.contains(&lhs) {
+            for (rhs, history_id) in rules {
+                grammar.add_rule(RuleRef {
+                    lhs,
+                    rhs: &rhs[..],
+                    history_id: *history_id,
+                });
+            }
+        }
+    }
+    result.unfixed = first_conflicts(grammar);
+    result
+}
+
+/// Returns the indices of the earliest group of alternatives that begin with the same symbol,
+/// if there is a group of at least two.
+fn first_shared_group(alternatives: &[(Vec<Symbol>, HistoryId)]) -> Option<Vec<usize>> {
+    alternatives.iter().enumerate().find_map(|(i, (rhs, _))| {
+        let first = rhs.first()?;
+        let group: Vec<usize> = (i..alternatives.len())
+            .filter(|&j| alternatives[j].0.first() == Some(first))
+            .collect();
+        if group.len() > 1 {
+            Some(group)
+        } else {
+            None
+        }
+    })
+}
+
+fn common_prefix_len(a: &[Symbol], b: &[Symbol]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Returns nonterminals whose alternatives have overlapping FIRST sets, or more than one
+/// nullable alternative.
+fn first_conflicts<G>(grammar: &G) -> Vec<Symbol>
+where
+    G: RuleContainer,
+{
+    let num_syms = grammar.num_syms();
+    let index = GrammarIndex::new(grammar);
+    let rules: Vec<RuleRef> = grammar.rules().collect();
+    let first_sets = FirstSets::new(grammar, &index);
+
+    let mut result = vec![];
+    for lhs in index.nonterminals() {
+        let mut seen = BitVec::from_elem(num_syms, false);
+        let mut seen_nullable = false;
+        let mut conflict = false;
+        for &rule_idx in index.rules_for_lhs(lhs) {
+            let rhs = rules[rule_idx].rhs;
+            let set = first_sets.first_of_rhs(rhs);
+            let mut overlap = set.clone();
+            overlap.and(&seen);
+            let is_nullable = first_sets.is_rhs_nullable(rhs);
+            conflict |= overlap.any() || (is_nullable && seen_nullable);
+            seen.or(&set);
+            seen_nullable |= is_nullable;
+        }
+        if conflict {
+            result.push(lhs);
+        }
+    }
+    result
+}
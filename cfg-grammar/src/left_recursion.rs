@@ -0,0 +1,168 @@
+//! Left recursion elimination.
+//!
+//! The transform is Paull's algorithm with Moore's improvement: only left-recursive
+//! nonterminals are ordered and substituted, since no other nonterminal can take part in
+//! a left-recursive cycle.
+
+use std::collections::BTreeMap;
+
+use bit_vec::BitVec;
+
+use crate::history::node::{
+    HistoryNodeEliminateLeftRecursion, HistoryNodeSubstituteLeftCorner, LeftRecursionPart,
+    RootHistoryNode,
+};
+use crate::local_prelude::*;
+use crate::rule::RuleRef;
+
+/// Removes direct and indirect left recursion. The language represented by the grammar is
+/// preserved.
+///
+/// The grammar must have no nullable symbols in leftmost positions and no cycles of unit rules.
+/// Otherwise, hidden left recursion may remain.
+///
+/// Returns a map from every nonterminal `A` whose direct left recursion was removed to its new
+/// tail nonterminal `A'`. Rules `A ::= A alpha` and `A ::= beta` are rewritten into
+/// `A ::= beta A'`, `A' ::= alpha A'` and `A' ::= ε`, with
+/// `LinkedHistoryNode::EliminateLeftRecursion` on top of the history of the original rules.
+/// A tree `A(beta, A'(alpha1, A'(alpha2, A'())))` stands for the left-associative tree
+/// `A(A(A(beta), alpha1), alpha2)`. When a rule
+/// `A ::= B gamma` is replaced with `A ::= delta gamma` for every rule `B ::= delta`, the new
+/// rule has `LinkedHistoryNode::SubstituteLeftCorner` on top of the history of `A ::= B gamma`,
+/// with `inner` pointing to the history of `B ::= delta`. Its first `len` symbols form the
+/// subtree of `B`.
+pub fn eliminate_left_recursion<G>(grammar: &mut G) -> BTreeMap<Symbol, Symbol>
+where
+    G: RuleContainer,
+{
+    let left_recursive = left_recursive_syms(grammar);
+    let ordered: Vec<Symbol> = (0..left_recursive.len())
+        .filter(|&i| left_recursive[i])
+        .map(Symbol::from)
+        .collect();
+    let mut result = BTreeMap::new();
+    if ordered.is_empty() {
+        return result;
+    }
+
+    let mut alternatives: BTreeMap<Symbol, Vec<(Vec<Symbol>, HistoryId)>> = BTreeMap::new();
+    for rule in grammar.rules() {
+        if left_recursive[rule.lhs.usize()] {
+            let alternative = (rule.rhs.to_vec(), rule.history_id);
+            alternatives.entry(rule.lhs).or_default().push(alternative);
+        }
+    }
+    grammar.retain(|rule| !left_recursive[rule.lhs.usize()]);
+
+    let mut new_rules = vec![];
+    let mut end_root = None;
+    for (i, &lhs) in ordered.iter().enumerate() {
+        // Substitute for left corners that precede `lhs` in the order.
+        for &left_corner in &ordered[..i] {
+            let current = alternatives.remove(&lhs).unwrap_or_default();
+            let mut substituted = vec![];
+            for (rhs, history_id) in current {
+                if rhs.first() != Some(&left_corner) {
+                    substituted.push((rhs, history_id));
+                    continue;
+                }
+                for (inner_rhs, inner_history_id) in &alternatives[&left_corner] {
+                    let history = HistoryNodeSubstituteLeftCorner {
+                        prev: history_id,
+                        inner: *inner_history_id,
+                        len: inner_rhs.len() as u32,
+                    };
+                    let new_rhs = inner_rhs.iter().chain(&rhs[1..]).cloned().collect();
+                    substituted.push((new_rhs, grammar.add_history_node(history.into())));
+                }
+            }
+            alternatives.insert(lhs, substituted);
+        }
+
+        // Remove direct left recursion.
+        let current = alternatives.remove(&lhs).unwrap_or_default();
+        let (recursive, base): (Vec<_>, Vec<_>) = current
+            .into_iter()
+            .partition(|(rhs, _)| rhs.first() == Some(&lhs));
+        if recursive.is_empty() {
+            alternatives.insert(lhs, base);
+            continue;
+        }
+        let tail = grammar.next_sym();
+        let part_history = |grammar: &mut G, prev, part| {
+            grammar.add_history_node(HistoryNodeEliminateLeftRecursion { prev, part }.into())
+        };
+        let mut rewritten = vec![];
+        for (mut rhs, history_id) in base {
+            rhs.push(tail);
+            rewritten.push((
+                rhs,
+                part_history(grammar, history_id, LeftRecursionPart::Base),
+            ));
+        }
+        for (rhs, history_id) in recursive {
+            // Rules of the form `A ::= A` are dropped.
+            if rhs.len() > 1 {
+                let mut tail_rhs = rhs[1..].to_vec();
+                tail_rhs.push(tail);
+                let history_id = part_history(grammar, history_id, LeftRecursionPart::Tail);
+                new_rules.push((tail, tail_rhs, history_id));
+            }
+        }
+        let root =
+            *end_root.get_or_insert_with(|| grammar.add_history_node(RootHistoryNode::NoOp.into()));
+        let history_id = part_history(grammar, root, LeftRecursionPart::End);
+        new_rules.push((tail, vec![], history_id));
+        alternatives.insert(lhs, rewritten);
+        result.insert(lhs, tail);
+    }
+
+    for (lhs, rules) in alternatives {
+        for (rhs, history_id) in rules {
+            new_rules.push((lhs, rhs, history_id));
+        }
+    }
+    new_rules.sort_by_key(|&(lhs, _, _)| lhs);
+    for (lhs, rhs, history_id) in new_rules {
+        grammar.add_rule(RuleRef {
+            lhs,
+            rhs: &rhs[..],
+            history_id,
+        });
+    }
+    result
+}
+
+/// Returns the set of symbols `A` such that `A` derives `A alpha` for some `alpha` through
+/// leftmost symbols of rules.
+fn left_recursive_syms<G>(grammar: &G) -> BitVec
+where
+    G: RuleContainer,
+{
+    let num_syms = grammar.num_syms();
+    let mut left_corners = vec![vec![]; num_syms];
+    for rule in grammar.rules() {
+        if let Some(&first) = rule.rhs.first() {
+            left_corners[rule.lhs.usize()].push(first);
+        }
+    }
+    let mut result = BitVec::from_elem(num_syms, false);
+    let mut visited = BitVec::from_elem(num_syms, false);
+    let mut stack = vec![];
+    for sym in 0..num_syms {
+        visited.clear();
+        stack.clear();
+        stack.extend(left_corners[sym].iter().cloned());
+        while let Some(left_corner) = stack.pop() {
+            if left_corner.usize() == sym {
+                result.set(sym, true);
+                break;
+            }
+            if !visited[left_corner.usize()] {
+                visited.set(left_corner.usize(), true);
+                stack.extend(left_corners[left_corner.usize()].iter().cloned());
+            }
+        }
+    }
+    result
+}
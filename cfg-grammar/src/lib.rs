@@ -14,17 +14,33 @@
 #![cfg_attr(test, allow(missing_docs))]
 
 pub mod binarized_cfg;
+pub mod btree_cfg;
 pub mod cfg;
+pub mod cnf;
+pub mod diagnostic;
+pub mod diff;
+mod first;
+pub mod fnv;
 pub mod history;
+pub mod index;
+pub mod left_factoring;
+pub mod left_recursion;
 pub mod precedenced_rule;
+pub mod refactor;
 pub mod rhs_closure;
 pub mod rule;
 pub mod rule_container;
+pub mod sequence;
+#[cfg(feature = "serialize")]
+mod serialize;
 pub mod symbol;
+pub mod unit_rules;
 
 pub use crate::binarized_cfg::BinarizedCfg;
+pub use crate::btree_cfg::BTreeCfg;
 pub use crate::cfg::Cfg;
 pub use crate::history::node::{HistoryId, HistoryNode};
+pub use crate::index::GrammarIndex;
 pub use crate::rule::AsRuleRef;
 pub use crate::rule_container::RuleContainer;
 pub use cfg_symbol::source::SymbolSource;
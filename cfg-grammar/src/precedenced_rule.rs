@@ -48,7 +48,7 @@ where
         let tightest_lhs = rules.next_sym();
         PrecedencedRuleBuilder {
             rules,
-            lhs: lhs,
+            lhs,
             tighter_lhs: tightest_lhs,
             current_lhs: tightest_lhs,
             history: None,
@@ -172,7 +172,7 @@ where
         let history_id = self.rules.add_history_node(RootHistoryNode::NoOp.into());
         RuleBuilder::new(&mut self.rules)
             .rule(self.current_lhs)
-            .rhs_with_history(&[self.tighter_lhs], history_id);
+            .rhs_with_history([self.tighter_lhs], history_id);
         self
     }
 
@@ -191,6 +191,6 @@ where
         // The associativity is not reset in the call to `rhs`.
         RuleBuilder::new(self.rules)
             .rule(self.lhs)
-            .rhs_with_history(&[loosest_lhs], history_id)
+            .rhs_with_history([loosest_lhs], history_id)
     }
 }
@@ -0,0 +1,207 @@
+//! Refactorings: inlining and extraction of nonterminals, which preserve the language, and
+//! renaming and merging of symbols.
+//!
+//! Every rewritten rule keeps the history of the rule it comes from, with a linked node on top
+//! that describes the change. Parse results can be traced back to the original rules.
+
+use std::collections::BTreeMap;
+
+use crate::history::node::{
+    HistoryNodeExtract, HistoryNodeExtracted, HistoryNodeInline, HistoryNodeRenameSymbol,
+    RootHistoryNode,
+};
+use crate::local_prelude::*;
+use crate::rule::cfg_rule::CfgRule;
+use crate::rule::RuleRef;
+use crate::AsRuleRef;
+
+/// Substitutes the alternatives of `sym` at all of its use sites. See `inline_at`.
+pub fn inline<G>(grammar: &mut G, sym: Symbol)
+where
+    G: RuleContainer,
+{
+    inline_at(grammar, sym, |_, _| true);
+}
+
+/// Substitutes the alternatives of `sym` at selected use sites. The predicate receives a rule
+/// and a position in its RHS where `sym` occurs.
+///
+/// A rule `A ::= alpha sym beta` is replaced with `A ::= alpha gamma beta` for every rule
+/// `sym ::= gamma`. The new rule has `LinkedHistoryNode::Inline` on top of the history of the
+/// replaced rule, with `inner` pointing to the history of `sym ::= gamma`. When a rule has
+/// several selected sites, they are substituted from right to left, so every node's `pos`
+/// refers to the RHS below it. The rules of `sym` are kept, even if `sym` becomes unused.
+pub fn inline_at<G, F>(grammar: &mut G, sym: Symbol, mut select: F)
+where
+    G: RuleContainer,
+    F: FnMut(RuleRef, usize) -> bool,
+{
+    let alternatives: Vec<(Vec<Symbol>, HistoryId)> = grammar
+        .rules()
+        .filter(|rule| rule.lhs == sym)
+        .map(|rule| (rule.rhs.to_vec(), rule.history_id))
+        .collect();
+    let mut sites = vec![];
+    grammar.retain(|rule| {
+        let positions: Vec<usize> = (0..rule.rhs.len())
+            .filter(|&pos| rule.rhs[pos] == sym && select(rule, pos))
+            .collect();
+        if positions.is_empty() {
+            true
+        } else {
+            let rule = CfgRule::new(rule.lhs, rule.rhs.to_vec(), rule.history_id);
+            sites.push((rule, positions));
+            false
+        }
+    });
+
+    for (rule, positions) in sites {
+        let mut rules = vec![rule];
+        for &pos in positions.iter().rev() {
+            let mut substituted = vec![];
+            for rule in rules {
+                for (inner_rhs, inner) in &alternatives {
+                    let history = HistoryNodeInline {
+                        prev: rule.history_id,
+                        inner: *inner,
+                        pos: pos as u32,
+                        len: inner_rhs.len() as u32,
+                    };
+                    let mut rhs = rule.rhs[..pos].to_vec();
+                    rhs.extend(inner_rhs.iter().cloned());
+                    rhs.extend(rule.rhs[pos + 1..].iter().cloned());
+                    let history_id = grammar.add_history_node(history.into());
+                    substituted.push(CfgRule::new(rule.lhs, rhs, history_id));
+                }
+            }
+            rules = substituted;
+        }
+        for rule in rules {
+            grammar.add_rule(rule.as_rule_ref());
+        }
+    }
+}
+
+/// Replaces every occurrence of `rhs` in right-hand sides with a new nonterminal, which is
+/// returned. Occurrences are found from left to right and do not overlap.
+///
+/// The new rule `N ::= rhs` has `LinkedHistoryNode::Extracted` on top of a new root, since it
+/// stands for parts of many rules. Every rewritten rule has one `LinkedHistoryNode::Extract`
+/// on top of its history for every occurrence, from left to right. Its `pos` refers to the
+/// RHS below it.
+///
+/// # Panics
+///
+/// Panics if `rhs` is empty.
+pub fn extract<G>(grammar: &mut G, rhs: &[Symbol]) -> Symbol
+where
+    G: RuleContainer,
+{
+    assert!(!rhs.is_empty(), "cannot extract an empty RHS");
+    let new_sym = grammar.next_sym();
+    let mut rewritten = vec![];
+    grammar.retain(|rule| {
+        if rule.rhs.windows(rhs.len()).any(|window| window == rhs) {
+            rewritten.push(CfgRule::new(rule.lhs, rule.rhs.to_vec(), rule.history_id));
+            false
+        } else {
+            true
+        }
+    });
+
+    for mut rule in rewritten {
+        let mut pos = 0;
+        while pos + rhs.len() <= rule.rhs.len() {
+            if &rule.rhs[pos..pos + rhs.len()] == rhs {
+                let history = HistoryNodeExtract {
+                    prev: rule.history_id,
+                    pos: pos as u32,
+                    len: rhs.len() as u32,
+                };
+                rule.history_id = grammar.add_history_node(history.into());
+                rule.rhs.splice(pos..pos + rhs.len(), Some(new_sym));
+            }
+            pos += 1;
+        }
+        grammar.add_rule(rule.as_rule_ref());
+    }
+
+    let root = grammar.add_history_node(RootHistoryNode::NoOp.into());
+    let history = HistoryNodeExtracted {
+        prev: root,
+        len: rhs.len() as u32,
+    };
+    let history_id = grammar.add_history_node(history.into());
+    grammar.add_rule(RuleRef {
+        lhs: new_sym,
+        rhs,
+        history_id,
+    });
+    new_sym
+}
+
+/// Renames symbols on both sides of all rules. Symbols that are not keys of `renames` are
+/// kept.
+///
+/// Every rewritten rule has one `LinkedHistoryNode::RenameSymbol` on top of its history for
+/// every renamed symbol it mentions, in the order of symbols.
+pub fn rename<G>(grammar: &mut G, renames: &BTreeMap<Symbol, Symbol>)
+where
+    G: RuleContainer,
+{
+    let renamed = |sym: Symbol| renames.get(&sym).cloned().unwrap_or(sym);
+    let mut rewritten = vec![];
+    grammar.retain(|rule| {
+        if renamed(rule.lhs) == rule.lhs && rule.rhs.iter().all(|&sym| renamed(sym) == sym) {
+            true
+        } else {
+            rewritten.push(CfgRule::new(rule.lhs, rule.rhs.to_vec(), rule.history_id));
+            false
+        }
+    });
+
+    for mut rule in rewritten {
+        let mut syms: Vec<Symbol> = rule
+            .rhs
+            .iter()
+            .cloned()
+            .chain(Some(rule.lhs))
+            .filter(|&sym| renamed(sym) != sym)
+            .collect();
+        syms.sort();
+        syms.dedup();
+        for from in syms {
+            let history = HistoryNodeRenameSymbol {
+                prev: rule.history_id,
+                from,
+                to: renamed(from),
+            };
+            rule.history_id = grammar.add_history_node(history.into());
+        }
+        rule.lhs = renamed(rule.lhs);
+        for sym in &mut rule.rhs {
+            *sym = renamed(*sym);
+        }
+        grammar.add_rule(rule.as_rule_ref());
+    }
+}
+
+/// Merges a set of symbols into its first symbol, which is returned. The language may grow.
+/// See `rename` for the history of rewritten rules.
+///
+/// # Panics
+///
+/// Panics if `syms` is empty.
+pub fn merge<G>(grammar: &mut G, syms: &[Symbol]) -> Symbol
+where
+    G: RuleContainer,
+{
+    let (&into, rest) = syms.split_first().expect("cannot merge an empty set");
+    let renames = rest
+        .iter()
+        .filter(|&&sym| sym != into)
+        .map(|&sym| (sym, into))
+        .collect();
+    rename(grammar, &renames);
+    into
+}
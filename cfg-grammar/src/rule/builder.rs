@@ -2,6 +2,7 @@
 
 use std::convert::AsRef;
 
+use crate::diagnostic::{BuilderError, GrammarError};
 use crate::history::node::{HistoryNodeRhs, LinkedHistoryNode, RootHistoryNode};
 use crate::local_prelude::*;
 use crate::precedenced_rule::PrecedencedRuleBuilder;
@@ -27,7 +28,7 @@ where
         RuleBuilder {
             lhs: None,
             history: None,
-            rules: rules,
+            rules,
         }
     }
 }
@@ -83,6 +84,41 @@ where
         self.rhs_with_history(syms, new_history)
     }
 
+    /// Adds a rule alternative to the grammar, like `rhs`. Returns an error if the LHS was not
+    /// given, in which case nothing is added and the error holds the builder.
+    pub fn try_rhs<S>(self, syms: S) -> Result<Self, BuilderError<Self>>
+    where
+        S: AsRef<[Symbol]>,
+    {
+        let this = self.check_lhs()?;
+        Ok(this.rhs(syms))
+    }
+
+    /// Adds a rule alternative with the given RHS and history to the grammar, like
+    /// `rhs_with_history`. Returns an error if the LHS was not given, in which case nothing is
+    /// added and the error holds the builder.
+    pub fn try_rhs_with_history<Sr>(
+        self,
+        syms: Sr,
+        history_id: HistoryId,
+    ) -> Result<Self, BuilderError<Self>>
+    where
+        Sr: AsRef<[Symbol]>,
+    {
+        let this = self.check_lhs()?;
+        Ok(this.rhs_with_history(syms, history_id))
+    }
+
+    fn check_lhs(self) -> Result<Self, BuilderError<Self>> {
+        match self.lhs {
+            Some(_) => Ok(self),
+            None => Err(BuilderError {
+                error: GrammarError::MissingLhs,
+                builder: self,
+            }),
+        }
+    }
+
     /// Adds a rule alternative with the given RHS and history to the grammar.
     pub fn rhs_with_history<Sr>(mut self, syms: Sr, history_id: HistoryId) -> Self
     where
@@ -16,16 +16,16 @@ impl CfgRule {
     /// Creates a new rule.
     pub fn new(lhs: Symbol, rhs: Vec<Symbol>, history_id: HistoryId) -> Self {
         CfgRule {
-            lhs: lhs,
-            rhs: rhs,
-            history_id: history_id,
+            lhs,
+            rhs,
+            history_id,
         }
     }
 }
 
 impl AsRuleRef for CfgRule {
     // TODO: better fn name?
-    fn as_rule_ref(&self) -> RuleRef {
+    fn as_rule_ref(&self) -> RuleRef<'_> {
         RuleRef {
             lhs: self.lhs,
             rhs: &self.rhs[..],
@@ -33,3 +33,70 @@ impl AsRuleRef for CfgRule {
         }
     }
 }
+
+#[cfg(feature = "serialize")]
+mod serialize {
+    use miniserde::de::{Deserialize, Map, Visitor};
+    use miniserde::ser::{Fragment, Serialize};
+    use miniserde::{make_place, Result};
+
+    use super::CfgRule;
+    use crate::local_prelude::*;
+    use crate::serialize::{history_id, take, Fields};
+
+    make_place!(Place);
+
+    impl Serialize for CfgRule {
+        fn begin(&self) -> Fragment<'_> {
+            Fields::new()
+                .field("lhs", self.lhs)
+                .field("rhs", &self.rhs)
+                .field("history_id", self.history_id.get())
+                .into_fragment()
+        }
+    }
+
+    impl Deserialize for CfgRule {
+        fn begin(out: &mut Option<Self>) -> &mut dyn Visitor {
+            Place::new(out)
+        }
+    }
+
+    impl Visitor for Place<CfgRule> {
+        fn map(&mut self) -> Result<Box<dyn Map + '_>> {
+            Ok(Box::new(CfgRuleBuilder {
+                lhs: None,
+                rhs: None,
+                history_id: None,
+                out: &mut self.out,
+            }))
+        }
+    }
+
+    struct CfgRuleBuilder<'a> {
+        lhs: Option<Symbol>,
+        rhs: Option<Vec<Symbol>>,
+        history_id: Option<usize>,
+        out: &'a mut Option<CfgRule>,
+    }
+
+    impl Map for CfgRuleBuilder<'_> {
+        fn key(&mut self, k: &str) -> Result<&mut dyn Visitor> {
+            match k {
+                "lhs" => Ok(Deserialize::begin(&mut self.lhs)),
+                "rhs" => Ok(Deserialize::begin(&mut self.rhs)),
+                "history_id" => Ok(Deserialize::begin(&mut self.history_id)),
+                _ => Ok(<dyn Visitor>::ignore()),
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            *self.out = Some(CfgRule::new(
+                take(&mut self.lhs)?,
+                take(&mut self.rhs)?,
+                history_id(take(&mut self.history_id)?)?,
+            ));
+            Ok(())
+        }
+    }
+}
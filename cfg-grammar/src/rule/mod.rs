@@ -10,14 +10,14 @@ use crate::local_prelude::*;
 
 /// Trait for rules of a context-free grammar.
 pub trait AsRuleRef {
-    fn as_rule_ref(&self) -> RuleRef;
+    fn as_rule_ref(&self) -> RuleRef<'_>;
 }
 
-impl<'a, R> AsRuleRef for &'a R
+impl<R> AsRuleRef for &R
 where
     R: AsRuleRef,
 {
-    fn as_rule_ref(&self) -> RuleRef {
+    fn as_rule_ref(&self) -> RuleRef<'_> {
         (**self).as_rule_ref()
     }
 }
@@ -34,7 +34,7 @@ pub struct RuleRef<'a> {
 }
 
 impl<'a> AsRuleRef for RuleRef<'a> {
-    fn as_rule_ref(&self) -> RuleRef {
+    fn as_rule_ref(&self) -> RuleRef<'_> {
         *self
     }
 }
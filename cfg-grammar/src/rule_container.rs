@@ -56,6 +56,19 @@ pub trait RuleContainer: Sized {
         Self: 'a;
     // fn rules<'a>(&'a self) -> Self::Rules<'a>;
 
+    /// Returns the rules with the given LHS, each paired with an ID that is unique among the
+    /// grammar's rules. The meaning of the ID is specific to the container, so it should only
+    /// be compared with other IDs from this method. By default, the ID is the rule's position
+    /// in `rules`, and every lookup scans all rules.
+    fn rules_for_lhs<'a>(&'a self, lhs: Symbol) -> impl Iterator<Item = (usize, RuleRef<'a>)>
+    where
+        Self: 'a,
+    {
+        self.rules()
+            .enumerate()
+            .filter(move |(_, rule)| rule.lhs == lhs)
+    }
+
     /// Reverses the grammar.
     fn reverse(&self) -> Self
     where
@@ -71,7 +84,7 @@ pub trait RuleContainer: Sized {
         }
 
         for rule in self.rules() {
-            let mut rhs = rule.rhs.iter().cloned().collect::<Vec<_>>();
+            let mut rhs = rule.rhs.to_vec();
             rhs.reverse();
             let rhs = &rhs[..];
             new_grammar.add_rule(RuleRef { rhs, ..rule });
@@ -80,7 +93,7 @@ pub trait RuleContainer: Sized {
     }
 }
 
-impl<'r, D> RuleContainer for &'r mut D
+impl<D> RuleContainer for &mut D
 where
     D: RuleContainer,
 {
@@ -119,6 +132,13 @@ where
         (**self).rules()
     }
 
+    fn rules_for_lhs<'a>(&'a self, lhs: Symbol) -> impl Iterator<Item = (usize, RuleRef<'a>)>
+    where
+        Self: 'a,
+    {
+        (**self).rules_for_lhs(lhs)
+    }
+
     /// Reverses the grammar.
     fn reverse(&self) -> Self
     where
@@ -134,7 +154,7 @@ where
         }
 
         for rule in self.rules() {
-            let mut rhs = rule.rhs.iter().cloned().collect::<Vec<_>>();
+            let mut rhs = rule.rhs.to_vec();
             rhs.reverse();
             let rhs = &rhs[..];
             new_grammar.add_rule(RuleRef { rhs, ..rule });
@@ -0,0 +1,87 @@
+//! Helpers for serialization with `miniserde`.
+//!
+//! History IDs have no `miniserde` impls of their own, so types that contain them are
+//! serialized by hand. Every history ID is written as its number.
+
+use std::borrow::Cow;
+
+use miniserde::ser::{Fragment, Map, Serialize};
+use miniserde::{Error, Result};
+
+use crate::local_prelude::*;
+use crate::rule::RuleRef;
+
+/// A serializer for a map of named fields, some of which may be computed on the fly.
+pub(crate) struct Fields<'a> {
+    fields: Vec<(&'static str, Box<dyn Serialize + 'a>)>,
+}
+
+struct FieldsIter<F> {
+    fields: F,
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    pub(crate) fn new() -> Self {
+        Fields { fields: vec![] }
+    }
+
+    /// Adds a field.
+    pub(crate) fn field(mut self, key: &'static str, value: impl Serialize + 'a) -> Self {
+        self.fields.push((key, Box::new(value)));
+        self
+    }
+
+    /// Returns a fragment that owns the fields.
+    pub(crate) fn into_fragment(self) -> Fragment<'a> {
+        Fragment::Map(Box::new(FieldsIter {
+            fields: self,
+            pos: 0,
+        }))
+    }
+
+    fn get(&self, pos: usize) -> Option<(Cow<'_, str>, &dyn Serialize)> {
+        let (key, value) = self.fields.get(pos)?;
+        Some((Cow::Borrowed(*key), &**value))
+    }
+}
+
+impl Map for FieldsIter<Fields<'_>> {
+    fn next(&mut self) -> Option<(Cow<'_, str>, &dyn Serialize)> {
+        self.pos += 1;
+        self.fields.get(self.pos - 1)
+    }
+}
+
+impl Map for FieldsIter<&Fields<'_>> {
+    fn next(&mut self) -> Option<(Cow<'_, str>, &dyn Serialize)> {
+        self.pos += 1;
+        self.fields.get(self.pos - 1)
+    }
+}
+
+impl Serialize for Fields<'_> {
+    fn begin(&self) -> Fragment<'_> {
+        Fragment::Map(Box::new(FieldsIter {
+            fields: self,
+            pos: 0,
+        }))
+    }
+}
+
+/// Converts a deserialized number into a history ID.
+pub(crate) fn history_id(id: usize) -> Result<HistoryId> {
+    HistoryId::new(id).ok_or(Error)
+}
+
+/// Checks that a deserialized rule refers to existing symbols and history nodes.
+pub(crate) fn is_valid_rule(rule: RuleRef, num_syms: usize, num_nodes: usize) -> bool {
+    rule.lhs.usize() < num_syms
+        && rule.rhs.iter().all(|sym| sym.usize() < num_syms)
+        && rule.history_id.get() < num_nodes
+}
+
+/// Takes a deserialized field, failing if it was missing.
+pub(crate) fn take<T>(field: &mut Option<T>) -> Result<T> {
+    field.take().ok_or(Error)
+}
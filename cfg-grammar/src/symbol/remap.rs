@@ -1,12 +1,14 @@
 //! Remaps symbols and removes unused symbols.
 
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::iter;
 use std::mem;
 use std::ops;
 
 use cfg_symbol::intern::{Intern, Mapping};
 
+use crate::history::node::HistoryNodeRenameSymbol;
 use crate::local_prelude::*;
 use crate::rule::cfg_rule::CfgRule;
 use crate::AsRuleRef;
@@ -28,7 +30,7 @@ where
     pub fn new(grammar: &'a mut G) -> Self {
         let num_syms = grammar.num_syms();
         Remap {
-            grammar: grammar,
+            grammar,
             mapping: Mapping {
                 to_internal: symbol_iter(num_syms).map(Some).collect(),
                 to_external: symbol_iter(num_syms).collect(),
@@ -44,6 +46,50 @@ where
         self.mapping.translate(&intern.mapping);
     }
 
+    /// Renumbers symbols in the order of a breadth-first traversal from `start`. Rules that
+    /// are unreachable from `start` are removed, along with their symbols.
+    ///
+    /// Alternatives of each nonterminal are visited in the order of their right-hand sides,
+    /// where already numbered symbols come first, by their new IDs. The numbering is therefore
+    /// independent of the initial symbol IDs and of the order of rules, unless alternatives
+    /// differ only in symbols that are not numbered yet. Such ties are broken by the order of
+    /// rules.
+    pub fn canonicalize_symbols(&mut self, start: Symbol) {
+        let mut alternatives: BTreeMap<Symbol, Vec<Vec<Symbol>>> = BTreeMap::new();
+        for rule in self.grammar.rules() {
+            alternatives
+                .entry(rule.lhs)
+                .or_default()
+                .push(rule.rhs.to_vec());
+        }
+        let mut intern = Intern::new(self.grammar.num_syms());
+        let mut queue = VecDeque::new();
+        intern.intern(start);
+        queue.push_back(start);
+        while let Some(lhs) = queue.pop_front() {
+            let mut rhss = alternatives.remove(&lhs).unwrap_or_default();
+            rhss.sort_by_cached_key(|rhs| {
+                rhs.iter()
+                    .map(|sym| {
+                        intern.mapping.to_internal[sym.usize()].map_or(usize::MAX, |s| s.usize())
+                    })
+                    .collect::<Vec<_>>()
+            });
+            for sym in rhss.into_iter().flatten() {
+                if intern.mapping.to_internal[sym.usize()].is_none() {
+                    intern.intern(sym);
+                    queue.push_back(sym);
+                }
+            }
+        }
+
+        self.grammar
+            .retain(|rule| intern.mapping.to_internal[rule.lhs.usize()].is_some());
+        self.remap_symbols(|sym| intern.mapping.to_internal[sym.usize()].unwrap());
+        let _ = mem::replace(self.grammar.sym_source_mut(), intern.source);
+        self.mapping.translate(&intern.mapping);
+    }
+
     /// Remaps symbols to satisfy given ordering constraints. The argument
     /// must be a function that gives total order.
     pub fn reorder_symbols<F>(&mut self, f: F)
@@ -65,6 +111,123 @@ where
         self.remap_symbols(|sym| new_mapping.to_internal[sym.usize()].unwrap());
     }
 
+    /// Merges nonterminals that have identical sets of alternatives, up to renaming of merged
+    /// nonterminals. Returns the number of merged nonterminals.
+    ///
+    /// Equivalence classes are refined until a fixed point, so mutually recursive nonterminals
+    /// such as `A ::= x A` and `B ::= x B` are merged too. Every class is merged into its
+    /// lowest symbol. Rules of other members are removed, along with duplicate rules that
+    /// result from renaming. Other members are freed, so remaining symbols are renumbered, and
+    /// the mapping sends every member to the new ID of the lowest symbol. Each remaining rule
+    /// that mentions a merged symbol gets one `LinkedHistoryNode::RenameSymbol` on top of its
+    /// history for every such symbol. Renames refer to symbol IDs from before merging.
+    pub fn merge_equivalent_nonterminals(&mut self) -> usize {
+        let num_syms = self.grammar.num_syms();
+        let mut is_nonterminal = vec![false; num_syms];
+        for rule in self.grammar.rules() {
+            is_nonterminal[rule.lhs.usize()] = true;
+        }
+        // Terminals are alone in their classes. All nonterminals start in one class.
+        let mut classes: Vec<usize> = (0..num_syms)
+            .map(|i| if is_nonterminal[i] { num_syms } else { i })
+            .collect();
+        let mut num_classes = 0;
+        loop {
+            let mut alternatives: Vec<Vec<Vec<usize>>> = vec![vec![]; num_syms];
+            for rule in self.grammar.rules() {
+                let rhs = rule.rhs.iter().map(|sym| classes[sym.usize()]).collect();
+                alternatives[rule.lhs.usize()].push(rhs);
+            }
+            let mut signatures = HashMap::new();
+            let mut refined = classes.clone();
+            for (sym, mut alternatives) in alternatives.into_iter().enumerate() {
+                if is_nonterminal[sym] {
+                    alternatives.sort();
+                    alternatives.dedup();
+                    let next_class = num_syms + signatures.len();
+                    refined[sym] = *signatures
+                        .entry((classes[sym], alternatives))
+                        .or_insert(next_class);
+                }
+            }
+            classes = refined;
+            if signatures.len() == num_classes {
+                break;
+            }
+            num_classes = signatures.len();
+        }
+
+        let mut representatives = HashMap::new();
+        let merged: Vec<Symbol> = symbol_iter(num_syms)
+            .map(|sym| *representatives.entry(classes[sym.usize()]).or_insert(sym))
+            .collect();
+        let num_merged = symbol_iter(num_syms)
+            .filter(|&sym| merged[sym.usize()] != sym)
+            .count();
+        if num_merged == 0 {
+            return 0;
+        }
+
+        let mut renamed_rules = vec![];
+        let mut kept_rules = HashSet::new();
+        self.grammar.retain(|rule| {
+            if merged[rule.lhs.usize()] != rule.lhs {
+                false
+            } else if rule.rhs.iter().all(|&sym| merged[sym.usize()] == sym) {
+                kept_rules.insert((rule.lhs, rule.rhs.to_vec()));
+                true
+            } else {
+                renamed_rules.push(CfgRule {
+                    lhs: rule.lhs,
+                    rhs: rule.rhs.to_vec(),
+                    history_id: rule.history_id,
+                });
+                false
+            }
+        });
+        for mut rule in renamed_rules {
+            let rhs: Vec<Symbol> = rule.rhs.iter().map(|sym| merged[sym.usize()]).collect();
+            if !kept_rules.insert((rule.lhs, rhs)) {
+                continue;
+            }
+            let mut renamed: Vec<Symbol> = rule
+                .rhs
+                .iter()
+                .cloned()
+                .filter(|&sym| merged[sym.usize()] != sym)
+                .collect();
+            renamed.sort();
+            renamed.dedup();
+            for from in renamed {
+                let history = HistoryNodeRenameSymbol {
+                    prev: rule.history_id,
+                    from,
+                    to: merged[from.usize()],
+                };
+                rule.history_id = self.grammar.add_history_node(history.into());
+            }
+            for sym in &mut rule.rhs {
+                *sym = merged[sym.usize()];
+            }
+            self.grammar.add_rule(rule.as_rule_ref());
+        }
+
+        let mut intern = Intern::new(num_syms);
+        for sym in symbol_iter(num_syms) {
+            if merged[sym.usize()] == sym {
+                intern.intern(sym);
+            }
+        }
+        for sym in symbol_iter(num_syms) {
+            intern.mapping.to_internal[sym.usize()] =
+                intern.mapping.to_internal[merged[sym.usize()].usize()];
+        }
+        self.remap_symbols(|sym| intern.mapping.to_internal[sym.usize()].unwrap());
+        let _ = mem::replace(self.grammar.sym_source_mut(), intern.source);
+        self.mapping.translate(&intern.mapping);
+        num_merged
+    }
+
     // Translates symbols in rules to new symbol IDs.
     fn remap_symbols<F>(&mut self, mut map: F)
     where
@@ -79,7 +79,7 @@ impl SymbolBitSet {
     }
 
     /// Iterates over symbols in the set.
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         Iter {
             iter: self.bit_vec.iter().enumerate(),
         }
@@ -0,0 +1,92 @@
+//! Unit rule elimination.
+//!
+//! A unit rule has the form `A ::= B`, where `B` is a nonterminal. Chains of unit rules appear,
+//! for example, between the levels of precedenced rules.
+
+use std::collections::VecDeque;
+
+use crate::history::node::HistoryNodeEliminateUnitRules;
+use crate::index::GrammarIndex;
+use crate::local_prelude::*;
+use crate::rule::cfg_rule::CfgRule;
+use crate::rule::RuleRef;
+
+/// Removes all unit rules. Every chain of unit rules `A ::= B`, ..., `C ::= D` followed by
+/// a rule `D ::= alpha` is replaced with a rule `A ::= alpha`. The language represented by the
+/// grammar is preserved.
+///
+/// The new rule's history is a `LinkedHistoryNode::EliminateUnitRules` node on top of the
+/// history of `D ::= alpha`. Its `chain` holds the history of every collapsed unit rule, from
+/// `A ::= B` to `C ::= D`, so that the skipped rules can be recovered. The shortest chain is
+/// chosen for every pair of symbols.
+pub fn eliminate_unit_rules<G>(grammar: &mut G)
+where
+    G: RuleContainer,
+{
+    let index = GrammarIndex::new(grammar);
+    eliminate_unit_rules_with(grammar, &index, |grammar, rule| grammar.add_rule(rule));
+}
+
+/// Removes all unit rules, inserting new rules with the given function. The index must
+/// describe the grammar.
+pub(crate) fn eliminate_unit_rules_with<G, F>(grammar: &mut G, index: &GrammarIndex, mut add: F)
+where
+    G: RuleContainer,
+    F: FnMut(&mut G, RuleRef),
+{
+    let rules: Vec<CfgRule> = grammar
+        .rules()
+        .map(|rule| CfgRule::new(rule.lhs, rule.rhs.to_vec(), rule.history_id))
+        .collect();
+    let is_unit = |rhs: &[Symbol]| rhs.len() == 1 && index.is_nonterminal(rhs[0]);
+
+    let mut new_rules = vec![];
+    let mut queue = VecDeque::new();
+    let mut visited = vec![false; grammar.num_syms()];
+    for lhs in index.nonterminals() {
+        visited.fill(false);
+        visited[lhs.usize()] = true;
+        queue.extend(
+            index
+                .rules_for_lhs(lhs)
+                .iter()
+                .map(|&rule_idx| &rules[rule_idx])
+                .filter(|rule| is_unit(&rule.rhs[..]))
+                .map(|rule| (rule.rhs[0], vec![rule.history_id])),
+        );
+        // Breadth-first search finds the shortest chain for every symbol.
+        while let Some((sym, chain)) = queue.pop_front() {
+            if visited[sym.usize()] {
+                continue;
+            }
+            visited[sym.usize()] = true;
+            for &rule_idx in index.rules_for_lhs(sym) {
+                let rule = &rules[rule_idx];
+                if is_unit(&rule.rhs[..]) {
+                    let mut longer_chain = chain.clone();
+                    longer_chain.push(rule.history_id);
+                    queue.push_back((rule.rhs[0], longer_chain));
+                } else {
+                    let history = HistoryNodeEliminateUnitRules {
+                        prev: rule.history_id,
+                        chain: chain.clone(),
+                    };
+                    new_rules.push((lhs, rule.rhs.clone(), history));
+                }
+            }
+        }
+    }
+
+    grammar.retain(|rule| !is_unit(rule.rhs));
+    for (lhs, rhs, history) in new_rules {
+        let history_id = grammar.add_history_node(history.into());
+        add(
+            grammar,
+            RuleRef {
+                lhs,
+                rhs: &rhs[..],
+                history_id,
+            },
+        );
+    }
+}
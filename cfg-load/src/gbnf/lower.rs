@@ -0,0 +1,150 @@
+//! Lowers parsed GBNF definitions into a grammar.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use cfg_grammar::history::node::RootHistoryNode;
+use cfg_grammar::history::Payload;
+use cfg_grammar::{Cfg, HistoryId, RuleContainer};
+use cfg_sequence::destination::SequenceDestination;
+use cfg_sequence::rewrite::SequencesToProductions;
+use cfg_symbol::Symbol;
+
+use super::parse::{Alternative, Definition, Element, Parser};
+use super::{Gbnf, GbnfError, GbnfErrorKind, Terminal};
+use crate::span::SourceSpan;
+
+struct Lower<'a> {
+    source: &'a str,
+    file_name: Option<Arc<str>>,
+    cfg: Cfg,
+    symbols: BTreeMap<&'a str, Symbol>,
+    terminals: BTreeMap<Terminal, Symbol>,
+}
+
+pub(super) fn lower(
+    source: &str,
+    file_name: Option<String>,
+    definitions: &[Definition],
+) -> Result<Gbnf, GbnfError> {
+    let mut lower = Lower {
+        source,
+        file_name: file_name.map(Arc::from),
+        cfg: Cfg::new(),
+        symbols: BTreeMap::new(),
+        terminals: BTreeMap::new(),
+    };
+    // Rules may be referenced before they are defined.
+    let mut names = BTreeMap::new();
+    for definition in definitions {
+        let cfg = &mut lower.cfg;
+        lower
+            .symbols
+            .entry(&definition.name[..])
+            .or_insert_with(|| {
+                let sym = cfg.next_sym();
+                names.insert(sym, definition.name.clone());
+                sym
+            });
+    }
+    let mut definition_spans = BTreeMap::new();
+    for (origin, definition) in definitions.iter().enumerate() {
+        let lhs = lower.symbols[&definition.name[..]];
+        definition_spans
+            .entry(lhs)
+            .or_insert_with(|| lower.source_span(&definition.span));
+        lower.alternatives(lhs, &definition.alternatives, origin)?;
+    }
+    Ok(Gbnf {
+        root: lower.symbols.get("root").cloned(),
+        names,
+        definition_spans,
+        terminals: lower
+            .terminals
+            .into_iter()
+            .map(|(terminal, sym)| (sym, terminal))
+            .collect(),
+        cfg: lower.cfg,
+    })
+}
+
+impl<'a> Lower<'a> {
+    fn alternatives(
+        &mut self,
+        lhs: Symbol,
+        alternatives: &'a [Alternative],
+        origin: usize,
+    ) -> Result<(), GbnfError> {
+        for sequence in alternatives {
+            let payload = Payload::new(self.source_span(&sequence.span));
+            let history_id = self
+                .cfg
+                .add_history_node(RootHistoryNode::Payload { origin, payload }.into());
+            let mut rhs = vec![];
+            for element in &sequence.elements {
+                rhs.extend(self.element(element, origin, history_id)?);
+            }
+            self.cfg.rule(lhs).history(history_id).rhs(rhs);
+        }
+        Ok(())
+    }
+
+    fn source_span(&self, range: &Range<usize>) -> SourceSpan {
+        SourceSpan {
+            file: self.file_name.clone(),
+            ..SourceSpan::from_offsets(self.source, range.start, range.end)
+        }
+    }
+
+    /// Returns the symbol for an element, or `None` if the element matches only the empty
+    /// string.
+    fn element(
+        &mut self,
+        element: &'a Element,
+        origin: usize,
+        history_id: HistoryId,
+    ) -> Result<Option<Symbol>, GbnfError> {
+        match element {
+            Element::Terminal(Terminal::Literal(literal)) if literal.is_empty() => Ok(None),
+            Element::Terminal(terminal) => {
+                let cfg = &mut self.cfg;
+                let sym = *self
+                    .terminals
+                    .entry(terminal.clone())
+                    .or_insert_with(|| cfg.next_sym());
+                Ok(Some(sym))
+            }
+            Element::Name(name, offset) => match self.symbols.get(&name[..]) {
+                Some(&sym) => Ok(Some(sym)),
+                None => Err(Parser::error_at(
+                    self.source,
+                    *offset,
+                    GbnfErrorKind::UndefinedRule(name.clone()),
+                )),
+            },
+            Element::Group(alternatives) => match &alternatives[..] {
+                [sequence] if sequence.elements.len() == 1 => {
+                    self.element(&sequence.elements[0], origin, history_id)
+                }
+                _ => {
+                    let lhs = self.cfg.next_sym();
+                    self.alternatives(lhs, alternatives, origin)?;
+                    Ok(Some(lhs))
+                }
+            },
+            Element::Repeat { element, min, max } => {
+                let rhs = match self.element(element, origin, history_id)? {
+                    Some(rhs) => rhs,
+                    None => return Ok(None),
+                };
+                let lhs = self.cfg.next_sym();
+                SequencesToProductions::new(&mut self.cfg)
+                    .sequence(lhs)
+                    .inclusive(*min, *max)
+                    .rhs_with_history(rhs, Some(history_id));
+                Ok(Some(lhs))
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Reading and writing grammars in the GBNF format of llama.cpp.
+//!
+//! Rule names become nonterminal symbols. Every distinct quoted literal, character class and
+//! the `.` wildcard becomes a single terminal symbol. Groups are given fresh nonterminals, and
+//! repetitions `*`, `+`, `?` and `{m,n}` are rewritten with `SequencesToProductions`.
+//!
+//! Every alternative, including alternatives of groups, gets a `RootHistoryNode::Payload`. Its
+//! `origin` is the index of the rule definition in the source text, and its payload is the
+//! `SourceSpan` of the alternative, including the file name if given. Rules for repetitions are
+//! linked to the root of the alternative they appear in.
+//!
+//! `Gbnf::span` looks up spans. They survive transformations of the grammar, as well as
+//! compaction and serialization of its history, so diagnostics about transformed grammars,
+//! such as cycles and parse table conflicts, can be reported with `Gbnf::report`. Payloads of
+//! other types added later, for example by the user, do not hide the span.
+
+mod lower;
+mod parse;
+mod write;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use cfg_grammar::diagnostic::{ConflictKind, Diagnostic, DiagnosticKind};
+use cfg_grammar::rule::RuleRef;
+use cfg_grammar::{Cfg, HistoryId, RuleContainer};
+use cfg_symbol::intern::Mapping;
+use cfg_symbol::Symbol;
+
+use crate::span::SourceSpan;
+
+/// A grammar loaded from GBNF, together with its symbol names and terminal descriptions.
+#[derive(Clone)]
+pub struct Gbnf {
+    cfg: Cfg,
+    root: Option<Symbol>,
+    names: BTreeMap<Symbol, String>,
+    terminals: BTreeMap<Symbol, Terminal>,
+    definition_spans: BTreeMap<Symbol, SourceSpan>,
+}
+
+/// The text matched by a terminal symbol.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Terminal {
+    /// A quoted literal, such as `"null"`.
+    Literal(String),
+    /// A character class, such as `[a-z_]` or `[^"\\]`.
+    CharClass {
+        /// Whether the class is negated with `^`.
+        negated: bool,
+        /// Inclusive ranges of characters.
+        ranges: Vec<(char, char)>,
+    },
+    /// The `.` wildcard, which matches any character.
+    AnyChar,
+}
+
+/// An error encountered while reading GBNF.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GbnfError {
+    /// The kind of error.
+    pub kind: GbnfErrorKind,
+    /// The line of the error, starting from 1.
+    pub line: usize,
+    /// The column of the error, starting from 1.
+    pub column: usize,
+}
+
+/// The kind of a GBNF error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GbnfErrorKind {
+    /// The input ended unexpectedly.
+    UnexpectedEof,
+    /// An unexpected character was found.
+    UnexpectedChar(char),
+    /// Expected a rule name.
+    ExpectedName,
+    /// Expected `::=` after a rule name.
+    ExpectedDefinition,
+    /// An invalid escape sequence in a literal or a character class.
+    InvalidEscape,
+    /// An invalid repetition count, such as `{3,1}`.
+    InvalidRepetition,
+    /// A reference to a rule that is never defined.
+    UndefinedRule(String),
+}
+
+impl Gbnf {
+    /// Reads a grammar from GBNF source text.
+    pub fn parse(source: &str) -> Result<Self, GbnfError> {
+        let definitions = parse::Parser::new(source).parse_grammar()?;
+        lower::lower(source, None, &definitions)
+    }
+
+    /// Reads a grammar from GBNF source text, which comes from the named file. The name is
+    /// recorded in source spans.
+    pub fn parse_with_file_name(file_name: &str, source: &str) -> Result<Self, GbnfError> {
+        let definitions = parse::Parser::new(source).parse_grammar()?;
+        lower::lower(source, Some(file_name.to_string()), &definitions)
+    }
+
+    /// Returns the source span of the alternative a rule comes from. Rules of the grammar
+    /// and rules obtained from them through transformations are supported.
+    pub fn span(&self, history_id: HistoryId) -> Option<&SourceSpan> {
+        self.cfg
+            .history_graph()
+            .nearest_payload::<SourceSpan>(history_id)
+    }
+
+    /// Returns the source span of the first definition of a named rule.
+    pub fn definition_span(&self, sym: Symbol) -> Option<&SourceSpan> {
+        self.definition_spans.get(&sym)
+    }
+
+    /// Formats a message about a rule, pointing at the rule's source location. The rule is
+    /// written in GBNF with the names of this grammar.
+    ///
+    /// The format is `file:line:column: message: `rule``, where the file and location are
+    /// omitted when unknown.
+    pub fn diagnostic(&self, rule: RuleRef, message: &str) -> String {
+        self.message(Some(rule.history_id), message, Some((rule.lhs, rule.rhs)))
+    }
+
+    /// Formats a diagnostic, such as one about a cycle or a parse table conflict, pointing at
+    /// the source location of its rule. Rules and symbols are written in GBNF with the names
+    /// of this grammar.
+    ///
+    /// The format is `file:line:column: severity: message: `rule``, where the file, location
+    /// and rule are omitted when unknown.
+    pub fn report(&self, diagnostic: &Diagnostic) -> String {
+        let num_syms = self.num_syms(diagnostic.kind.rule());
+        let symbol = |sym: Symbol| write::write_symbol(self, num_syms.max(sym.usize() + 1), sym);
+        let message = match &diagnostic.kind {
+            DiagnosticKind::UselessRule { .. } => "useless rule".to_string(),
+            DiagnosticKind::Cycle { .. } => "rule in a cycle".to_string(),
+            DiagnosticKind::Conflict { conflict, .. } => match *conflict {
+                ConflictKind::Ll(terminal) => format!("LL(1) conflict on {}", symbol(terminal)),
+                ConflictKind::ShiftReduce(terminal) => {
+                    format!("shift/reduce conflict on {}", symbol(terminal))
+                }
+                ConflictKind::ReduceReduce => "reduce/reduce conflict".to_string(),
+            },
+            DiagnosticKind::Lint { lint, sym } => format!("{} `{}`", lint, symbol(*sym)),
+            DiagnosticKind::Error(error) => error.to_string(),
+        };
+        self.message(
+            diagnostic.history_id,
+            &format!("{}: {}", diagnostic.severity, message),
+            diagnostic.kind.rule(),
+        )
+    }
+
+    fn message(
+        &self,
+        history_id: Option<HistoryId>,
+        message: &str,
+        rule: Option<(Symbol, &[Symbol])>,
+    ) -> String {
+        let mut result = String::new();
+        if let Some(span) = history_id.and_then(|history_id| self.span(history_id)) {
+            if let Some(file) = &span.file {
+                result.push_str(&format!("{}:", file));
+            }
+            result.push_str(&format!("{}: ", span.start));
+        }
+        result.push_str(message);
+        if let Some((lhs, rhs)) = rule {
+            let rule_text = write::write_rule(self, self.num_syms(rule), lhs, rhs);
+            result.push_str(&format!(": `{}`", rule_text));
+        }
+        result
+    }
+
+    /// Returns the number of symbols to name, which covers the symbols of `rule`.
+    fn num_syms(&self, rule: Option<(Symbol, &[Symbol])>) -> usize {
+        rule.into_iter()
+            .flat_map(|(lhs, rhs)| rhs.iter().cloned().chain(Some(lhs)))
+            .map(|sym| sym.usize() + 1)
+            .fold(self.cfg.num_syms(), usize::max)
+    }
+
+    /// Returns the grammar.
+    pub fn cfg(&self) -> &Cfg {
+        &self.cfg
+    }
+
+    /// Returns a mutable reference to the grammar.
+    pub fn cfg_mut(&mut self) -> &mut Cfg {
+        &mut self.cfg
+    }
+
+    /// Returns the grammar, discarding symbol names and terminal descriptions.
+    pub fn into_cfg(self) -> Cfg {
+        self.cfg
+    }
+
+    /// Returns the symbol of the rule named `root`, if defined.
+    pub fn root(&self) -> Option<Symbol> {
+        self.root
+    }
+
+    /// Returns the symbol of a named rule.
+    pub fn symbol(&self, name: &str) -> Option<Symbol> {
+        self.names
+            .iter()
+            .find(|&(_, sym_name)| sym_name == name)
+            .map(|(&sym, _)| sym)
+    }
+
+    /// Returns the name of a rule's symbol.
+    pub fn name(&self, sym: Symbol) -> Option<&str> {
+        self.names.get(&sym).map(|name| &name[..])
+    }
+
+    /// Returns the description of a terminal symbol.
+    pub fn terminal(&self, sym: Symbol) -> Option<&Terminal> {
+        self.terminals.get(&sym)
+    }
+
+    /// Iterates over terminal symbols and their descriptions.
+    pub fn terminals(&self) -> impl Iterator<Item = (Symbol, &Terminal)> {
+        self.terminals
+            .iter()
+            .map(|(&sym, terminal)| (sym, terminal))
+    }
+
+    /// Translates symbol names and terminal descriptions after the grammar's symbols were
+    /// remapped, for example with `Remap`. Symbols that were removed lose their names.
+    pub fn translate(&mut self, mapping: &Mapping) {
+        let internal = |sym: Symbol| mapping.to_internal.get(sym.usize()).cloned().flatten();
+        self.root = self.root.and_then(internal);
+        self.names = std::mem::take(&mut self.names)
+            .into_iter()
+            .filter_map(|(sym, name)| internal(sym).map(|sym| (sym, name)))
+            .collect();
+        self.terminals = std::mem::take(&mut self.terminals)
+            .into_iter()
+            .filter_map(|(sym, terminal)| internal(sym).map(|sym| (sym, terminal)))
+            .collect();
+        self.definition_spans = std::mem::take(&mut self.definition_spans)
+            .into_iter()
+            .filter_map(|(sym, span)| internal(sym).map(|sym| (sym, span)))
+            .collect();
+    }
+
+    /// Writes this grammar in GBNF.
+    pub fn to_gbnf(&self) -> String {
+        self.write_grammar(&self.cfg)
+    }
+
+    /// Writes any grammar in GBNF, using the symbol names and terminal descriptions of this
+    /// grammar. Symbols without a name are given generated names.
+    pub fn write_grammar<G>(&self, grammar: &G) -> String
+    where
+        G: cfg_grammar::RuleContainer,
+    {
+        write::write(self, grammar)
+    }
+}
+
+impl fmt::Display for Gbnf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_gbnf())
+    }
+}
+
+impl fmt::Display for GbnfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl fmt::Display for GbnfErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GbnfErrorKind::UnexpectedEof => write!(f, "unexpected end of input"),
+            GbnfErrorKind::UnexpectedChar(ch) => write!(f, "unexpected character {:?}", ch),
+            GbnfErrorKind::ExpectedName => write!(f, "expected a rule name"),
+            GbnfErrorKind::ExpectedDefinition => write!(f, "expected `::=`"),
+            GbnfErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            GbnfErrorKind::InvalidRepetition => write!(f, "invalid repetition count"),
+            GbnfErrorKind::UndefinedRule(name) => write!(f, "undefined rule `{}`", name),
+        }
+    }
+}
+
+impl Error for GbnfError {}
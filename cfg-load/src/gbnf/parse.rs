@@ -0,0 +1,341 @@
+//! Parses GBNF source text into rule definitions.
+
+use std::ops::Range;
+
+use super::{GbnfError, GbnfErrorKind, Terminal};
+use crate::span::Location;
+
+/// A rule definition of the form `name ::= alternatives`.
+pub(super) struct Definition {
+    pub(super) name: String,
+    /// The byte offsets of the definition.
+    pub(super) span: Range<usize>,
+    pub(super) alternatives: Vec<Alternative>,
+}
+
+/// A sequence of elements, which is one of the alternatives of a definition or a group.
+pub(super) struct Alternative {
+    pub(super) elements: Vec<Element>,
+    /// The byte offsets of the sequence.
+    pub(super) span: Range<usize>,
+}
+
+/// An element of a sequence.
+pub(super) enum Element {
+    Terminal(Terminal),
+    /// A reference to a rule, with the offset of its name.
+    Name(String, usize),
+    Group(Vec<Alternative>),
+    Repeat {
+        element: Box<Element>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+/// A recursive descent parser for GBNF.
+pub(super) struct Parser<'a> {
+    source: &'a str,
+    chars: Vec<(usize, char)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub(super) fn new(source: &'a str) -> Self {
+        Parser {
+            source,
+            chars: source.char_indices().collect(),
+            pos: 0,
+        }
+    }
+
+    pub(super) fn parse_grammar(mut self) -> Result<Vec<Definition>, GbnfError> {
+        let mut definitions = vec![];
+        self.skip_space(true);
+        while self.peek().is_some() {
+            definitions.push(self.parse_definition()?);
+            self.skip_space(true);
+        }
+        Ok(definitions)
+    }
+
+    /// Creates an error at the given byte offset.
+    pub(super) fn error_at(source: &str, offset: usize, kind: GbnfErrorKind) -> GbnfError {
+        let Location { line, column } = Location::from_offset(source, offset);
+        GbnfError { kind, line, column }
+    }
+
+    fn error(&self, kind: GbnfErrorKind) -> GbnfError {
+        Self::error_at(self.source, self.offset(), kind)
+    }
+
+    fn unexpected(&self) -> GbnfError {
+        match self.peek() {
+            Some(ch) => self.error(GbnfErrorKind::UnexpectedChar(ch)),
+            None => self.error(GbnfErrorKind::UnexpectedEof),
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.chars
+            .get(self.pos)
+            .map_or(self.source.len(), |&(offset, _)| offset)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, ch)| ch)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).map(|&(_, ch)| ch)
+    }
+
+    fn next(&mut self) -> Result<char, GbnfError> {
+        let ch = self.peek().ok_or_else(|| self.unexpected())?;
+        self.pos += 1;
+        Ok(ch)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skips spaces and comments. Newlines are skipped only if `newline_ok` is set.
+    fn skip_space(&mut self, newline_ok: bool) {
+        while let Some(ch) = self.peek() {
+            match ch {
+                ' ' | '\t' => self.pos += 1,
+                '\r' | '\n' if newline_ok => self.pos += 1,
+                '#' => {
+                    while !matches!(self.peek(), None | Some('\r') | Some('\n')) {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String, GbnfError> {
+        let start = self.pos;
+        while let Some(ch) = self.peek() {
+            if is_name_char(ch) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        if start == self.pos {
+            return Err(self.error(GbnfErrorKind::ExpectedName));
+        }
+        Ok(self.chars[start..self.pos]
+            .iter()
+            .map(|&(_, ch)| ch)
+            .collect())
+    }
+
+    fn parse_definition(&mut self) -> Result<Definition, GbnfError> {
+        let start = self.offset();
+        let name = self.parse_name()?;
+        self.skip_space(false);
+        if !(self.eat(':') && self.eat(':') && self.eat('=')) {
+            return Err(self.error(GbnfErrorKind::ExpectedDefinition));
+        }
+        self.skip_space(true);
+        let alternatives = self.parse_alternatives(false)?;
+        match self.peek() {
+            None | Some('\r') | Some('\n') => Ok(Definition {
+                name,
+                span: start..self.offset(),
+                alternatives,
+            }),
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    /// Parses alternatives separated by `|`. At the top level, a newline ends the sequence, but
+    /// a `|` that starts the next line continues the alternatives.
+    fn parse_alternatives(&mut self, nested: bool) -> Result<Vec<Alternative>, GbnfError> {
+        let mut alternatives = vec![self.parse_sequence(nested)?];
+        loop {
+            let before_space = self.pos;
+            self.skip_space(true);
+            if self.eat('|') {
+                self.skip_space(true);
+                alternatives.push(self.parse_sequence(nested)?);
+            } else {
+                self.pos = before_space;
+                return Ok(alternatives);
+            }
+        }
+    }
+
+    fn parse_sequence(&mut self, nested: bool) -> Result<Alternative, GbnfError> {
+        let mut elements = vec![];
+        let start = self.offset();
+        let mut end = start;
+        loop {
+            match self.peek() {
+                None | Some('|') | Some(')') | Some('\r') | Some('\n') => {
+                    return Ok(Alternative {
+                        elements,
+                        span: start..end,
+                    })
+                }
+                Some(_) => {}
+            }
+            let mut element = self.parse_primary()?;
+            while let Some((min, max)) = self.parse_repetition()? {
+                element = Element::Repeat {
+                    element: Box::new(element),
+                    min,
+                    max,
+                };
+            }
+            elements.push(element);
+            end = self.offset();
+            self.skip_space(nested);
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Element, GbnfError> {
+        match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let mut literal = String::new();
+                while !self.eat('"') {
+                    literal.push(self.parse_char()?);
+                }
+                Ok(Element::Terminal(Terminal::Literal(literal)))
+            }
+            Some('[') => {
+                self.pos += 1;
+                let negated = self.eat('^');
+                let mut ranges = vec![];
+                while !self.eat(']') {
+                    let start = self.parse_char()?;
+                    let end = if self.peek() == Some('-') && self.peek_at(1) != Some(']') {
+                        self.pos += 1;
+                        self.parse_char()?
+                    } else {
+                        start
+                    };
+                    ranges.push((start, end));
+                }
+                Ok(Element::Terminal(Terminal::CharClass { negated, ranges }))
+            }
+            Some('.') => {
+                self.pos += 1;
+                Ok(Element::Terminal(Terminal::AnyChar))
+            }
+            Some('(') => {
+                self.pos += 1;
+                self.skip_space(true);
+                let alternatives = self.parse_alternatives(true)?;
+                self.skip_space(true);
+                if !self.eat(')') {
+                    return Err(self.unexpected());
+                }
+                Ok(Element::Group(alternatives))
+            }
+            Some(ch) if is_name_char(ch) => {
+                let offset = self.offset();
+                Ok(Element::Name(self.parse_name()?, offset))
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Parses a postfix repetition operator, if present.
+    fn parse_repetition(&mut self) -> Result<Option<(u32, Option<u32>)>, GbnfError> {
+        let range = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let offset = self.offset();
+                self.pos += 1;
+                self.skip_space(false);
+                let min = self.parse_number()?;
+                self.skip_space(false);
+                let max = if self.eat(',') {
+                    self.skip_space(false);
+                    if self.peek() == Some('}') {
+                        None
+                    } else {
+                        Some(self.parse_number()?)
+                    }
+                } else {
+                    Some(min)
+                };
+                self.skip_space(false);
+                if !self.eat('}') {
+                    return Err(self.unexpected());
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err(Self::error_at(
+                        self.source,
+                        offset,
+                        GbnfErrorKind::InvalidRepetition,
+                    ));
+                }
+                return Ok(Some((min, max)));
+            }
+            _ => return Ok(None),
+        };
+        self.pos += 1;
+        Ok(Some(range))
+    }
+
+    fn parse_number(&mut self) -> Result<u32, GbnfError> {
+        let start = self.pos;
+        let mut number: u32 = 0;
+        while let Some(digit) = self.peek().and_then(|ch| ch.to_digit(10)) {
+            number = number
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(digit))
+                .ok_or_else(|| self.error(GbnfErrorKind::InvalidRepetition))?;
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.unexpected());
+        }
+        Ok(number)
+    }
+
+    /// Parses a character in a literal or a character class, which may be escaped.
+    fn parse_char(&mut self) -> Result<char, GbnfError> {
+        let (source, offset) = (self.source, self.offset());
+        let invalid = || Self::error_at(source, offset, GbnfErrorKind::InvalidEscape);
+        match self.next()? {
+            '\\' => {
+                let digits = match self.next()? {
+                    'n' => return Ok('\n'),
+                    'r' => return Ok('\r'),
+                    't' => return Ok('\t'),
+                    'x' => 2,
+                    'u' => 4,
+                    'U' => 8,
+                    ch @ ('\\' | '"' | '[' | ']' | '-' | '^') => return Ok(ch),
+                    _ => return Err(invalid()),
+                };
+                let mut value = 0;
+                for _ in 0..digits {
+                    let digit = self.next()?.to_digit(16).ok_or_else(invalid)?;
+                    value = value * 16 + digit;
+                }
+                char::from_u32(value).ok_or_else(invalid)
+            }
+            ch => Ok(ch),
+        }
+    }
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '-'
+}
@@ -0,0 +1,153 @@
+//! Writes grammars in GBNF.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use cfg_grammar::RuleContainer;
+use cfg_symbol::Symbol;
+
+use super::{Gbnf, Terminal};
+
+pub(super) fn write<G>(gbnf: &Gbnf, grammar: &G) -> String
+where
+    G: RuleContainer,
+{
+    // Group alternatives by LHS, in order of first appearance.
+    let mut order = vec![];
+    let mut alternatives: BTreeMap<Symbol, Vec<&[Symbol]>> = BTreeMap::new();
+    for rule in grammar.rules() {
+        alternatives
+            .entry(rule.lhs)
+            .or_insert_with(|| {
+                order.push(rule.lhs);
+                vec![]
+            })
+            .push(rule.rhs);
+    }
+    // The root rule goes first.
+    if let Some(pos) = order.iter().position(|&lhs| Some(lhs) == gbnf.root) {
+        let root = order.remove(pos);
+        order.insert(0, root);
+    }
+
+    let names = names(gbnf, grammar.num_syms());
+    let mut result = String::new();
+    for lhs in order {
+        result.push_str(&names[lhs.usize()]);
+        result.push_str(" ::=");
+        for (i, rhs) in alternatives[&lhs].iter().enumerate() {
+            if i != 0 {
+                result.push_str(" |");
+            }
+            write_rhs(&mut result, gbnf, &names, rhs);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Writes a single rule in GBNF, without a trailing newline.
+pub(super) fn write_rule(gbnf: &Gbnf, num_syms: usize, lhs: Symbol, rhs: &[Symbol]) -> String {
+    let names = names(gbnf, num_syms);
+    let mut result = format!("{} ::=", names[lhs.usize()]);
+    write_rhs(&mut result, gbnf, &names, rhs);
+    result
+}
+
+/// Writes a single symbol in GBNF.
+pub(super) fn write_symbol(gbnf: &Gbnf, num_syms: usize, sym: Symbol) -> String {
+    let names = names(gbnf, num_syms);
+    let mut result = String::new();
+    write_rhs(&mut result, gbnf, &names, &[sym]);
+    // Drop the leading space.
+    result.split_off(1)
+}
+
+fn write_rhs(result: &mut String, gbnf: &Gbnf, names: &[String], rhs: &[Symbol]) {
+    if rhs.is_empty() {
+        result.push_str(" \"\"");
+    }
+    for &sym in rhs {
+        result.push(' ');
+        match gbnf.terminals.get(&sym) {
+            Some(terminal) => write_terminal(result, terminal),
+            None => result.push_str(&names[sym.usize()]),
+        }
+    }
+}
+
+/// Assigns a unique name to every symbol.
+fn names(gbnf: &Gbnf, num_syms: usize) -> Vec<String> {
+    let taken: BTreeSet<&str> = gbnf.names.values().map(|name| &name[..]).collect();
+    let mut generated = vec![];
+    for id in 0..num_syms {
+        if !gbnf.names.contains_key(&Symbol::from(id)) {
+            let mut name = format!("sym-{}", id);
+            while taken.contains(&name[..]) {
+                name.push_str("-x");
+            }
+            generated.push((id, name));
+        }
+    }
+    let mut result = vec![String::new(); num_syms];
+    for (&sym, name) in &gbnf.names {
+        if let Some(slot) = result.get_mut(sym.usize()) {
+            slot.clone_from(name);
+        }
+    }
+    for (id, name) in generated {
+        result[id] = name;
+    }
+    result
+}
+
+fn write_terminal(result: &mut String, terminal: &Terminal) {
+    match terminal {
+        Terminal::Literal(literal) => {
+            result.push('"');
+            for ch in literal.chars() {
+                write_char(result, ch, &['"', '\\']);
+            }
+            result.push('"');
+        }
+        Terminal::CharClass { negated, ranges } => {
+            result.push('[');
+            if *negated {
+                result.push('^');
+            }
+            let special = &['\\', ']', '[', '-', '^'];
+            for &(start, end) in ranges {
+                write_char(result, start, special);
+                if start != end {
+                    result.push('-');
+                    write_char(result, end, special);
+                }
+            }
+            result.push(']');
+        }
+        Terminal::AnyChar => result.push('.'),
+    }
+}
+
+fn write_char(result: &mut String, ch: char, special: &[char]) {
+    match ch {
+        '\n' => result.push_str("\\n"),
+        '\r' => result.push_str("\\r"),
+        '\t' => result.push_str("\\t"),
+        ch if special.contains(&ch) => {
+            result.push('\\');
+            result.push(ch);
+        }
+        ch if ch.is_control() => {
+            let code = ch as u32;
+            if code <= 0xff {
+                write!(result, "\\x{:02X}", code).unwrap();
+            } else if code <= 0xffff {
+                write!(result, "\\u{:04X}", code).unwrap();
+            } else {
+                write!(result, "\\U{:08X}", code).unwrap();
+            }
+        }
+        ch => result.push(ch),
+    }
+}
@@ -0,0 +1,4 @@
+//! Loading grammars from text formats, and writing them back.
+
+pub mod gbnf;
+pub mod span;
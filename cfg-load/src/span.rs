@@ -0,0 +1,124 @@
+//! Locations in source text.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use cfg_grammar::history::PayloadValue;
+
+/// A position in source text.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Location {
+    /// The line, starting from 1.
+    pub line: usize,
+    /// The column, starting from 1. Columns count characters, not bytes.
+    pub column: usize,
+}
+
+/// A range of source text. The end is exclusive.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SourceSpan {
+    /// The name of the file, if known.
+    pub file: Option<Arc<str>>,
+    /// The first character.
+    pub start: Location,
+    /// The position after the last character.
+    pub end: Location,
+}
+
+/// An error from parsing a location or a span that is not formatted like `Display` does.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseSpanError;
+
+impl Location {
+    /// Finds the location of a byte offset in `source`.
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let column = before[line_start..].chars().count() + 1;
+        Location { line, column }
+    }
+}
+
+impl SourceSpan {
+    /// Finds the span of a range of byte offsets in `source`. The file is not set.
+    pub fn from_offsets(source: &str, start: usize, end: usize) -> Self {
+        SourceSpan {
+            file: None,
+            start: Location::from_offset(source, start),
+            end: Location::from_offset(source, end),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl FromStr for Location {
+    type Err = ParseSpanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (line, column) = s.split_once(':').ok_or(ParseSpanError)?;
+        Ok(Location {
+            line: line.parse().map_err(|_| ParseSpanError)?,
+            column: column.parse().map_err(|_| ParseSpanError)?,
+        })
+    }
+}
+
+impl FromStr for SourceSpan {
+    type Err = ParseSpanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The file name may contain any character, so the locations are split off the end.
+        let (start, end) = s.rsplit_once('-').ok_or(ParseSpanError)?;
+        let (rest, column) = start.rsplit_once(':').ok_or(ParseSpanError)?;
+        let (file, line) = match rest.rsplit_once(':') {
+            Some((file, line)) => (Some(file.into()), line),
+            None => (None, rest),
+        };
+        Ok(SourceSpan {
+            file,
+            start: Location {
+                line: line.parse().map_err(|_| ParseSpanError)?,
+                column: column.parse().map_err(|_| ParseSpanError)?,
+            },
+            end: end.parse()?,
+        })
+    }
+}
+
+/// Spans are kept in rule histories as payloads.
+impl PayloadValue for SourceSpan {
+    const KEY: &'static str = "cfg_load::SourceSpan";
+
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        text.parse().ok()
+    }
+}
+
+impl fmt::Display for ParseSpanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid source span")
+    }
+}
+
+impl Error for ParseSpanError {}
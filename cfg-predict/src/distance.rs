@@ -27,8 +27,8 @@ where
             .map(|rule| (rule.history_id, vec![None; rule.rhs.len() + 1]))
             .collect();
         MinimalDistance {
-            grammar: grammar,
-            distances: distances,
+            grammar,
+            distances,
             prediction_distances: vec![None; grammar.num_syms()],
             completion_distances: vec![None; grammar.num_syms()],
             min_of: vec![None; grammar.num_syms()],
@@ -71,7 +71,7 @@ where
             let mut history = &self.grammar.history_graph()[rule.history_id.get()];
             let mut positions = &[][..];
             while let &HistoryNode::Linked { prev, ref node } = history {
-                if let &LinkedHistoryNode::Distances { ref events } = node {
+                if let LinkedHistoryNode::Distances { events } = node {
                     positions = &events[..];
                 }
                 history = &self.grammar.history_graph()[prev.get()];
@@ -78,7 +78,7 @@ where
 
     fn rule(&mut self, lhs: Symbol, rhs: &[Symbol]) -> bool {
         self.first_set_collect(rhs);
-        let first_set = self.map.entry(lhs).or_insert_with(BTreeSet::new);
+        let first_set = self.map.entry(lhs).or_default();
         let prev_cardinality = first_set.len();
         first_set.extend(self.lookahead.iter().cloned());
         self.lookahead.clear();
@@ -1,11 +1,11 @@
 //! FOLLOW sets.
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
 
 use cfg_symbol::Symbol;
 
-use cfg_grammar::symbol::set::SymbolBitSet;
-use cfg_grammar::RuleContainer;
+use cfg_grammar::diagnostic::GrammarError;
+use cfg_grammar::{GrammarIndex, RuleContainer};
 
 use super::{PerSymbolSets, PredictSets};
 
@@ -18,7 +18,28 @@ pub struct FollowSets {
 impl FollowSets {
     /// Compute all FOLLOW sets of the grammar.
     /// Returns FollowSets.
-    pub fn new<'a, G>(grammar: &'a G, start_sym: Symbol, first_sets: &PerSymbolSets) -> Self
+    ///
+    /// # Panics
+    ///
+    /// Panics if `first_sets` lacks a nonterminal of the grammar.
+    pub fn new<G>(grammar: &G, start_sym: Symbol, first_sets: &PerSymbolSets) -> Self
+    where
+        G: RuleContainer,
+    {
+        match Self::try_new(grammar, start_sym, first_sets) {
+            Ok(this) => this,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Compute all FOLLOW sets of the grammar, like `new`. Returns an error if `first_sets`
+    /// lacks a nonterminal of the grammar, for example when they were computed for another
+    /// grammar.
+    pub fn try_new<G>(
+        grammar: &G,
+        start_sym: Symbol,
+        first_sets: &PerSymbolSets,
+    ) -> Result<Self, GrammarError>
     where
         G: RuleContainer,
     {
@@ -27,21 +48,21 @@ impl FollowSets {
         };
 
         for rule in grammar.rules() {
-            let follow_set = this.map.entry(rule.lhs).or_insert_with(BTreeSet::new);
+            let follow_set = this.map.entry(rule.lhs).or_default();
             if rule.lhs == start_sym {
                 follow_set.insert(None);
             }
         }
 
+        let index = GrammarIndex::new(grammar);
         let mut changed = true;
         while changed {
             changed = false;
-            let terminal_set = SymbolBitSet::terminal_set(grammar);
             for rule in grammar.rules() {
                 let mut follow_set = this.map.get(&rule.lhs).unwrap().clone();
 
                 for &sym in rule.rhs.iter().rev() {
-                    if terminal_set.has_sym(sym) {
+                    if !index.is_nonterminal(sym) {
                         follow_set.clear();
                         follow_set.insert(Some(sym));
                     } else {
@@ -50,7 +71,9 @@ impl FollowSets {
                         followed.extend(follow_set.iter().cloned());
                         changed |= prev_cardinality != followed.len();
 
-                        let first_set = first_sets.get(&sym).unwrap();
+                        let first_set = first_sets
+                            .get(&sym)
+                            .ok_or(GrammarError::MissingFirstSet(sym))?;
                         if !first_set.contains(&None) {
                             follow_set.clear();
                         }
@@ -60,7 +83,7 @@ impl FollowSets {
             }
         }
 
-        this
+        Ok(this)
     }
 }
 
@@ -4,6 +4,7 @@ use std::ops::{Bound, RangeBounds};
 
 use crate::destination::SequenceDestination;
 use crate::{Separator, Sequence};
+use cfg_grammar::diagnostic::{BuilderError, GrammarError};
 use cfg_grammar::history::HistoryId;
 use cfg_symbol::Symbol;
 
@@ -101,16 +102,60 @@ where
     }
 
     /// Adds a sequence rule to the grammar.
-    pub fn rhs_with_history(mut self, rhs: Symbol, history_id: Option<HistoryId>) -> Self {
-        let (start, end) = self.range.take().expect("expected inclusive(n, m)");
-        self.destination.add_sequence(Sequence {
-            lhs: self.lhs.unwrap(),
+    ///
+    /// # Panics
+    ///
+    /// Panics if the LHS or the range was not given.
+    pub fn rhs_with_history(self, rhs: Symbol, history_id: Option<HistoryId>) -> Self {
+        match self.try_rhs_with_history(rhs, history_id) {
+            Ok(this) => this,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Adds a sequence rule to the grammar, like `rhs`. Returns an error if the LHS or the
+    /// range was not given, in which case nothing is added and the error holds the builder.
+    pub fn try_rhs(self, rhs: Symbol) -> Result<Self, BuilderError<Self>> {
+        let mut this = self.check()?;
+        let history = this.history.take().or(this.default_history);
+        this.try_rhs_with_history(rhs, history)
+    }
+
+    /// Adds a sequence rule to the grammar, like `rhs_with_history`. Returns an error if the
+    /// LHS or the range was not given, in which case nothing is added and the error holds the
+    /// builder.
+    pub fn try_rhs_with_history(
+        self,
+        rhs: Symbol,
+        history_id: Option<HistoryId>,
+    ) -> Result<Self, BuilderError<Self>> {
+        let mut this = self.check()?;
+        let (lhs, (start, end)) = match (this.lhs, this.range.take()) {
+            (Some(lhs), Some(range)) => (lhs, range),
+            _ => unreachable!(),
+        };
+        this.destination.add_sequence(Sequence {
+            lhs,
             rhs,
             start,
             end,
-            separator: self.separator,
+            separator: this.separator,
             history_id,
         });
-        self
+        Ok(this)
+    }
+
+    fn check(self) -> Result<Self, BuilderError<Self>> {
+        let error = if self.lhs.is_none() {
+            GrammarError::MissingLhs
+        } else if self.range.is_none() {
+            GrammarError::MissingRange
+        } else {
+            return Ok(self);
+        };
+        Err(BuilderError {
+            error,
+            builder: self,
+        })
     }
 }
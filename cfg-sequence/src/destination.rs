@@ -1,5 +1,6 @@
 //! Sequence destination.
 
+use cfg_grammar::BTreeCfg;
 use cfg_symbol::Symbol;
 
 use super::{builder::SequenceRuleBuilder, Sequence};
@@ -15,7 +16,7 @@ pub trait SequenceDestination: Sized {
     }
 }
 
-impl<'a> SequenceDestination for &'a mut Vec<Sequence> {
+impl SequenceDestination for &mut Vec<Sequence> {
     fn add_sequence(&mut self, seq: Sequence) {
         self.push(seq);
     }
@@ -25,3 +26,9 @@ impl<'a> SequenceDestination for &'a mut Vec<Sequence> {
         SequenceRuleBuilder::new(self).sequence(lhs)
     }
 }
+
+impl SequenceDestination for &mut BTreeCfg {
+    fn add_sequence(&mut self, seq: Sequence) {
+        BTreeCfg::add_sequence(self, seq);
+    }
+}
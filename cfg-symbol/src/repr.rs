@@ -37,10 +37,10 @@ impl From<SymbolRepr> for Symbol {
     }
 }
 
-impl Into<SymbolRepr> for Symbol {
+impl From<Symbol> for SymbolRepr {
     #[inline]
-    fn into(self) -> SymbolRepr {
-        self.0.get().wrapping_sub(1)
+    fn from(sym: Symbol) -> Self {
+        sym.0.get().wrapping_sub(1)
     }
 }
 
@@ -59,10 +59,10 @@ impl From<usize> for Symbol {
     }
 }
 
-impl Into<usize> for Symbol {
+impl From<Symbol> for usize {
     #[inline]
-    fn into(self) -> usize {
-        let id: SymbolRepr = self.into();
+    fn from(sym: Symbol) -> Self {
+        let id: SymbolRepr = sym.into();
         id as usize
     }
 }
@@ -70,7 +70,7 @@ impl Into<usize> for Symbol {
 #[cfg(feature = "serialize")]
 impl Visitor for Place<Symbol> {
     fn nonnegative(&mut self, n: u64) -> Result<()> {
-        if n < ::std::u32::MAX as u64 {
+        if n < u32::MAX as u64 {
             self.out = Some((n as SymbolRepr).into());
             Ok(())
         } else {
@@ -88,7 +88,7 @@ impl Deserialize for Symbol {
 
 #[cfg(feature = "serialize")]
 impl Serialize for Symbol {
-    fn begin(&self) -> miniserde::ser::Fragment {
+    fn begin(&self) -> miniserde::ser::Fragment<'_> {
         let n: u32 = (*self).into();
         miniserde::ser::Fragment::U64(n as u64)
     }
@@ -7,6 +7,10 @@ use crate::*;
 /// A source of numeric symbols.
 #[allow(missing_copy_implementations)]
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(miniserde::Serialize, miniserde::Deserialize)
+)]
 pub struct SymbolSource {
     next_id: SymbolRepr,
 }
@@ -27,7 +31,7 @@ impl SymbolSource {
     /// Generates a new unique symbol.
     pub fn next_sym(&mut self) -> Symbol {
         let ret = self.next_id.into();
-        self.next_id = self.next_id + 1;
+        self.next_id += 1;
         debug_assert_ne!(self.next_id, NULL_ID, "ran out of Symbol space?");
         ret
     }
@@ -36,7 +40,7 @@ impl SymbolSource {
         self.next_id as usize
     }
     /// Returns an iterator that generates symbols.
-    pub fn generate(&mut self) -> Generate {
+    pub fn generate(&mut self) -> Generate<'_> {
         Generate { source: self }
     }
 }
@@ -1,3 +1,5 @@
+mod macros;
+
 #[cfg(feature = "cfg-classify")]
 pub use cfg_classify as classify;
 #[cfg(feature = "cfg-earley")]
@@ -5,6 +7,8 @@ pub use cfg_earley as earley;
 #[cfg(feature = "cfg-generate")]
 pub use cfg_generate as generate;
 pub use cfg_grammar::*;
+#[cfg(feature = "cfg-load")]
+pub use cfg_load as load;
 #[cfg(feature = "cfg-predict")]
 pub use cfg_predict as predict;
 #[cfg(feature = "cfg-sequence")]
@@ -0,0 +1,125 @@
+/// Declares a grammar with named symbols in a BNF-like syntax.
+///
+/// The macro returns a `Cfg` together with a struct that has a field for every declared
+/// symbol. Symbols are created in the order of declaration, and rules are added in the order
+/// in which they appear.
+///
+/// ```
+/// let (grammar, syms) = cfg::grammar! {
+///     symbols { start, expr, num, plus, mul, l_paren, r_paren }
+///
+///     start ::= expr | ;
+///     // Alternatives separated with `|>` have lower precedence.
+///     precedence expr ::=
+///         num | #[assoc(Group)] l_paren expr r_paren
+///         |> expr mul expr
+///         |> expr plus expr;
+/// };
+/// assert!(grammar.num_syms() > 7);
+/// assert_ne!(syms.start, syms.expr);
+/// ```
+///
+/// Rules of the form `lhs ::= a b | c;` are built with `RuleBuilder`, and rules that start with
+/// `precedence` with `PrecedencedRuleBuilder`. An alternative in a precedenced rule may be
+/// prefixed with `#[assoc(Left)]`, `#[assoc(Right)]` or `#[assoc(Group)]`. An empty alternative
+/// is written as nothing, as in `empty ::= ;` or `opt ::= | a;`.
+///
+/// Sequence rules such as `list ::= num+ % comma;` take `*`, `+` or a range such as `{1, 5}`,
+/// and an optional separator. They are rewritten with `SequencesToProductions`, which requires
+/// the `cfg-sequence` feature. The separator is either a symbol, which is a `Proper` separator,
+/// or one of `Proper(sym)`, `Trailing(sym)` and `Liberal(sym)`.
+#[macro_export]
+macro_rules! grammar {
+    (symbols { $($sym:ident),* $(,)? } $($rules:tt)*) => {{
+        #[allow(dead_code)]
+        struct Symbols {
+            $($sym: $crate::Symbol,)*
+        }
+        let mut grammar = $crate::Cfg::new();
+        let [$($sym),*] = grammar.sym();
+        $crate::grammar!(@rules grammar; $($rules)*);
+        (grammar, Symbols { $($sym,)* })
+    }};
+
+    (@rules $g:ident;) => {};
+    (@rules $g:ident; precedence $lhs:ident ::= $($rest:tt)*) => {
+        $crate::grammar!(
+            @alternatives $g; (.finalize())
+            [$crate::RuleContainer::precedenced_rule(&mut $g, $lhs)]
+            []
+            $($rest)*
+        );
+    };
+    (@rules $g:ident; $lhs:ident ::= $elem:ident * $(% $sep:tt $(($sep_sym:ident))?)? ; $($rest:tt)*) => {
+        $crate::grammar!(@sequence $g; $lhs, $elem, 0.., $($sep $(($sep_sym))?)?);
+        $crate::grammar!(@rules $g; $($rest)*);
+    };
+    (@rules $g:ident; $lhs:ident ::= $elem:ident + $(% $sep:tt $(($sep_sym:ident))?)? ; $($rest:tt)*) => {
+        $crate::grammar!(@sequence $g; $lhs, $elem, 1.., $($sep $(($sep_sym))?)?);
+        $crate::grammar!(@rules $g; $($rest)*);
+    };
+    (@rules $g:ident; $lhs:ident ::= $elem:ident { $($range:tt)* } $(% $sep:tt $(($sep_sym:ident))?)? ; $($rest:tt)*) => {
+        $crate::grammar!(
+            @sequence $g;
+            $lhs,
+            $elem,
+            $crate::grammar!(@range $($range)*),
+            $($sep $(($sep_sym))?)?
+        );
+        $crate::grammar!(@rules $g; $($rest)*);
+    };
+    (@rules $g:ident; $lhs:ident ::= $($rest:tt)*) => {
+        $crate::grammar!(
+            @alternatives $g; ()
+            [$crate::RuleContainer::rule(&mut $g, $lhs)]
+            []
+            $($rest)*
+        );
+    };
+
+    // Alternatives are munched one token at a time.
+    (@alternatives $g:ident; ($($finish:tt)*) [$($chain:tt)*] [$($cur:ident)*] | > $($rest:tt)*) => {
+        $crate::grammar!(
+            @alternatives $g; ($($finish)*)
+            [$($chain)* .rhs([$($cur),*]).lower_precedence()]
+            []
+            $($rest)*
+        );
+    };
+    (@alternatives $g:ident; ($($finish:tt)*) [$($chain:tt)*] [$($cur:ident)*] | $($rest:tt)*) => {
+        $crate::grammar!(@alternatives $g; ($($finish)*) [$($chain)* .rhs([$($cur),*])] [] $($rest)*);
+    };
+    (@alternatives $g:ident; ($($finish:tt)*) [$($chain:tt)*] [] #[assoc($assoc:ident)] $($rest:tt)*) => {
+        $crate::grammar!(
+            @alternatives $g; ($($finish)*)
+            [$($chain)* .associativity($crate::precedenced_rule::Associativity::$assoc)]
+            []
+            $($rest)*
+        );
+    };
+    (@alternatives $g:ident; ($($finish:tt)*) [$($chain:tt)*] [$($cur:ident)*] $sym:ident $($rest:tt)*) => {
+        $crate::grammar!(@alternatives $g; ($($finish)*) [$($chain)*] [$($cur)* $sym] $($rest)*);
+    };
+    (@alternatives $g:ident; ($($finish:tt)*) [$($chain:tt)*] [$($cur:ident)*] ; $($rest:tt)*) => {
+        $($chain)*.rhs([$($cur),*]) $($finish)*;
+        $crate::grammar!(@rules $g; $($rest)*);
+    };
+
+    (@sequence $g:ident; $lhs:ident, $elem:ident, $range:expr, $($sep:tt)*) => {
+        $crate::sequence::builder::SequenceRuleBuilder::new(
+            $crate::sequence::rewrite::SequencesToProductions::new(&mut $g),
+        )
+        .sequence($lhs)
+        .separator($crate::grammar!(@separator $($sep)*))
+        .range($range)
+        .rhs($elem);
+    };
+
+    (@separator) => { $crate::sequence::Separator::Null };
+    (@separator $kind:ident($sep:ident)) => { $crate::sequence::Separator::$kind($sep) };
+    (@separator $sep:ident) => { $crate::sequence::Separator::Proper($sep) };
+
+    (@range $min:literal) => { $min..=$min };
+    (@range $min:literal ,) => { $min.. };
+    (@range $min:literal , $max:literal) => { $min..=$max };
+}
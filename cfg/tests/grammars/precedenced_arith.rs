@@ -23,7 +23,7 @@ pub fn grammar() -> Grammar {
         .rhs([lparen, sum, rparen])
         .rhs([number]);
     for sym in bnf.sym::<10>() {
-        bnf.rule(number).rhs(&[sym, number]).rhs(&[sym]);
+        bnf.rule(number).rhs([sym, number]).rhs([sym]);
     }
     bnf.set_start(sum);
     bnf
@@ -56,7 +56,7 @@ pub fn weighted_grammar() -> (Cfg, Symbol, BTreeMap<Symbol, char>, Symbol) {
         .rhs_with_linked_history([], weight(1.0));
     for ch in '0'..='9' {
         let [sym] = bnf.sym();
-        bnf.rule(number).rhs(&[sym, number]).rhs(&[sym]);
+        bnf.rule(number).rhs([sym, number]).rhs([sym]);
         map.insert(sym, ch);
     }
     (bnf, sum, map, neg)
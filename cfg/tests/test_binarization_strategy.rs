@@ -0,0 +1,232 @@
+mod support;
+
+use cfg::binarized_cfg::BinarizationStrategy;
+use cfg::history::node::LinkedHistoryNode;
+use cfg::{BinarizedCfg, Cfg, HistoryNode, RuleContainer};
+
+fn grammar() -> Cfg {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, d, x] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, b, c, d])
+        .rhs([a, b, c, x])
+        .rhs([x, b, c, d])
+        .rhs([a, b]);
+    cfg
+}
+
+#[test]
+fn test_right_branching() {
+    let cfg = grammar();
+    let binarized = cfg.binarize_with(BinarizationStrategy::RightBranching);
+
+    let mut expected = BinarizedCfg::new();
+    let [start, a, b, c, d, x] = expected.sym();
+    let [g0, g1, g2, g3, g4, g5] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(start)
+        .rhs([a, g0])
+        .rule(g0)
+        .rhs([b, g1])
+        .rule(g1)
+        .rhs([c, d])
+        .rule(start)
+        .rhs([a, g2])
+        .rule(g2)
+        .rhs([b, g3])
+        .rule(g3)
+        .rhs([c, x])
+        .rule(start)
+        .rhs([x, g4])
+        .rule(g4)
+        .rhs([b, g5])
+        .rule(g5)
+        .rhs([c, d])
+        .rule(start)
+        .rhs([a, b]);
+    support::assert_eq_rules(binarized.rules(), expected.rules());
+
+    let depths: Vec<_> = binarized
+        .rules()
+        .map(
+            |rule| match &binarized.history_graph()[rule.history_id.get()] {
+                HistoryNode::Linked {
+                    node: LinkedHistoryNode::BinarizeRight { depth },
+                    ..
+                } => *depth,
+                HistoryNode::Linked {
+                    node: LinkedHistoryNode::Binarize { depth },
+                    ..
+                } => *depth,
+                _ => panic!("expected binarization"),
+            },
+        )
+        .collect();
+    assert_eq!(depths, vec![0, 1, 2, 0, 1, 2, 0, 1, 2, 0]);
+}
+
+#[test]
+fn test_shared_prefix() {
+    let cfg = grammar();
+    let binarized = cfg.binarize_with(BinarizationStrategy::SharedPrefix);
+
+    let mut expected = BinarizedCfg::new();
+    let [start, a, b, c, d, x] = expected.sym();
+    let [ab, abc, xb, xbc] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(ab)
+        .rhs([a, b])
+        .rule(abc)
+        .rhs([ab, c])
+        .rule(start)
+        .rhs([abc, d])
+        .rhs([abc, x])
+        .rule(xb)
+        .rhs([x, b])
+        .rule(xbc)
+        .rhs([xb, c])
+        .rule(start)
+        .rhs([xbc, d])
+        .rhs([a, b]);
+    support::assert_eq_rules(binarized.rules(), expected.rules());
+    let shared: Vec<_> = binarized
+        .rules()
+        .filter_map(
+            |rule| match &binarized.history_graph()[rule.history_id.get()] {
+                HistoryNode::Linked {
+                    node: LinkedHistoryNode::BinarizeShared { len, suffix, rules },
+                    ..
+                } => Some((*len, *suffix, rules.len())),
+                _ => None,
+            },
+        )
+        .collect();
+    // Order is significant.
+    assert_eq!(
+        shared,
+        vec![(2, false, 2), (3, false, 2), (2, false, 1), (3, false, 1)]
+    );
+}
+
+#[test]
+fn test_shared_suffix() {
+    let cfg = grammar();
+    let binarized = cfg.binarize_with(BinarizationStrategy::SharedSuffix);
+
+    let mut expected = BinarizedCfg::new();
+    let [start, a, b, c, d, x] = expected.sym();
+    let [cd, bcd, cx, bcx] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(cd)
+        .rhs([c, d])
+        .rule(bcd)
+        .rhs([b, cd])
+        .rule(start)
+        .rhs([a, bcd])
+        .rule(cx)
+        .rhs([c, x])
+        .rule(bcx)
+        .rhs([b, cx])
+        .rule(start)
+        .rhs([a, bcx])
+        .rhs([x, bcd])
+        .rhs([a, b]);
+    support::assert_eq_rules(binarized.rules(), expected.rules());
+}
+
+#[test]
+fn test_left_branching_is_default() {
+    let cfg = grammar();
+    support::assert_eq_rules(
+        cfg.binarize().rules(),
+        cfg.binarize_with(BinarizationStrategy::LeftBranching)
+            .rules(),
+    );
+}
+
+#[cfg(feature = "cfg-earley")]
+#[test]
+fn test_binarization_strategy_dots() {
+    use cfg::earley::Grammar;
+
+    let mut grammar = Grammar::new();
+    let [start, a, b, c, d] = grammar.sym();
+    grammar.rule(start).rhs([a, b, c, d]);
+    grammar.rule(start).rhs([a, b, c]);
+    grammar.set_start(start);
+
+    let traces = |strategy| {
+        let binarized = grammar.binarize_with(strategy);
+        let histories = binarized.final_history();
+        binarized
+            .rules()
+            .map(|rule| {
+                let history = &histories[rule.history_id.get()];
+                let dots: Vec<_> = (0..3).map(|i| history.dot(i).trace()).collect();
+                (history.origin().map(|origin| origin.usize()), dots)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        traces(BinarizationStrategy::RightBranching),
+        vec![
+            (Some(0), vec![Some((0, 0)), Some((0, 1)), Some((0, 4))]),
+            (None, vec![None, Some((0, 2)), None]),
+            (None, vec![None, Some((0, 3)), None]),
+            (Some(1), vec![Some((0, 0)), Some((0, 1)), Some((0, 3))]),
+            (None, vec![None, Some((0, 2)), None]),
+        ]
+    );
+    assert_eq!(
+        traces(BinarizationStrategy::SharedPrefix),
+        vec![
+            (None, vec![None, Some((0, 1)), None]),
+            (None, vec![None, Some((0, 2)), None]),
+            (Some(0), vec![Some((0, 0)), Some((0, 3)), Some((0, 4))]),
+            (Some(1), vec![Some((0, 0)), Some((0, 2)), Some((0, 3))]),
+        ]
+    );
+    assert_eq!(
+        traces(BinarizationStrategy::SharedSuffix),
+        vec![
+            (None, vec![None, Some((0, 3)), None]),
+            (None, vec![None, Some((0, 2)), None]),
+            (Some(0), vec![Some((0, 0)), Some((0, 1)), Some((0, 4))]),
+            (None, vec![None, Some((0, 2)), None]),
+            (Some(1), vec![Some((0, 0)), Some((0, 1)), Some((0, 3))]),
+        ]
+    );
+}
+
+#[test]
+fn test_shared_history_is_complete() {
+    let cfg = grammar();
+    let histories: Vec<_> = cfg.rules().map(|rule| rule.history_id).collect();
+    let binarized = cfg.binarize_with(BinarizationStrategy::SharedPrefix);
+
+    let graph = binarized.history_graph();
+    assert!(graph.is_valid(binarized.num_syms()));
+    // Nodes of the original grammar are left as they were.
+    for (id, node) in cfg.history_graph().iter().enumerate() {
+        assert_eq!(format!("{:?}", graph[id]), format!("{:?}", node));
+    }
+    let shared: Vec<_> = graph
+        .iter()
+        .filter_map(|node| match node {
+            HistoryNode::Linked {
+                node: LinkedHistoryNode::BinarizeShared { rules, .. },
+                ..
+            } => Some(rules.clone()),
+            _ => None,
+        })
+        .collect();
+    let [h0, h1, h2, _] = histories[..] else {
+        panic!("expected four rules")
+    };
+    // Order is significant.
+    assert_eq!(shared, vec![vec![h0, h1], vec![h0, h1], vec![h2], vec![h2]]);
+}
@@ -0,0 +1,141 @@
+#![cfg(all(feature = "cfg-sequence", feature = "cfg-classify"))]
+
+mod support;
+
+use cfg::btree_cfg::BTreeRule;
+use cfg::classify::lr::Lr0FsmBuilder;
+use cfg::sequence::destination::SequenceDestination;
+use cfg::sequence::rewrite::SequencesToProductions;
+use cfg::sequence::Separator;
+use cfg::{BTreeCfg, Cfg, RuleContainer};
+
+#[test]
+fn test_rules_for_lhs() {
+    let mut grammar = BTreeCfg::new();
+    let [start, a, b, c] = grammar.sym();
+    grammar
+        .rule(b)
+        .rhs([c])
+        .rule(start)
+        .rhs([a, b])
+        .rule(b)
+        .rhs([a])
+        .rhs([]);
+
+    let rules_for_b: Vec<_> = grammar
+        .rules_for_lhs(b)
+        .map(|(id, rule)| (id, rule.rhs.to_vec()))
+        .collect();
+    assert_eq!(rules_for_b, vec![(0, vec![c]), (2, vec![a]), (3, vec![])]);
+    assert_eq!(grammar.rules_for_lhs(a).count(), 0);
+
+    let mut expected = Cfg::new();
+    let [start, a, b, c] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(start)
+        .rhs([a, b])
+        .rule(b)
+        .rhs([c])
+        .rhs([a])
+        .rhs([]);
+    support::assert_eq_rules(grammar.rules(), expected.rules());
+
+    grammar.retain(|rule| !rule.rhs.is_empty());
+    assert!(grammar.remove(b, 0).is_some());
+    assert!(grammar.get(b, 0).is_none());
+    let ids: Vec<_> = grammar.rules_for_lhs(b).map(|(id, _)| id).collect();
+    assert_eq!(ids, vec![2]);
+}
+
+#[test]
+fn test_sequence_rules() {
+    let mut grammar = BTreeCfg::new();
+    let [start, list, elem, sep] = grammar.sym();
+    grammar.rule(start).rhs([list]);
+    (&mut grammar)
+        .sequence(list)
+        .separator(Separator::Proper(sep))
+        .inclusive(1, None)
+        .rhs(elem);
+
+    assert_eq!(grammar.rules().count(), 1);
+    assert_eq!(grammar.sequences().count(), 1);
+    assert_eq!(grammar.sequences_for_lhs(list).count(), 1);
+    assert_eq!(grammar.sequences_for_lhs(start).count(), 0);
+    let entries: Vec<_> = grammar.entries_for_lhs(list).collect();
+    assert!(matches!(entries[..], [(1, BTreeRule::Sequence(_))]));
+
+    let sequences = grammar.take_sequences();
+    assert_eq!(grammar.sequences().count(), 0);
+    SequencesToProductions::rewrite_sequences(&sequences[..], &mut grammar);
+
+    let mut expected = Cfg::new();
+    let [start, list, _, _] = expected.sym();
+    SequencesToProductions::rewrite_sequences(&sequences[..], &mut expected);
+    expected.rule(start).rhs([list]);
+
+    let mut rules: Vec<_> = grammar
+        .rules()
+        .map(|rule| (rule.lhs, rule.rhs.to_vec()))
+        .collect();
+    let mut expected_rules: Vec<_> = expected
+        .rules()
+        .map(|rule| (rule.lhs, rule.rhs.to_vec()))
+        .collect();
+    rules.sort();
+    expected_rules.sort();
+    assert_eq!(rules, expected_rules);
+}
+
+#[test]
+fn test_btree_cfg_lr0() {
+    fn build<G: RuleContainer>(grammar: &mut G) -> cfg::Symbol {
+        let [start, a, x, b, c, y] = grammar.sym();
+        grammar
+            .rule(start)
+            .rhs([a, x, b])
+            .rhs([c])
+            .rule(b)
+            .rhs([a, a])
+            .rhs([a, c])
+            .rule(c)
+            .rhs([x])
+            .rhs([y])
+            .rule(a)
+            .rhs([]);
+        start
+    }
+
+    let mut cfg = Cfg::new();
+    let start = build(&mut cfg);
+    let mut btree_cfg = BTreeCfg::new();
+    build(&mut btree_cfg);
+
+    assert_eq!(
+        Lr0FsmBuilder::new(&mut btree_cfg).make_lr0_fsm(start),
+        Lr0FsmBuilder::new(&mut cfg).make_lr0_fsm(start)
+    );
+}
+
+#[test]
+fn test_lr0_conflicts() {
+    use cfg::classify::lr::{self, LrConflictKind};
+
+    let mut grammar = BTreeCfg::new();
+    let [start, b, x] = grammar.sym();
+    // Rule IDs differ from positions in `rules`.
+    grammar.rule(b).rhs([x]).rule(start).rhs([b]).rhs([x]);
+
+    let fsm = Lr0FsmBuilder::new(&mut grammar).make_lr0_fsm(start);
+    let conflicts = lr::lr0_conflicts(&grammar, &fsm);
+
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].kind, LrConflictKind::ReduceReduce);
+    let rules: Vec<_> = conflicts[0]
+        .rules
+        .iter()
+        .map(|rule| (rule.lhs, rule.rhs.to_vec()))
+        .collect();
+    assert_eq!(rules, vec![(b, vec![x]), (start, vec![x])]);
+}
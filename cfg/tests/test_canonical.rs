@@ -0,0 +1,96 @@
+use cfg::{Cfg, RuleContainer};
+
+fn rules(cfg: &Cfg) -> Vec<(usize, Vec<usize>)> {
+    cfg.rules()
+        .map(|rule| {
+            (
+                rule.lhs.usize(),
+                rule.rhs.iter().map(|sym| sym.usize()).collect(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_canonicalize() {
+    let mut cfg: Cfg = Cfg::new();
+    let [_unused, term, start, list, unreachable] = cfg.sym();
+    cfg.rule(list)
+        .rhs([term, list])
+        .rhs([])
+        .rule(start)
+        .rhs([list])
+        .rule(unreachable)
+        .rhs([term])
+        .rule(list)
+        .rhs([term, list]);
+
+    let mapping = cfg.canonicalize(start);
+    let [new_start, new_list, new_term] = Cfg::new().sym();
+
+    assert_eq!(cfg.num_syms(), 3);
+    // Order is significant.
+    assert_eq!(
+        rules(&cfg),
+        vec![(0, vec![1]), (1, vec![]), (1, vec![2, 1])]
+    );
+    assert_eq!(
+        mapping.to_internal,
+        vec![None, Some(new_term), Some(new_start), Some(new_list), None]
+    );
+    assert_eq!(mapping.to_external, vec![start, list, term]);
+}
+
+#[test]
+fn test_canonical_equality() {
+    let mut a: Cfg = Cfg::new();
+    let [a_start, sum, num, plus] = a.sym();
+    a.rule(a_start)
+        .rhs([sum])
+        .rule(sum)
+        .rhs([sum, plus, num])
+        .rhs([num]);
+
+    let mut b: Cfg = Cfg::new();
+    let [plus, _unused, num, sum, start] = b.sym();
+    b.rule(sum)
+        .rhs([num])
+        .rhs([sum, plus, num])
+        .rule(start)
+        .rhs([sum])
+        .rule(sum)
+        .rhs([num]);
+
+    assert_ne!(a.fingerprint(), b.fingerprint());
+    a.canonicalize(a_start);
+    b.canonicalize(start);
+    assert_eq!(rules(&a), rules(&b));
+    assert_eq!(a.fingerprint(), b.fingerprint());
+}
+
+#[test]
+fn test_fingerprint() {
+    let mut a: Cfg = Cfg::new();
+    let [start, x, y] = a.sym();
+    a.rule(start).rhs([x, y]).rhs([y]);
+    let mut b: Cfg = Cfg::new();
+    let [start, x, y] = b.sym();
+    b.rule(start).rhs([y]).rhs([x, y]).rhs([y]);
+    let mut c: Cfg = Cfg::new();
+    let [start, x, y] = c.sym();
+    c.rule(start).rhs([y, x]).rhs([y]);
+
+    assert_eq!(a.fingerprint(), b.fingerprint());
+    assert_ne!(a.fingerprint(), c.fingerprint());
+    assert_eq!(a.fingerprint(), 0x9982_6559_92a6_ef06);
+}
+
+#[test]
+fn test_fnv1a() {
+    use cfg::fnv::Fnv1a;
+
+    let mut hasher = Fnv1a::new();
+    assert_eq!(hasher.finish(), 0xcbf2_9ce4_8422_2325);
+    hasher.write_bytes(b"a");
+    assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+}
@@ -0,0 +1,160 @@
+use cfg::cnf::ChomskyNormalForm;
+use cfg::history::node::LinkedHistoryNode;
+use cfg::{BinarizedCfg, Cfg, HistoryNode, RuleContainer, Symbol};
+
+/// Recognizes a string with the CYK algorithm.
+fn cyk(grammar: &BinarizedCfg, start: Symbol, input: &[Symbol]) -> bool {
+    if input.is_empty() {
+        return grammar
+            .rules()
+            .any(|rule| rule.lhs == start && rule.rhs.is_empty());
+    }
+    let n = input.len();
+    // table[len - 1][i] holds symbols that derive input[i..i + len].
+    let mut table = vec![vec![vec![]; n]; n];
+    for (i, &terminal) in input.iter().enumerate() {
+        for rule in grammar.rules() {
+            if rule.rhs == [terminal] {
+                table[0][i].push(rule.lhs);
+            }
+        }
+    }
+    for len in 2..=n {
+        for i in 0..=n - len {
+            for split in 1..len {
+                for rule in grammar.rules().filter(|rule| rule.rhs.len() == 2) {
+                    if table[split - 1][i].contains(&rule.rhs[0])
+                        && table[len - split - 1][i + split].contains(&rule.rhs[1])
+                    {
+                        table[len - 1][i].push(rule.lhs);
+                    }
+                }
+            }
+        }
+    }
+    table[n - 1][0].contains(&start)
+}
+
+#[test]
+fn test_cnf() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, empty, x, y, a, b, c] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, start, b])
+        .rhs([empty])
+        .rhs([x])
+        .rule(empty)
+        .rhs([])
+        .rule(x)
+        .rhs([y])
+        .rule(y)
+        .rhs([c]);
+
+    let cnf = ChomskyNormalForm::new(&cfg, start);
+    let grammar = cnf.grammar();
+    let index = cfg::GrammarIndex::new(grammar);
+
+    for rule in grammar.rules() {
+        match rule.rhs {
+            [] => assert_eq!(rule.lhs, cnf.start()),
+            [terminal] => assert!(!index.is_nonterminal(*terminal)),
+            [left, right] => {
+                assert!(index.is_nonterminal(*left) && index.is_nonterminal(*right));
+                assert!(*left != cnf.start() && *right != cnf.start());
+            }
+            _ => panic!("rule is not in CNF"),
+        }
+    }
+
+    // The language is `a^n (c | ε) b^n`.
+    let terminals = [a, b, c];
+    let mut strings = vec![vec![]];
+    for _ in 0..5 {
+        for string in strings.clone() {
+            for &terminal in &terminals {
+                let mut longer = string.clone();
+                longer.push(terminal);
+                strings.push(longer);
+            }
+        }
+        strings.sort();
+        strings.dedup();
+    }
+    for string in strings {
+        let count = |sym| string.iter().filter(|&&s| s == sym).count();
+        let num_a = string.iter().take_while(|&&s| s == a).count();
+        let rest = &string[num_a..];
+        let rest = rest.strip_prefix(&[c]).unwrap_or(rest);
+        let expected = rest.iter().all(|&s| s == b) && count(b) == num_a && count(c) <= 1;
+        assert_eq!(cyk(grammar, cnf.start(), &string), expected, "{:?}", string);
+    }
+}
+
+#[test]
+fn test_cnf_history() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, x, y, a, c] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, x])
+        .rhs([x])
+        .rule(x)
+        .rhs([y])
+        .rule(y)
+        .rhs([c]);
+
+    let cnf = ChomskyNormalForm::new(&cfg, start);
+    let grammar = cnf.grammar();
+    let node = |rule: cfg::rule::RuleRef| match &grammar.history_graph()[rule.history_id.get()] {
+        HistoryNode::Linked { node, .. } => Some(node.clone()),
+        HistoryNode::Root(..) => None,
+    };
+
+    // The rule `start ::= c` replaces the chain `start ::= x`, `x ::= y`.
+    let rule = grammar
+        .rules()
+        .find(|rule| rule.lhs == start && rule.rhs == [c])
+        .unwrap();
+    match node(rule) {
+        Some(LinkedHistoryNode::EliminateUnitRules { chain }) => assert_eq!(chain.len(), 2),
+        _ => panic!("expected a unit rule elimination"),
+    }
+
+    // The terminal `a` in `start ::= a x` is lifted.
+    let lifted = grammar
+        .rules()
+        .find(|rule| rule.rhs == [a])
+        .expect("missing lifted terminal");
+    assert!(matches!(
+        node(lifted),
+        Some(LinkedHistoryNode::LiftTerminal { terminal }) if terminal == a
+    ));
+    assert!(grammar
+        .rules()
+        .any(|rule| rule.lhs == start && rule.rhs == [lifted.lhs, x]));
+    assert!(!grammar.rules().any(|rule| rule.rhs.is_empty()));
+}
+
+#[test]
+fn test_cnf_nonterminals_without_rules_after_unit_elimination() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, x] = cfg.sym();
+    // `a` and `b` have only unit rules, which are eliminated.
+    cfg.rule(start)
+        .rhs([a, x])
+        .rule(a)
+        .rhs([b])
+        .rule(b)
+        .rhs([a]);
+
+    let cnf = ChomskyNormalForm::new(&cfg, start);
+    let grammar = cnf.grammar();
+    let index = cfg::GrammarIndex::new(grammar);
+
+    assert!(!index.is_nonterminal(a));
+    for rule in grammar.rules() {
+        assert!(
+            rule.rhs.len() != 2 || rule.rhs.iter().all(|&sym| index.is_nonterminal(sym)),
+            "rule is not in CNF"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use cfg::history::node::{LinkedHistoryNode, RootHistoryNode};
+use cfg::refactor::inline;
+use cfg::{BTreeCfg, Cfg, HistoryId, HistoryNode, RuleContainer, Symbol};
+
+/// Follows history links to the root, which holds the LHS of the rule built by the user.
+fn root_lhs<G: RuleContainer>(grammar: &G, mut id: HistoryId) -> Option<Symbol> {
+    loop {
+        match grammar.history_graph()[id.get()] {
+            HistoryNode::Linked { prev, .. } => id = prev,
+            HistoryNode::Root(RootHistoryNode::Rule { lhs }) => return Some(lhs),
+            HistoryNode::Root(..) => return None,
+        }
+    }
+}
+
+#[test]
+fn test_compact_history() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, dead] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a])
+        .rule(dead)
+        .rhs([b])
+        .rhs([c])
+        .rule(start)
+        .rhs([b, c]);
+    cfg.retain(|rule| rule.lhs != dead);
+    let rules_before: Vec<_> = cfg.rules().map(|rule| rule.rhs.to_vec()).collect();
+    assert_eq!(cfg.history_graph().len(), 9);
+
+    let new_ids = cfg.compact_history();
+
+    assert_eq!(cfg.history_graph().len(), 5);
+    assert_eq!(new_ids.iter().filter(|id| id.is_some()).count(), 4);
+    assert_eq!(new_ids[0], None);
+    let rules_after: Vec<_> = cfg.rules().map(|rule| rule.rhs.to_vec()).collect();
+    assert_eq!(rules_before, rules_after);
+    for rule in cfg.rules() {
+        assert_eq!(root_lhs(&cfg, rule.history_id), Some(start));
+        match &cfg.history_graph()[rule.history_id.get()] {
+            HistoryNode::Linked {
+                node: LinkedHistoryNode::Rhs { rhs },
+                ..
+            } => assert_eq!(&rhs[..], rule.rhs),
+            _ => panic!("expected an Rhs node"),
+        }
+    }
+}
+
+#[test]
+fn test_compact_history_keeps_inner_links() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, sign, num, plus] = cfg.sym();
+    cfg.rule(start).rhs([sign, num]).rule(sign).rhs([plus]);
+    inline(&mut cfg, sign);
+    cfg.retain(|rule| rule.lhs != sign);
+
+    cfg.compact_history();
+
+    let rules: Vec<_> = cfg.rules().collect();
+    assert_eq!(rules.len(), 1);
+    match cfg.history_graph()[rules[0].history_id.get()] {
+        HistoryNode::Linked {
+            node: LinkedHistoryNode::Inline { inner, .. },
+            prev,
+        } => {
+            assert_eq!(root_lhs(&cfg, inner), Some(sign));
+            assert_eq!(root_lhs(&cfg, prev), Some(start));
+        }
+        _ => panic!("expected an Inline node"),
+    }
+    assert_eq!(cfg.history_graph().len(), 6);
+}
+
+#[test]
+fn test_compact_history_binarized() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, unused] = cfg.sym();
+    cfg.rule(start).rhs([a, b, c]).rhs([]).rule(unused).rhs([a]);
+    let mut bin = cfg.binarize();
+    bin.retain(|rule| rule.lhs != unused);
+    let len_before = bin.history_graph().len();
+
+    bin.compact_history();
+
+    // The removed rule had a root, an Rhs node and a Binarize node.
+    assert_eq!(bin.history_graph().len(), len_before - 3);
+    let mut roots = 0;
+    for rule in bin.rules() {
+        if let Some(lhs) = root_lhs(&bin, rule.history_id) {
+            assert_eq!(lhs, start);
+            roots += 1;
+        }
+    }
+    assert_eq!(roots, bin.rules().count());
+}
+
+#[test]
+fn test_compact_history_btree() {
+    let mut cfg = BTreeCfg::new();
+    let [start, a, dead] = cfg.sym();
+    cfg.rule(dead).rhs([a]).rule(start).rhs([a, a]);
+    cfg.retain(|rule| rule.lhs != dead);
+
+    cfg.compact_history();
+
+    assert_eq!(cfg.history_graph().len(), 3);
+    let rule = cfg.rules().next().unwrap();
+    assert_eq!(root_lhs(&cfg, rule.history_id), Some(start));
+}
@@ -89,3 +89,84 @@ fn test_cycle_branch() {
     support::assert_eq_rules(equivalent.rules(), cfg.rules());
     assert!(Cycles::new(&mut cfg).cycle_free());
 }
+
+#[test]
+fn test_cycles_with_witness() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, d, e, x] = cfg.sym();
+
+    cfg.rule(start)
+        .rhs([a])
+        .rhs([d, x])
+        .rule(a)
+        .rhs([b])
+        .rhs([c])
+        .rule(b)
+        .rhs([c])
+        .rule(c)
+        .rhs([a])
+        .rhs([x])
+        .rule(d)
+        .rhs([e])
+        .rule(e)
+        .rhs([d])
+        .rhs([x]);
+
+    let cycles = Cycles::new(&mut cfg).cycles();
+    let syms: Vec<_> = cycles.iter().map(|cycle| cycle.syms.clone()).collect();
+    assert_eq!(syms, vec![vec![a, b, c], vec![d, e]]);
+    let witness: Vec<_> = cycles[0]
+        .witness
+        .iter()
+        .map(|rule| (rule.lhs, rule.rhs.clone()))
+        .collect();
+    // Order is significant.
+    assert_eq!(witness, vec![(a, vec![c]), (c, vec![a])]);
+    assert_eq!(cycles[1].witness.len(), 2);
+}
+
+#[test]
+fn test_rewrite_selected_cycle() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, d, e, x] = cfg.sym();
+
+    cfg.rule(start)
+        .rhs([a])
+        .rhs([d, x])
+        .rule(a)
+        .rhs([b])
+        .rule(b)
+        .rhs([a])
+        .rhs([x])
+        .rule(d)
+        .rhs([e])
+        .rule(e)
+        .rhs([d])
+        .rhs([x]);
+
+    let mut equivalent: Cfg = Cfg::new();
+    let [start, a, b, _, e, x] = equivalent.sym();
+
+    // Order is significant.
+    equivalent
+        .rule(start)
+        .rhs([a])
+        .rule(a)
+        .rhs([b])
+        .rule(b)
+        .rhs([a])
+        .rhs([x])
+        .rule(e)
+        .rhs([x])
+        .rule(start)
+        .rhs([e, x]);
+    {
+        let mut cycles = Cycles::new(&mut cfg);
+        let selected = cycles.cycles().pop().unwrap();
+        let remaining = cycles.rewrite_cycle(&selected, e);
+        assert_eq!(remaining.len(), cycles.cycles().len());
+        let remaining: Vec<_> = remaining.into_iter().map(|cycle| cycle.syms).collect();
+        assert_eq!(remaining, vec![vec![a, b]]);
+    };
+    support::assert_eq_rules(equivalent.rules(), cfg.rules());
+}
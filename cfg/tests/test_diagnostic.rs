@@ -0,0 +1,125 @@
+use cfg::diagnostic::{ConflictKind, Diagnostic, DiagnosticKind, GrammarError, Severity};
+use cfg::rule::builder::RuleBuilder;
+use cfg::{Cfg, RuleContainer};
+
+#[test]
+fn test_rule_builder_missing_lhs() {
+    let mut cfg: Cfg = Cfg::new();
+    let [a] = cfg.sym();
+
+    let error = RuleBuilder::new(&mut cfg).try_rhs([a]).err().unwrap();
+
+    assert_eq!(error.error, GrammarError::MissingLhs);
+    error.builder.rule(a).try_rhs([]).unwrap();
+    assert_eq!(cfg.rules().count(), 1);
+}
+
+#[cfg(feature = "cfg-sequence")]
+#[test]
+fn test_sequence_builder_missing_range() {
+    use cfg_sequence::destination::SequenceDestination;
+    use cfg_sequence::rewrite::SequencesToProductions;
+
+    let mut cfg: Cfg = Cfg::new();
+    let [start, elem] = cfg.sym();
+
+    let error = SequencesToProductions::new(&mut cfg)
+        .sequence(start)
+        .try_rhs(elem)
+        .err()
+        .unwrap();
+
+    assert_eq!(error.error, GrammarError::MissingRange);
+    assert_eq!(error.to_string(), "sequence rule has no range".to_string());
+    error.builder.inclusive(1, None).try_rhs(elem).unwrap();
+    assert!(cfg.rules().count() > 0);
+}
+
+#[cfg(feature = "cfg-predict")]
+#[test]
+fn test_follow_sets_missing_first_set() {
+    use cfg::predict::{FollowSets, PerSymbolSets};
+
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, x] = cfg.sym();
+    cfg.rule(start).rhs([a, b]).rule(b).rhs([x]);
+
+    let result = FollowSets::try_new(&cfg, start, &PerSymbolSets::new());
+
+    assert_eq!(result.err(), Some(GrammarError::MissingFirstSet(b)));
+}
+
+#[cfg(feature = "cfg-earley")]
+#[test]
+fn test_make_proper_diagnostics() {
+    use cfg::earley::Grammar;
+
+    let mut grammar = Grammar::new();
+    let [start, a, unreachable, unproductive] = grammar.sym();
+    grammar
+        .rule(start)
+        .rhs([a])
+        .rhs([a, unproductive])
+        .rule(unreachable)
+        .rhs([a])
+        .rule(unproductive)
+        .rhs([unproductive, a]);
+    assert_eq!(grammar.try_start(), Err(GrammarError::MissingStart));
+    assert_eq!(
+        grammar.binarize().try_make_proper().err(),
+        Some(GrammarError::MissingStart)
+    );
+    grammar.set_start(start);
+
+    let (proper, diagnostics) = grammar.binarize().try_make_proper().unwrap();
+
+    assert_eq!(proper.rules().count(), 1);
+    let useless: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| match &diagnostic.kind {
+            DiagnosticKind::UselessRule { lhs, rhs } => (*lhs, rhs.clone()),
+            kind => panic!("unexpected diagnostic {:?}", kind),
+        })
+        .collect();
+    // Order is significant.
+    assert_eq!(
+        useless,
+        vec![
+            (start, vec![a, unproductive]),
+            (unreachable, vec![a]),
+            (unproductive, vec![unproductive, a])
+        ]
+    );
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity == Severity::Warning
+            && diagnostic.history_id.is_some()));
+}
+
+#[test]
+fn test_diagnostic_display() {
+    let diagnostic = Diagnostic::from(GrammarError::MissingStart);
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.to_string(), "error: grammar has no start symbol");
+}
+
+#[test]
+fn test_conflict_diagnostic() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a] = cfg.sym();
+    cfg.rule(start).rhs([a]);
+    let rule = cfg.rules().next().unwrap();
+
+    let diagnostic = Diagnostic::conflict(
+        ConflictKind::ShiftReduce(a),
+        rule.lhs,
+        rule.rhs,
+        rule.history_id,
+    );
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.kind.rule(), Some((start, &[a][..])));
+    assert_eq!(
+        diagnostic.to_string(),
+        "error: shift/reduce conflict on symbol 1 for symbol 0"
+    );
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+use cfg::diff::{FirstSetChange, GrammarDiff, LhsDiff, PropertyChange, SymbolAlignment};
+use cfg::symbol::remap::Remap;
+use cfg::{Cfg, RuleContainer};
+
+#[test]
+fn test_equal_grammars() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b] = cfg.sym();
+    cfg.rule(start).rhs([a, start]).rhs([b]);
+
+    let diff = GrammarDiff::new(&cfg, &cfg.clone(), &SymbolAlignment::identity(3)).reachable(
+        &cfg,
+        &[start],
+        &cfg,
+        &[start],
+        &SymbolAlignment::identity(3),
+    );
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn test_rule_changes() {
+    let mut old: Cfg = Cfg::new();
+    let [start, list, a, b] = old.sym();
+    old.rule(start)
+        .rhs([list])
+        .rule(list)
+        .rhs([a, list])
+        .rhs([a])
+        .rhs([b]);
+
+    let mut new: Cfg = Cfg::new();
+    let [start2, list2, a2, b2, extra] = new.sym();
+    new.rule(start2)
+        .rhs([list2])
+        .rule(list2)
+        .rhs([a2, list2])
+        .rhs([b2])
+        .rhs([])
+        .rule(extra)
+        .rhs([a2]);
+
+    let diff = GrammarDiff::new(&old, &new, &SymbolAlignment::identity(4));
+
+    assert_eq!(
+        diff.rules(),
+        &[
+            LhsDiff {
+                old_lhs: Some(list),
+                new_lhs: Some(list2),
+                removed: vec![vec![a]],
+                added: vec![vec![]],
+            },
+            LhsDiff {
+                old_lhs: None,
+                new_lhs: Some(extra),
+                removed: vec![],
+                added: vec![vec![a2]],
+            },
+        ]
+    );
+    let became_nullable = |old_sym, new_sym| PropertyChange {
+        old_sym,
+        new_sym,
+        now: true,
+    };
+    assert_eq!(
+        diff.nullable(),
+        &[became_nullable(start, start2), became_nullable(list, list2)]
+    );
+    assert_eq!(diff.productive(), &[]);
+    assert_eq!(diff.first_sets(), &[]);
+}
+
+#[test]
+fn test_first_sets_and_reachability() {
+    let mut old: Cfg = Cfg::new();
+    let [start, x, a, b] = old.sym();
+    old.rule(start).rhs([a]).rule(x).rhs([b]);
+
+    let mut new: Cfg = Cfg::new();
+    let [start2, x2, a2, b2] = new.sym();
+    new.rule(start2).rhs([x2]).rule(x2).rhs([b2]);
+
+    let alignment = SymbolAlignment::identity(4);
+    let diff = GrammarDiff::new(&old, &new, &alignment).reachable(
+        &old,
+        &[start],
+        &new,
+        &[start2],
+        &alignment,
+    );
+
+    assert_eq!(
+        diff.first_sets(),
+        &[FirstSetChange {
+            old_sym: start,
+            new_sym: start2,
+            removed: vec![a],
+            added: vec![b2],
+        }]
+    );
+    assert_eq!(
+        diff.reachable_changes(),
+        &[
+            PropertyChange {
+                old_sym: x,
+                new_sym: x2,
+                now: true,
+            },
+            PropertyChange {
+                old_sym: a,
+                new_sym: a2,
+                now: false,
+            },
+            PropertyChange {
+                old_sym: b,
+                new_sym: b2,
+                now: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_unproductive() {
+    let mut old: Cfg = Cfg::new();
+    let [start, a] = old.sym();
+    old.rule(start).rhs([a]);
+
+    let mut new: Cfg = Cfg::new();
+    let [start2, _] = new.sym();
+    new.rule(start2).rhs([start2]);
+
+    let diff = GrammarDiff::new(&old, &new, &SymbolAlignment::identity(2));
+
+    assert_eq!(
+        diff.productive(),
+        &[PropertyChange {
+            old_sym: start,
+            new_sym: start2,
+            now: false,
+        }]
+    );
+}
+
+#[test]
+fn test_alignment_by_names_and_mapping() {
+    let mut old: Cfg = Cfg::new();
+    let [start, unused, a, b] = old.sym();
+    old.rule(start).rhs([a, b]);
+
+    let mut new = old.clone();
+    let mut remap = Remap::new(&mut new);
+    remap.remove_unused_symbols();
+    let mapping = remap.get_mapping();
+    let [start2, a2, b2] = [start, a, b].map(|sym| mapping.to_internal[sym.usize()].unwrap());
+    assert_eq!(mapping.to_internal[unused.usize()], None);
+
+    let diff = GrammarDiff::new(&old, &new, &SymbolAlignment::new().by_mapping(&mapping));
+    assert!(diff.is_empty());
+
+    // Names take precedence over the mapping.
+    let old_names = BTreeMap::from([(a, "a".to_string()), (b, "b".to_string())]);
+    let new_names = BTreeMap::from([(b2, "a".to_string()), (a2, "b".to_string())]);
+    let alignment = SymbolAlignment::new()
+        .by_names(&old_names, &new_names)
+        .by_mapping(&mapping);
+    assert_eq!(alignment.get(a), Some(b2));
+    assert_eq!(alignment.get(start), Some(start2));
+
+    let diff = GrammarDiff::new(&old, &new, &alignment);
+    assert_eq!(
+        diff.rules(),
+        &[LhsDiff {
+            old_lhs: Some(start),
+            new_lhs: Some(start2),
+            removed: vec![vec![a, b]],
+            added: vec![vec![a2, b2]],
+        }]
+    );
+    assert_eq!(
+        diff.first_sets(),
+        &[FirstSetChange {
+            old_sym: start,
+            new_sym: start2,
+            removed: vec![a],
+            added: vec![a2],
+        }]
+    );
+}
@@ -0,0 +1,148 @@
+mod support;
+
+use cfg::diagnostic::GrammarError;
+use cfg::history::node::LinkedHistoryNode;
+use cfg::{Cfg, HistoryNode, RuleContainer};
+
+#[test]
+fn test_eliminate_nulling_rules() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, x, y] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, x, b, a])
+        .rhs([b])
+        .rule(a)
+        .rhs([])
+        .rhs([y])
+        .rule(b)
+        .rhs([a, a]);
+
+    let nulling = cfg.eliminate_nulling_rules();
+
+    let mut expected: Cfg = Cfg::new();
+    let [start, a, b, x, y] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(start)
+        .rhs([a, x, b, a])
+        .rhs([x, b, a])
+        .rhs([a, x, a])
+        .rhs([x, a])
+        .rhs([a, x, b])
+        .rhs([x, b])
+        .rhs([a, x])
+        .rhs([x])
+        .rhs([b])
+        .rule(a)
+        .rhs([y])
+        .rule(b)
+        .rhs([a, a])
+        .rhs([a])
+        .rhs([a]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+
+    let mut expected_nulling: Cfg = Cfg::new();
+    let [start, a, b] = expected_nulling.sym();
+    expected_nulling
+        .rule(start)
+        .rhs([b])
+        .rule(a)
+        .rhs([])
+        .rule(b)
+        .rhs([a, a]);
+    support::assert_eq_rules(nulling.rules(), expected_nulling.rules());
+}
+
+#[test]
+fn test_nulled_positions() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, x] = cfg.sym();
+    cfg.rule(start).rhs([a, x, a]).rule(a).rhs([]).rhs([x]);
+
+    cfg.eliminate_nulling_rules();
+
+    let nulled: Vec<_> = cfg
+        .rules()
+        .filter(|rule| rule.lhs == start)
+        .map(|rule| match &cfg.history_graph()[rule.history_id.get()] {
+            HistoryNode::Linked {
+                node: LinkedHistoryNode::EliminateNullingSubset { nulled },
+                ..
+            } => nulled.clone(),
+            _ => vec![],
+        })
+        .collect();
+    assert_eq!(nulled, vec![vec![], vec![0], vec![2], vec![0, 2]]);
+}
+
+#[test]
+fn test_eliminate_nulling_only_empty() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, x] = cfg.sym();
+    cfg.rule(start).rhs([a]).rhs([x, a]).rule(a).rhs([]);
+
+    let nulling = cfg.eliminate_nulling_rules();
+
+    // `a` derives only the empty string, so rules that keep it are unproductive.
+    let mut expected: Cfg = Cfg::new();
+    let [start, _a, x] = expected.sym();
+    expected.rule(start).rhs([x]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+    assert!(nulling.rules().any(|rule| rule.lhs == start));
+}
+
+#[test]
+fn test_too_many_nullable_symbols() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, nullable] = cfg.sym();
+    cfg.rule(start)
+        .rhs(vec![nullable; usize::BITS as usize])
+        .rule(nullable)
+        .rhs([]);
+    let num_rules = cfg.rules().count();
+
+    assert_eq!(
+        cfg.try_eliminate_nulling_rules(usize::MAX).err(),
+        Some(GrammarError::TooManyNullableSymbols(start))
+    );
+    assert_eq!(cfg.rules().count(), num_rules);
+}
+
+#[test]
+fn test_max_nulling_variants() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, nullable, x] = cfg.sym();
+    cfg.rule(start)
+        .rhs([nullable, nullable, nullable])
+        .rhs([nullable, nullable, x])
+        .rule(nullable)
+        .rhs([])
+        .rhs([x]);
+
+    // The first rule has 2^3 - 2 variants and the second rule has 2^2 - 1 variants.
+    assert_eq!(
+        cfg.clone().try_eliminate_nulling_rules(5).err(),
+        Some(GrammarError::TooManyNullableSymbols(start))
+    );
+    let mut limited = cfg.clone();
+    limited.try_eliminate_nulling_rules(6).unwrap();
+    let mut unlimited = cfg.clone();
+    unlimited.eliminate_nulling_rules();
+    support::assert_eq_rules(limited.rules(), unlimited.rules());
+    assert_eq!(
+        limited.rules().filter(|rule| rule.lhs == start).count(),
+        2 + 6 + 3
+    );
+
+    let mut wide: Cfg = Cfg::new();
+    let [start, nullable] = wide.sym();
+    wide.rule(start)
+        .rhs(vec![nullable; 17])
+        .rule(nullable)
+        .rhs([]);
+    assert_eq!(
+        wide.try_eliminate_nulling_rules(Cfg::DEFAULT_MAX_NULLING_VARIANTS)
+            .err(),
+        Some(GrammarError::TooManyNullableSymbols(start))
+    );
+}
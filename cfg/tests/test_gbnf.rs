@@ -0,0 +1,338 @@
+#![cfg(all(feature = "cfg-load", feature = "cfg-sequence"))]
+
+mod support;
+
+use cfg::load::gbnf::{Gbnf, GbnfErrorKind, Terminal};
+use cfg::{Cfg, HistoryId, RuleContainer};
+use cfg_sequence::destination::SequenceDestination;
+use cfg_sequence::rewrite::SequencesToProductions;
+
+const JSON: &str = r#"
+root   ::= object
+value  ::= object | array | string | number | ("true" | "false" | "null") ws
+
+object ::=
+  "{" ws (
+            string ":" ws value
+    ("," ws string ":" ws value)*
+  )? "}" ws
+
+array  ::=
+  "[" ws (
+            value
+    ("," ws value)*
+  )? "]" ws
+
+string ::=
+  "\"" (
+    [^"\\\x7F\x00-\x1F] |
+    "\\" (["\\/bfnrt] | "u" [0-9a-fA-F]{4}) # escapes
+  )* "\"" ws
+
+number ::= ("-"? ([0-9] | [1-9] [0-9]{0,15})) ("." [0-9]+)? ([eE] [-+]? [0-9]+)? ws
+
+# Optional space: by convention, applied in this grammar after literal chars when allowed
+ws ::= | " " | "\n" [ \t]{0,20}
+"#;
+
+fn origin(grammar: &Cfg, history_id: HistoryId) -> Option<usize> {
+    grammar.history_graph().provenance(history_id).origin()
+}
+
+#[test]
+fn test_gbnf_rules() {
+    let gbnf = Gbnf::parse("root ::= \"a\" b | c\nb ::= [0-9]\nc ::= \"x\" \"y\"\n").unwrap();
+
+    let mut equivalent: Cfg = Cfg::new();
+    let [root, b, c, a_lit, digit, x_lit, y_lit] = equivalent.sym();
+    equivalent
+        .rule(root)
+        .rhs([a_lit, b])
+        .rhs([c])
+        .rule(b)
+        .rhs([digit])
+        .rule(c)
+        .rhs([x_lit, y_lit]);
+
+    support::assert_eq_rules(equivalent.rules(), gbnf.cfg().rules());
+    assert_eq!(gbnf.root(), Some(root));
+    assert_eq!(gbnf.symbol("c"), Some(c));
+    assert_eq!(gbnf.name(b), Some("b"));
+    assert_eq!(
+        gbnf.terminal(digit),
+        Some(&Terminal::CharClass {
+            negated: false,
+            ranges: vec![('0', '9')]
+        })
+    );
+    assert_eq!(gbnf.terminal(root), None);
+}
+
+#[test]
+fn test_gbnf_repetition() {
+    let gbnf = Gbnf::parse("root ::= \"a\"*").unwrap();
+
+    let mut equivalent: Cfg = Cfg::new();
+    let [root, a_lit, star] = equivalent.sym();
+    // Order is significant.
+    SequencesToProductions::new(&mut equivalent)
+        .sequence(star)
+        .inclusive(0, None)
+        .rhs(a_lit);
+    equivalent.rule(root).rhs([star]);
+
+    support::assert_eq_rules(equivalent.rules(), gbnf.cfg().rules());
+}
+
+#[test]
+fn test_gbnf_history_origin() {
+    let gbnf = Gbnf::parse("root ::= (\"a\" | b)+ b\nb ::= \"c\"{2,5}").unwrap();
+    let b_alone = Gbnf::parse("b ::= \"c\"{2,5}").unwrap();
+    let b = gbnf.symbol("b").unwrap();
+    let b_rule = gbnf.cfg().rules().find(|rule| rule.lhs == b).unwrap();
+    let origins: Vec<_> = gbnf
+        .cfg()
+        .rules()
+        .map(|rule| origin(gbnf.cfg(), rule.history_id))
+        .collect();
+
+    assert_eq!(origin(gbnf.cfg(), b_rule.history_id), Some(1));
+    assert!(origins.iter().all(|origin| origin.is_some()));
+    assert_eq!(
+        origins.iter().filter(|&&origin| origin == Some(1)).count(),
+        b_alone.cfg().rules().count()
+    );
+}
+
+#[test]
+fn test_gbnf_write() {
+    let source = "root ::= \"a\\n\\\"\" item | \"\"\nitem ::= [^a-c\\]] | .\n";
+    let gbnf = Gbnf::parse(source).unwrap();
+    assert_eq!(gbnf.to_gbnf(), source);
+}
+
+#[test]
+fn test_gbnf_round_trip() {
+    let gbnf = Gbnf::parse(JSON).unwrap();
+    let written = gbnf.to_gbnf();
+    let reparsed = Gbnf::parse(&written).unwrap();
+    assert_eq!(reparsed.cfg().rules().count(), gbnf.cfg().rules().count());
+    assert_eq!(reparsed.to_gbnf(), written);
+}
+
+#[test]
+fn test_gbnf_errors() {
+    let error = Gbnf::parse("root ::= a\n\na ::= b c\nb ::= \"\"")
+        .err()
+        .unwrap();
+    assert_eq!(error.kind, GbnfErrorKind::UndefinedRule("c".to_string()));
+    assert_eq!((error.line, error.column), (3, 9));
+
+    let error = Gbnf::parse("root ::= \"x\"{3,1}").err().unwrap();
+    assert_eq!(error.kind, GbnfErrorKind::InvalidRepetition);
+
+    let error = Gbnf::parse("root ::= (\"x\"").err().unwrap();
+    assert_eq!(error.kind, GbnfErrorKind::UnexpectedEof);
+
+    let error = Gbnf::parse("root = \"x\"").err().unwrap();
+    assert_eq!(error.kind, GbnfErrorKind::ExpectedDefinition);
+}
+
+#[test]
+fn test_gbnf_spans() {
+    let source = "root ::= \"a\" b | b\nb ::= (\"x\" | \"y\")*\n";
+    let gbnf = Gbnf::parse_with_file_name("test.gbnf", source).unwrap();
+    let root = gbnf.root().unwrap();
+    let b = gbnf.symbol("b").unwrap();
+    let spans: Vec<_> = gbnf
+        .cfg()
+        .rules()
+        .filter(|rule| rule.lhs == root)
+        .map(|rule| gbnf.span(rule.history_id).unwrap().to_string())
+        .collect();
+
+    // Order is significant.
+    assert_eq!(spans, vec!["test.gbnf:1:10-1:15", "test.gbnf:1:18-1:19"]);
+    assert_eq!(
+        gbnf.definition_span(b).unwrap().to_string(),
+        "test.gbnf:2:1-2:19"
+    );
+    assert!(gbnf
+        .cfg()
+        .rules()
+        .all(|rule| gbnf.span(rule.history_id).is_some()));
+    let rule = gbnf.cfg().rules().find(|rule| rule.lhs == root).unwrap();
+    assert_eq!(
+        gbnf.diagnostic(rule, "example"),
+        "test.gbnf:1:10: example: `root ::= \"a\" b`"
+    );
+}
+
+#[cfg(feature = "cfg-classify")]
+#[test]
+fn test_gbnf_useless_rule_spans() {
+    use cfg::classify::useful::Usefulness;
+
+    let gbnf = Gbnf::parse("root ::= \"a\"\nunused ::= \"b\" | root\n").unwrap();
+    let mut cfg = gbnf.cfg().clone();
+    let usefulness = Usefulness::new(&mut cfg).reachable([gbnf.root().unwrap()]);
+    let diagnostics: Vec<_> = usefulness
+        .useless_rules()
+        .map(|useless| gbnf.diagnostic(*useless.rule(), "unreachable rule"))
+        .collect();
+
+    // Order is significant.
+    assert_eq!(
+        diagnostics,
+        vec![
+            "2:12: unreachable rule: `unused ::= \"b\"`",
+            "2:18: unreachable rule: `unused ::= root`",
+        ]
+    );
+}
+
+#[cfg(feature = "cfg-classify")]
+#[test]
+fn test_gbnf_cycle_spans() {
+    use cfg::classify::cyclical::Cycles;
+
+    let gbnf = Gbnf::parse("root ::= a | \"x\"\na ::= root\n").unwrap();
+    let mut cfg = gbnf.cfg().clone();
+    let cycles = Cycles::new(&mut cfg);
+    let reports: Vec<_> = cycles
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| gbnf.report(diagnostic))
+        .collect();
+
+    assert_eq!(
+        reports,
+        vec![
+            "1:10: warning: rule in a cycle: `root ::= a`",
+            "2:7: warning: rule in a cycle: `a ::= root`",
+        ]
+    );
+}
+
+#[cfg(feature = "ll")]
+#[test]
+fn test_gbnf_ll_conflict_spans() {
+    use cfg::classify::ll::LlParseTable;
+
+    let gbnf =
+        Gbnf::parse_with_file_name("ll.gbnf", "root ::= \"a\" \"b\" | \"a\" \"c\"\n").unwrap();
+    let table = LlParseTable::new(gbnf.cfg(), gbnf.root().unwrap());
+    let conflicts = table.conflicts();
+
+    assert_eq!(conflicts.len(), 1);
+    let spans: Vec<_> = conflicts[0]
+        .rules
+        .iter()
+        .map(|rule| gbnf.span(rule.history_id).unwrap().to_string())
+        .collect();
+    assert_eq!(spans, vec!["ll.gbnf:1:10-1:17", "ll.gbnf:1:20-1:27"]);
+    let reports: Vec<_> = conflicts[0]
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| gbnf.report(diagnostic))
+        .collect();
+    assert_eq!(
+        reports,
+        vec![
+            "ll.gbnf:1:10: error: LL(1) conflict on \"a\": `root ::= \"a\" \"b\"`",
+            "ll.gbnf:1:20: error: LL(1) conflict on \"a\": `root ::= \"a\" \"c\"`",
+        ]
+    );
+}
+
+#[cfg(feature = "cfg-classify")]
+#[test]
+fn test_gbnf_lr_conflict_spans() {
+    use cfg::classify::lr::{self, Lr0FsmBuilder, LrConflictKind};
+
+    let gbnf = Gbnf::parse("root ::= \"a\" | \"a\" \"b\" | other\nother ::= \"a\"\n").unwrap();
+    let mut cfg = gbnf.cfg().clone();
+    let fsm = Lr0FsmBuilder::new(&mut cfg).make_lr0_fsm(gbnf.root().unwrap());
+    let conflicts = lr::lr0_conflicts(&cfg, &fsm);
+
+    let conflicts: Vec<_> = conflicts
+        .iter()
+        .map(|conflict| {
+            let spans: Vec<_> = conflict
+                .rules
+                .iter()
+                .map(|rule| gbnf.span(rule.history_id).unwrap().to_string())
+                .collect();
+            (conflict.kind, spans)
+        })
+        .collect();
+    let (b, _) = gbnf
+        .terminals()
+        .find(|(_, terminal)| **terminal == Terminal::Literal("b".to_string()))
+        .unwrap();
+    assert_eq!(
+        conflicts,
+        vec![
+            (
+                LrConflictKind::ShiftReduce(b),
+                vec!["1:10-1:13", "2:11-2:14", "1:16-1:23"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            ),
+            (
+                LrConflictKind::ReduceReduce,
+                vec!["1:10-1:13".to_string(), "2:11-2:14".to_string()]
+            ),
+        ]
+    );
+    let reports: Vec<_> = lr::lr0_conflicts(&cfg, &fsm)[1]
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| gbnf.report(diagnostic))
+        .collect();
+    assert_eq!(
+        reports,
+        vec![
+            "1:10: error: reduce/reduce conflict: `root ::= \"a\"`",
+            "2:11: error: reduce/reduce conflict: `other ::= \"a\"`",
+        ]
+    );
+}
+
+#[test]
+fn test_source_span_from_str() {
+    use cfg::load::span::SourceSpan;
+
+    let span: SourceSpan = "dir/a-b.gbnf:3:4-5:6".parse().unwrap();
+    assert_eq!(span.file.as_deref(), Some("dir/a-b.gbnf"));
+    assert_eq!((span.start.line, span.start.column), (3, 4));
+    assert_eq!((span.end.line, span.end.column), (5, 6));
+    assert_eq!(span.to_string(), "dir/a-b.gbnf:3:4-5:6");
+
+    let span: SourceSpan = "3:4-5:6".parse().unwrap();
+    assert_eq!(span.file, None);
+    assert!("3-5:6".parse::<SourceSpan>().is_err());
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_gbnf_spans_survive_serialization() {
+    use cfg::load::span::SourceSpan;
+
+    let gbnf = Gbnf::parse_with_file_name("s.gbnf", "root ::= \"a\" | \"b\"\n").unwrap();
+    let json = miniserde::json::to_string(gbnf.cfg());
+    let loaded: Cfg = miniserde::json::from_str(&json).unwrap();
+    let spans: Vec<_> = loaded
+        .rules()
+        .map(|rule| {
+            loaded
+                .history_graph()
+                .nearest_payload::<SourceSpan>(rule.history_id)
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+
+    assert_eq!(spans, vec!["s.gbnf:1:10-1:13", "s.gbnf:1:16-1:19"]);
+}
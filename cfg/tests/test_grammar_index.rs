@@ -0,0 +1,52 @@
+use cfg::index::Occurrence;
+use cfg::{Cfg, GrammarIndex, RuleContainer};
+
+#[test]
+fn test_grammar_index() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, x, y, unused] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, b])
+        .rule(a)
+        .rhs([x, a, x])
+        .rhs([])
+        .rule(start)
+        .rhs([b])
+        .rule(b)
+        .rhs([y]);
+
+    let index = GrammarIndex::new(&cfg);
+
+    assert_eq!(index.num_rules(), 5);
+    assert_eq!(index.rules_for_lhs(start), &[0, 3]);
+    assert_eq!(index.rules_for_lhs(a), &[1, 2]);
+    assert_eq!(index.rules_for_lhs(x), &[] as &[usize]);
+    assert_eq!(
+        index.occurrences(x),
+        &[
+            Occurrence { rule: 1, pos: 0 },
+            Occurrence { rule: 1, pos: 2 }
+        ]
+    );
+    assert_eq!(
+        index.occurrences(b),
+        &[
+            Occurrence { rule: 0, pos: 1 },
+            Occurrence { rule: 3, pos: 0 }
+        ]
+    );
+    assert!(index.occurrences(unused).is_empty());
+    assert_eq!(index.nonterminals().collect::<Vec<_>>(), vec![start, a, b]);
+    assert!(!index.is_nonterminal(y));
+
+    // Indices are consistent with `rules().enumerate()`.
+    for (rule_idx, rule) in cfg.rules().enumerate() {
+        assert!(index.rules_for_lhs(rule.lhs).contains(&rule_idx));
+        for (pos, &sym) in rule.rhs.iter().enumerate() {
+            assert!(index.occurrences(sym).contains(&Occurrence {
+                rule: rule_idx,
+                pos
+            }));
+        }
+    }
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "cfg-sequence")]
+
+mod support;
+
+use cfg::precedenced_rule::Associativity::*;
+use cfg::sequence::Separator::*;
+use cfg::{grammar, Cfg, RuleContainer};
+use cfg_sequence::destination::SequenceDestination;
+use cfg_sequence::rewrite::SequencesToProductions;
+
+#[test]
+fn test_macro_rules() {
+    let (cfg, syms) = grammar! {
+        symbols { start, a, b, c, empty }
+
+        start ::= a b | c empty;
+        empty ::= ;
+        start ::= | a;
+    };
+
+    let mut equivalent: Cfg = Cfg::new();
+    let [start, a, b, c, empty] = equivalent.sym();
+    equivalent
+        .rule(start)
+        .rhs([a, b])
+        .rhs([c, empty])
+        .rule(empty)
+        .rhs([])
+        .rule(start)
+        .rhs([])
+        .rhs([a]);
+
+    support::assert_eq_rules(equivalent.rules(), cfg.rules());
+    assert_eq!((syms.start, syms.empty), (start, empty));
+}
+
+#[test]
+fn test_macro_precedence() {
+    let (cfg, _) = grammar! {
+        symbols { start, top, num, var, l_paren, r_paren, exp, mul, div, plus, minus, eq }
+
+        start ::= top;
+        precedence top ::=
+            num
+            | var
+            | #[assoc(Group)] l_paren top r_paren
+            |> minus top
+            | #[assoc(Right)] top exp top
+            |> top mul top
+            | top div top
+            |> top plus top
+            | top minus top
+            |> var eq top;
+    };
+
+    let mut equivalent: Cfg = Cfg::new();
+    let [start, top, num, var] = equivalent.sym();
+    let [l_paren, r_paren, exp, mul, div, plus, minus, eq] = equivalent.sym();
+    equivalent
+        .rule(start)
+        .rhs([top])
+        .precedenced_rule(top)
+        .rhs([num])
+        .rhs([var])
+        .associativity(Group)
+        .rhs([l_paren, top, r_paren])
+        .lower_precedence()
+        .rhs([minus, top])
+        .associativity(Right)
+        .rhs([top, exp, top])
+        .lower_precedence()
+        .rhs([top, mul, top])
+        .rhs([top, div, top])
+        .lower_precedence()
+        .rhs([top, plus, top])
+        .rhs([top, minus, top])
+        .lower_precedence()
+        .rhs([var, eq, top])
+        .finalize();
+
+    support::assert_eq_rules(equivalent.rules(), cfg.rules());
+}
+
+#[test]
+fn test_macro_sequence() {
+    let (cfg, _) = grammar! {
+        symbols { start, list, elem, sep }
+
+        start ::= list;
+        list ::= elem{1, 4} % Trailing(sep);
+        list ::= elem* % sep;
+        list ::= elem+;
+        list ::= elem{2,};
+    };
+
+    let mut equivalent: Cfg = Cfg::new();
+    let [start, list, elem, sep] = equivalent.sym();
+    // Order is significant.
+    equivalent.rule(start).rhs([list]);
+    SequencesToProductions::new(&mut equivalent)
+        .sequence(list)
+        .separator(Trailing(sep))
+        .inclusive(1, Some(4))
+        .rhs(elem)
+        .sequence(list)
+        .separator(Proper(sep))
+        .inclusive(0, None)
+        .rhs(elem)
+        .sequence(list)
+        .inclusive(1, None)
+        .rhs(elem)
+        .sequence(list)
+        .inclusive(2, None)
+        .rhs(elem);
+
+    support::assert_eq_rules(equivalent.rules(), cfg.rules());
+}
@@ -0,0 +1,144 @@
+mod support;
+
+use cfg::diagnostic::GrammarError;
+use cfg::history::node::{LinkedHistoryNode, RootHistoryNode};
+use cfg::unit_rules::eliminate_unit_rules;
+use cfg::{Cfg, HistoryId, HistoryNode, RuleContainer, Symbol};
+
+/// Follows history links to the root, which holds the LHS of the rule built by the user.
+fn root_lhs(grammar: &Cfg, mut id: HistoryId) -> Option<Symbol> {
+    loop {
+        match grammar.history_graph()[id.get()] {
+            HistoryNode::Linked { prev, .. } => id = prev,
+            HistoryNode::Root(RootHistoryNode::Rule { lhs }) => return Some(lhs),
+            HistoryNode::Root(..) => return None,
+        }
+    }
+}
+
+#[test]
+fn test_import() {
+    let mut sql: Cfg = Cfg::new();
+    let sql_syms = sql.sym();
+    let [query, select, column, from, table] = sql_syms;
+    sql.rule(query).rhs([select, column, from, table]);
+
+    let mut host: Cfg = Cfg::new();
+    let [expr, sql_literal, quote, num] = host.sym();
+    host.rule(expr).rhs([quote, sql_literal, quote]).rhs([num]);
+
+    let mapping = host.import(&sql, &[(query, sql_literal)]).unwrap();
+
+    let mut expected: Cfg = Cfg::new();
+    let [expr, sql_literal, quote, num] = expected.sym();
+    let [select, column, from, table] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(expr)
+        .rhs([quote, sql_literal, quote])
+        .rhs([num])
+        .rule(sql_literal)
+        .rhs([select, column, from, table]);
+    support::assert_eq_rules(host.rules(), expected.rules());
+    assert_eq!(
+        mapping.to_external,
+        &[sql_literal, select, column, from, table]
+    );
+    let [query, select, column, from, table] = sql_syms.map(Some);
+    let to_internal = [None, query, None, None, select, column, from, table];
+    assert_eq!(mapping.to_internal, &to_internal);
+
+    let rule = host.rules().last().unwrap();
+    assert_eq!(root_lhs(&host, rule.history_id), Some(sql_literal));
+    match &host.history_graph()[rule.history_id.get()] {
+        HistoryNode::Linked {
+            node: LinkedHistoryNode::Rhs { rhs },
+            ..
+        } => assert_eq!(rhs, &expected.rules().last().unwrap().rhs),
+        _ => panic!("expected an RHS node"),
+    }
+}
+
+#[test]
+fn test_import_history() {
+    let mut inner: Cfg = Cfg::new();
+    let [start, unit, x] = inner.sym();
+    inner.rule(start).rhs([unit]).rule(unit).rhs([x]);
+    eliminate_unit_rules(&mut inner);
+
+    let mut outer: Cfg = Cfg::new();
+    let [top, a] = outer.sym();
+    outer.rule(top).rhs([a]).rhs([a, a]);
+    let num_nodes = outer.history_graph().len();
+
+    let mapping = outer.import(&inner, &[]).unwrap();
+
+    assert_eq!(
+        outer.history_graph().len(),
+        num_nodes + inner.history_graph().len() - 1
+    );
+    let start = mapping.to_external[start.usize()];
+    let rule = outer
+        .rules()
+        .find(|rule| rule.lhs == start)
+        .expect("missing imported rule");
+    match &outer.history_graph()[rule.history_id.get()] {
+        HistoryNode::Linked {
+            prev,
+            node: LinkedHistoryNode::EliminateUnitRules { chain },
+        } => {
+            let unit = mapping.to_external[unit.usize()];
+            assert_eq!(root_lhs(&outer, *prev), Some(unit));
+            assert_eq!(root_lhs(&outer, chain[0]), Some(start));
+        }
+        _ => panic!("expected a unit rule elimination"),
+    }
+}
+
+#[test]
+fn test_import_origins() {
+    let mut inner: Cfg = Cfg::new();
+    let [start, x] = inner.sym();
+    for origin in 0..2 {
+        let history_id = inner.add_history_node(RootHistoryNode::Origin { origin }.into());
+        inner.rule(start).history(history_id).rhs([x]);
+    }
+
+    let mut outer: Cfg = Cfg::new();
+    let [top, a] = outer.sym();
+    for origin in [0, 4] {
+        let history_id = outer.add_history_node(RootHistoryNode::Origin { origin }.into());
+        outer.rule(top).history(history_id).rhs([a]);
+    }
+
+    outer.import(&inner, &[]).unwrap();
+
+    let origins: Vec<_> = outer
+        .rules()
+        .map(|rule| outer.history_graph().provenance(rule.history_id).origin())
+        .collect();
+    // Order is significant.
+    assert_eq!(origins, vec![Some(0), Some(4), Some(5), Some(6)]);
+}
+
+#[test]
+fn test_import_invalid_binding() {
+    let mut inner: Cfg = Cfg::new();
+    let [start, x] = inner.sym();
+    inner.rule(start).rhs([x]);
+
+    let mut outer: Cfg = Cfg::new();
+    let [top] = outer.sym();
+    let missing = Symbol::from(5usize);
+
+    assert_eq!(
+        outer.import(&inner, &[(start, missing)]).err(),
+        Some(GrammarError::InvalidBinding(start, missing))
+    );
+    assert_eq!(
+        outer.import(&inner, &[(missing, top)]).err(),
+        Some(GrammarError::InvalidBinding(missing, top))
+    );
+    assert_eq!(outer.num_syms(), 1);
+    assert_eq!(outer.rules().count(), 0);
+}
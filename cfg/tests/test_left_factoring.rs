@@ -0,0 +1,130 @@
+mod support;
+
+use cfg::history::node::LinkedHistoryNode;
+use cfg::left_factoring::left_factor;
+use cfg::rule::RuleRef;
+use cfg::{Cfg, HistoryNode, RuleContainer};
+
+fn linked_node(grammar: &Cfg, rule: RuleRef) -> LinkedHistoryNode {
+    match &grammar.history_graph()[rule.history_id.get()] {
+        HistoryNode::Linked { node, .. } => node.clone(),
+        HistoryNode::Root(..) => panic!("expected a linked history node"),
+    }
+}
+
+#[test]
+fn test_left_factor() {
+    let mut cfg: Cfg = Cfg::new();
+    let [stmt, cond, then, els, end, other] = cfg.sym();
+    cfg.rule(stmt)
+        .rhs([cond, then, stmt, end])
+        .rhs([other])
+        .rhs([cond, then, stmt, els, stmt, end]);
+
+    let factoring = left_factor(&mut cfg);
+
+    let mut expected: Cfg = Cfg::new();
+    let [stmt, cond, then, els, end, other] = expected.sym();
+    let [suffix] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(stmt)
+        .rhs([cond, then, stmt, suffix])
+        .rhs([other])
+        .rule(suffix)
+        .rhs([end])
+        .rhs([els, stmt, end]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+    assert_eq!(factoring.new_syms(), &[suffix]);
+    assert!(factoring.unfixed().is_empty());
+
+    let nodes: Vec<_> = cfg.rules().map(|rule| linked_node(&cfg, rule)).collect();
+    assert!(matches!(
+        nodes[0],
+        LinkedHistoryNode::FactorPrefix { len: 3 }
+    ));
+    assert!(matches!(
+        nodes[2],
+        LinkedHistoryNode::FactorSuffix { prefix_len: 3 }
+    ));
+    assert!(matches!(
+        nodes[3],
+        LinkedHistoryNode::FactorSuffix { prefix_len: 3 }
+    ));
+}
+
+#[test]
+fn test_left_factor_nested() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, d] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, b, c])
+        .rhs([a, b, d])
+        .rhs([a, c])
+        .rhs([a]);
+
+    let factoring = left_factor(&mut cfg);
+
+    let mut expected: Cfg = Cfg::new();
+    let [start, a, b, c, d] = expected.sym();
+    let [suffix, inner] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(start)
+        .rhs([a, suffix])
+        .rule(suffix)
+        .rhs([b, inner])
+        .rhs([c])
+        .rhs([])
+        .rule(inner)
+        .rhs([c])
+        .rhs([d]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+    assert_eq!(factoring.new_syms(), &[suffix, inner]);
+    assert!(factoring.unfixed().is_empty());
+}
+
+#[test]
+fn test_left_factor_unfixed() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, x, y, a, b, c] = cfg.sym();
+    cfg.rule(start)
+        .rhs([x, b])
+        .rhs([y, c])
+        .rule(x)
+        .rhs([a])
+        .rule(y)
+        .rhs([a]);
+
+    let factoring = left_factor(&mut cfg);
+
+    // Both alternatives of `start` begin with `a`, through distinct nonterminals.
+    assert!(factoring.new_syms().is_empty());
+    assert_eq!(factoring.unfixed(), &[start]);
+    assert_eq!(cfg.rules().count(), 4);
+}
+
+#[cfg(feature = "ll")]
+#[test]
+fn test_left_factor_ll() {
+    use cfg::classify::ll::{LlNonterminalClass, LlParseTable};
+
+    let mut cfg: Cfg = Cfg::new();
+    let [start, list, item, comma] = cfg.sym();
+    cfg.rule(start)
+        .rhs([list])
+        .rule(list)
+        .rhs([item, comma, list])
+        .rhs([item]);
+
+    let classes = LlParseTable::new(&cfg, start).classify().classes().clone();
+    assert_ne!(classes[&list], LlNonterminalClass::Ll1);
+
+    let factoring = left_factor(&mut cfg);
+
+    let classification = LlParseTable::new(&cfg, start).classify();
+    let classes = classification.classes();
+    assert_eq!(classes[&list], LlNonterminalClass::Ll1);
+    assert_eq!(classes[&factoring.new_syms()[0]], LlNonterminalClass::Ll1);
+    assert!(factoring.unfixed().is_empty());
+}
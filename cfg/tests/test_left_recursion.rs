@@ -0,0 +1,138 @@
+mod support;
+
+use cfg::history::node::{LeftRecursionPart, LinkedHistoryNode};
+use cfg::left_recursion::eliminate_left_recursion;
+use cfg::rule::RuleRef;
+use cfg::{Cfg, HistoryNode, RuleContainer, Symbol};
+
+fn is_left_recursive(grammar: &Cfg, sym: Symbol) -> bool {
+    let mut stack = vec![sym];
+    let mut visited = vec![];
+    while let Some(lhs) = stack.pop() {
+        for rule in grammar.rules().filter(|rule| rule.lhs == lhs) {
+            match rule.rhs.first() {
+                Some(&first) if first == sym => return true,
+                Some(&first) if !visited.contains(&first) => {
+                    visited.push(first);
+                    stack.push(first);
+                }
+                _ => {}
+            }
+        }
+    }
+    false
+}
+
+fn linked_node(grammar: &Cfg, rule: RuleRef) -> LinkedHistoryNode {
+    match &grammar.history_graph()[rule.history_id.get()] {
+        HistoryNode::Linked { node, .. } => node.clone(),
+        HistoryNode::Root(..) => panic!("expected a linked history node"),
+    }
+}
+
+#[test]
+fn test_direct_left_recursion() {
+    let mut cfg: Cfg = Cfg::new();
+    let [sum, product, plus, mul, num] = cfg.sym();
+    cfg.rule(sum)
+        .rhs([sum, plus, product])
+        .rhs([product])
+        .rule(product)
+        .rhs([product, mul, num])
+        .rhs([num]);
+
+    let tails = eliminate_left_recursion(&mut cfg);
+
+    let mut expected: Cfg = Cfg::new();
+    let [sum, product, plus, mul, num] = expected.sym();
+    let [sum_tail, product_tail] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(sum)
+        .rhs([product, sum_tail])
+        .rule(product)
+        .rhs([num, product_tail])
+        .rule(sum_tail)
+        .rhs([plus, product, sum_tail])
+        .rhs([])
+        .rule(product_tail)
+        .rhs([mul, num, product_tail])
+        .rhs([]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+    assert_eq!(
+        tails.into_iter().collect::<Vec<_>>(),
+        vec![(sum, sum_tail), (product, product_tail)]
+    );
+
+    let parts: Vec<_> = cfg
+        .rules()
+        .map(|rule| match linked_node(&cfg, rule) {
+            LinkedHistoryNode::EliminateLeftRecursion { part } => part,
+            _ => panic!("expected left recursion elimination"),
+        })
+        .collect();
+    use LeftRecursionPart::*;
+    assert_eq!(parts, vec![Base, Base, Tail, End, Tail, End]);
+}
+
+#[test]
+fn test_indirect_left_recursion() {
+    let mut cfg: Cfg = Cfg::new();
+    let [a, b, x, y, z] = cfg.sym();
+    cfg.rule(a)
+        .rhs([b, x])
+        .rhs([z])
+        .rule(b)
+        .rhs([a, y])
+        .rhs([b, z])
+        .rhs([x]);
+
+    let tails = eliminate_left_recursion(&mut cfg);
+
+    assert!(!is_left_recursive(&cfg, a));
+    assert!(!is_left_recursive(&cfg, b));
+    assert!(tails.contains_key(&b));
+    for &tail in tails.values() {
+        assert!(!is_left_recursive(&cfg, tail));
+    }
+    // `b ::= a y` is replaced with `b ::= b x y` and `b ::= z y` first.
+    let substituted = cfg
+        .rules()
+        .find(|rule| rule.lhs == b && rule.rhs[0] == z)
+        .unwrap();
+    assert!(matches!(
+        linked_node(&cfg, substituted),
+        LinkedHistoryNode::EliminateLeftRecursion {
+            part: LeftRecursionPart::Base
+        }
+    ));
+    assert!(cfg.rules().any(|rule| {
+        matches!(
+            linked_node(&cfg, rule),
+            LinkedHistoryNode::EliminateLeftRecursion {
+                part: LeftRecursionPart::Tail
+            }
+        ) && rule.rhs[..2] == [x, y]
+    }));
+}
+
+#[cfg(feature = "ll")]
+#[test]
+fn test_left_recursion_ll() {
+    use cfg::classify::ll::{LlNonterminalClass, LlParseTable};
+
+    let mut cfg: Cfg = Cfg::new();
+    let [start, sum, plus, num] = cfg.sym();
+    cfg.rule(start)
+        .rhs([sum])
+        .rule(sum)
+        .rhs([sum, plus, num])
+        .rhs([num]);
+
+    let tails = eliminate_left_recursion(&mut cfg);
+
+    let classification = LlParseTable::new(&cfg, start).classify();
+    let classes = classification.classes();
+    assert_eq!(classes[&sum], LlNonterminalClass::Ll1);
+    assert_eq!(classes[&tails[&sum]], LlNonterminalClass::Ll1);
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "cfg-classify")]
+
+use cfg::classify::lint::Linter;
+use cfg::diagnostic::{DiagnosticKind, Lint, Severity};
+use cfg::{Cfg, RuleContainer};
+
+fn lints(cfg: &Cfg, linter: &Linter) -> Vec<(Lint, usize)> {
+    linter
+        .lint(cfg)
+        .into_iter()
+        .map(|diagnostic| match diagnostic.kind {
+            DiagnosticKind::Lint { lint, sym } => (lint, sym.usize()),
+            kind => panic!("unexpected diagnostic {:?}", kind),
+        })
+        .collect()
+}
+
+#[test]
+fn test_lint_duplicates_and_ambiguity() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, x, plus] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a])
+        .rhs([a])
+        .rule(a)
+        .rhs([a, a])
+        .rhs([a, plus, a])
+        .rhs([b])
+        .rhs([x])
+        .rule(b)
+        .rhs([a]);
+
+    let linter = Linter::new().reachable([start]).terminals([x, plus]);
+
+    // Order is significant.
+    assert_eq!(
+        lints(&cfg, &linter),
+        vec![
+            (Lint::DuplicateRule, start.usize()),
+            (Lint::AmbiguousShape, a.usize()),
+            (Lint::AmbiguousShape, a.usize()),
+            (Lint::AmbiguousShape, a.usize()),
+            (Lint::AmbiguousShape, b.usize()),
+            (Lint::ReachableOnlyThroughCycles, b.usize()),
+        ]
+    );
+}
+
+#[test]
+fn test_lint_symbols() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, missing, x, unused, list] = cfg.sym();
+    cfg.rule(start)
+        .rhs([missing, x, list])
+        .rule(list)
+        .rhs([])
+        .rhs([list, x]);
+
+    let linter = Linter::new().terminals([x, unused]);
+
+    // Order is significant.
+    assert_eq!(
+        lints(&cfg, &linter),
+        vec![
+            (Lint::MissingRules, missing.usize()),
+            (Lint::UnusedTerminal, unused.usize()),
+            (Lint::NullableRecursive, list.usize()),
+        ]
+    );
+    let diagnostics = linter.lint(&cfg);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].history_id, None);
+}
+
+#[test]
+fn test_lint_unit_chains() {
+    let mut cfg: Cfg = Cfg::new();
+    let [a, b, c, d, e, x] = cfg.sym();
+    cfg.rule(a)
+        .rhs([b])
+        .rule(b)
+        .rhs([c])
+        .rule(c)
+        .rhs([d])
+        .rule(d)
+        .rhs([e])
+        .rule(e)
+        .rhs([x]);
+
+    assert_eq!(
+        lints(&cfg, &Linter::new()),
+        vec![(Lint::LongUnitChain, a.usize())]
+    );
+    assert_eq!(lints(&cfg, &Linter::new().max_unit_chain(4)), vec![]);
+}
+
+#[test]
+fn test_lint_unit_chain_ends_at_terminal() {
+    let mut cfg: Cfg = Cfg::new();
+    let [a, b, c, x] = cfg.sym();
+    cfg.rule(a).rhs([b]).rule(b).rhs([c]).rule(c).rhs([x]);
+
+    // `C ::= x` is not a unit rule, so the chain has 2 rules.
+    assert_eq!(lints(&cfg, &Linter::new().max_unit_chain(2)), vec![]);
+    assert_eq!(
+        lints(&cfg, &Linter::new().max_unit_chain(1)),
+        vec![(Lint::LongUnitChain, a.usize())]
+    );
+}
+
+#[test]
+fn test_lint_unit_chains_through_cycles() {
+    // The chain `A ::= B`, `B ::= C`, `C ::= D`, `D ::= E` is found whether `C` or `B` is
+    // visited first.
+    for order in [false, true] {
+        let mut cfg: Cfg = Cfg::new();
+        let [a, b, c, d, e, x] = cfg.sym();
+        let mut builder = cfg.rule(a);
+        builder = if order {
+            builder.rhs([c]).rhs([b])
+        } else {
+            builder.rhs([b]).rhs([c])
+        };
+        builder
+            .rule(b)
+            .rhs([c])
+            .rule(c)
+            .rhs([b])
+            .rhs([d])
+            .rule(d)
+            .rhs([e])
+            .rule(e)
+            .rhs([x, x]);
+
+        let long = |max| {
+            lints(
+                &cfg,
+                &Linter::new()
+                    .allow(Lint::AmbiguousShape)
+                    .max_unit_chain(max),
+            )
+            .into_iter()
+            .filter(|&(lint, _)| lint == Lint::LongUnitChain)
+            .count()
+        };
+        assert_eq!(long(4), 0);
+        assert_eq!(long(3), 1);
+        assert_eq!(long(2), 2);
+    }
+}
+
+#[test]
+fn test_lint_configuration() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, x] = cfg.sym();
+    cfg.rule(start)
+        .rhs([start, start])
+        .rhs([x])
+        .rule(a)
+        .rhs([x])
+        .rhs([x]);
+    let duplicate = cfg.rules().last().unwrap().history_id;
+
+    let linter = Linter::new()
+        .severity(Lint::DuplicateRule, Severity::Error)
+        .suppress_symbol(Lint::AmbiguousShape, start);
+    let diagnostics = linter.lint(&cfg);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].history_id, Some(duplicate));
+
+    let linter = Linter::new()
+        .allow(Lint::AmbiguousShape)
+        .suppress_rule(Lint::DuplicateRule, duplicate);
+    assert_eq!(lints(&cfg, &linter), vec![]);
+}
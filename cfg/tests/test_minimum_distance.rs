@@ -21,7 +21,7 @@ fn test_minimum_distance() {
     let mut cfg = Cfg::new();
     //   0      1  2  3  4  5
     let [start, a, b, c, x, y] = cfg.sym();
-    const POS_3: &'static [u32] = &[3];
+    const POS_3: &[u32] = &[3];
     cfg.rule(a)
         .rhs_with_linked_history([], empty())
         .rule(start)
@@ -40,7 +40,7 @@ fn test_minimum_distance() {
     // min(b) = 0
     // min(a) = 0
     // min(c) = 1
-    let expected_distances = vec![
+    let expected_distances = [
         (NonZeroUsize::new(3).unwrap(), vec![Some(0)]),
         (
             NonZeroUsize::new(6).unwrap(),
@@ -0,0 +1,181 @@
+use cfg::history::node::{HistoryNodePayload, LinkedHistoryNode};
+use cfg::history::{Payload, PayloadValue};
+use cfg::{Cfg, RuleContainer};
+
+#[test]
+fn test_nearest_payload() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, d] = cfg.sym();
+    cfg.rule(start)
+        .rhs_with_linked_history(
+            [a, b, c, d],
+            LinkedHistoryNode::Payload {
+                payload: Payload::new("action".to_string()),
+            },
+        )
+        .rhs([a]);
+
+    let binarized = cfg.binarize();
+
+    let graph = binarized.history_graph();
+    let payloads: Vec<_> = binarized
+        .rules()
+        .map(|rule| {
+            (
+                rule.rhs.len(),
+                graph
+                    .nearest_payload::<String>(rule.history_id)
+                    .map(String::as_str),
+            )
+        })
+        .collect();
+    // Order is significant.
+    assert_eq!(
+        payloads,
+        vec![
+            (2, Some("action")),
+            (2, Some("action")),
+            (2, Some("action")),
+            (1, None)
+        ]
+    );
+}
+
+#[test]
+fn test_nearest_payload_is_innermost() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a] = cfg.sym();
+    cfg.rule(start).rhs_with_linked_history(
+        [a],
+        LinkedHistoryNode::Payload {
+            payload: Payload::new("inner".to_string()),
+        },
+    );
+    let history_id = cfg.rules().next().unwrap().history_id;
+    let outer = cfg.add_history_node(
+        HistoryNodePayload {
+            prev: history_id,
+            payload: Payload::new("outer".to_string()),
+        }
+        .into(),
+    );
+
+    assert_eq!(
+        cfg.history_graph()
+            .nearest_payload::<String>(history_id)
+            .map(String::as_str),
+        Some("inner")
+    );
+    assert_eq!(
+        cfg.history_graph()
+            .nearest_payload::<String>(outer)
+            .map(String::as_str),
+        Some("outer")
+    );
+}
+
+#[test]
+fn test_payload_survives_compaction() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b] = cfg.sym();
+    cfg.rule(start).rhs([a]).rhs_with_linked_history(
+        [b],
+        LinkedHistoryNode::Payload {
+            payload: Payload::new("kept".to_string()),
+        },
+    );
+    cfg.retain(|rule| rule.rhs[0] == b);
+
+    cfg.compact_history();
+
+    let rule = cfg.rules().next().unwrap();
+    assert_eq!(
+        cfg.history_graph()
+            .nearest_payload::<String>(rule.history_id)
+            .map(String::as_str),
+        Some("kept")
+    );
+}
+
+#[cfg(feature = "cfg-earley")]
+#[test]
+fn test_payload_in_earley_history() {
+    use cfg::earley::Grammar;
+
+    let mut grammar = Grammar::new();
+    let [start, a, b, c] = grammar.sym();
+    grammar.rule(start).rhs_with_linked_history(
+        [a, b, c],
+        LinkedHistoryNode::Payload {
+            payload: Payload::new("3".to_string()),
+        },
+    );
+    grammar.set_start(start);
+
+    let binarized = grammar.binarize();
+    let histories = binarized.final_history();
+    for rule in binarized.rules() {
+        assert_eq!(
+            histories[rule.history_id.get()]
+                .payload::<String>()
+                .map(String::as_str),
+            Some("3")
+        );
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ActionId(u32);
+
+impl PayloadValue for ActionId {
+    const KEY: &'static str = "ActionId";
+
+    fn encode(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        text.parse().ok().map(ActionId)
+    }
+}
+
+#[test]
+fn test_payloads_of_other_types_are_skipped() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a] = cfg.sym();
+    cfg.rule(start).rhs_with_linked_history(
+        [a],
+        LinkedHistoryNode::Payload {
+            payload: Payload::new(ActionId(7)),
+        },
+    );
+    let history_id = cfg.rules().next().unwrap().history_id;
+    let outer = cfg.add_history_node(
+        HistoryNodePayload {
+            prev: history_id,
+            payload: Payload::new("documentation".to_string()),
+        }
+        .into(),
+    );
+
+    let graph = cfg.history_graph();
+    assert_eq!(graph.nearest_payload::<ActionId>(outer), Some(&ActionId(7)));
+    assert_eq!(
+        graph.nearest_payload::<String>(outer).map(String::as_str),
+        Some("documentation")
+    );
+    assert_eq!(graph.nearest_payload::<String>(history_id), None);
+    assert_eq!(graph.nearest_payload::<u64>(outer), None);
+}
+
+#[test]
+fn test_encoded_payload() {
+    let payload = Payload::from_encoded("ActionId", "12".to_string());
+    assert_eq!(payload.key(), "ActionId");
+    assert_eq!(payload.get::<ActionId>(), Some(&ActionId(12)));
+    assert_eq!(payload.get::<String>(), None);
+    assert_eq!(Payload::new(ActionId(12)).encode(), "12");
+
+    let malformed = Payload::from_encoded("ActionId", "twelve".to_string());
+    assert_eq!(malformed.get::<ActionId>(), None);
+}
@@ -0,0 +1,198 @@
+#![cfg(feature = "cfg-earley")]
+
+mod support;
+
+use cfg::earley::history::History;
+use cfg::earley::precompiled::{PrecompiledError, FORMAT_VERSION};
+use cfg::earley::{Grammar, PrecompiledGrammar};
+use cfg::history::node::LinkedHistoryNode;
+use cfg::history::Payload;
+use cfg::{HistoryId, HistoryNode, RuleContainer, Symbol};
+
+fn grammar() -> Grammar {
+    let mut grammar = Grammar::new();
+    let [start, a, b, c, list, comma, empty] = grammar.sym();
+    grammar
+        .rule(start)
+        .rhs([a, list, empty, b])
+        .rhs([empty, c])
+        .rhs([empty])
+        .rule(list)
+        .rhs([list, comma, a])
+        .rhs_with_linked_history(
+            [a],
+            LinkedHistoryNode::Payload {
+                payload: Payload::new("an item".to_string()),
+            },
+        )
+        .rule(empty)
+        .rhs([]);
+    grammar.set_start(start);
+    grammar
+}
+
+fn debug(histories: &[History]) -> Vec<String> {
+    histories
+        .iter()
+        .map(|history| format!("{:?}", history))
+        .collect()
+}
+
+#[test]
+fn test_precompiled_round_trip() {
+    let precompiled = PrecompiledGrammar::new(&grammar());
+    let bytes = precompiled.to_bytes();
+    let loaded = PrecompiledGrammar::from_bytes(&bytes).unwrap();
+
+    support::assert_eq_rules(precompiled.grammar().rules(), loaded.grammar().rules());
+    support::assert_eq_rules(
+        precompiled.nulling_grammar().rules(),
+        loaded.nulling_grammar().rules(),
+    );
+    assert!(loaded.nulling_grammar().rules().count() > 0);
+    assert_eq!(loaded.grammar().start(), precompiled.grammar().start());
+    assert_eq!(
+        loaded.mapping().to_internal,
+        precompiled.mapping().to_internal
+    );
+    assert_eq!(
+        loaded.mapping().to_external,
+        precompiled.mapping().to_external
+    );
+    assert_eq!(debug(loaded.histories()), debug(precompiled.histories()));
+    assert!(loaded
+        .histories()
+        .iter()
+        .any(|history| history.payload::<String>().map(String::as_str) == Some("an item")));
+    assert_eq!(
+        debug(loaded.nulling_histories()),
+        debug(precompiled.nulling_histories())
+    );
+    assert_eq!(
+        debug(&loaded.grammar().final_history()),
+        debug(&precompiled.grammar().final_history())
+    );
+    assert_eq!(loaded.to_bytes(), bytes);
+}
+
+#[test]
+fn test_precompiled_errors() {
+    let bytes = PrecompiledGrammar::new(&grammar()).to_bytes();
+    let error = |bytes: &[u8]| PrecompiledGrammar::from_bytes(bytes).err().unwrap();
+
+    assert_eq!(error(&bytes[..10]), PrecompiledError::UnexpectedEof);
+    assert_eq!(
+        error(&bytes[..bytes.len() - 4]),
+        PrecompiledError::UnexpectedEof
+    );
+
+    let mut corrupted = bytes.clone();
+    corrupted[0] = b'X';
+    assert_eq!(error(&corrupted), PrecompiledError::BadMagic);
+
+    let mut corrupted = bytes.clone();
+    corrupted[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        error(&corrupted),
+        PrecompiledError::UnsupportedVersion(FORMAT_VERSION + 1)
+    );
+
+    let mut corrupted = bytes.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert_eq!(error(&corrupted), PrecompiledError::ChecksumMismatch);
+}
+
+#[test]
+fn test_precompiled_forward_history_reference() {
+    let (proper, nulling) = grammar().binarize().make_proper().eliminate_nulling();
+    let (mut proper, mapping) = proper.remap_symbols();
+    let prev = proper.rules().next().unwrap().history_id;
+    let next = HistoryId::new(proper.history_graph().len() + 1).unwrap();
+    proper.add_history_node(HistoryNode::Linked {
+        prev,
+        node: LinkedHistoryNode::Inline {
+            inner: next,
+            pos: 0,
+            len: 1,
+        },
+    });
+    let bytes = PrecompiledGrammar::from_parts(proper, nulling, mapping).to_bytes();
+
+    assert_eq!(
+        PrecompiledGrammar::from_bytes(&bytes).err(),
+        Some(PrecompiledError::Invalid)
+    );
+}
+
+#[test]
+fn test_precompiled_symbol_count_exceeds_input() {
+    let (proper, nulling) = grammar().binarize().make_proper().eliminate_nulling();
+    let (mut proper, mapping) = proper.remap_symbols();
+    for _ in 0..10_000 {
+        proper.next_sym();
+    }
+    let bytes = PrecompiledGrammar::from_parts(proper, nulling, mapping).to_bytes();
+
+    assert_eq!(
+        PrecompiledGrammar::from_bytes(&bytes).err(),
+        Some(PrecompiledError::UnexpectedEof)
+    );
+}
+
+#[test]
+fn test_precompiled_empty_shared_helper() {
+    let (proper, nulling) = grammar().binarize().make_proper().eliminate_nulling();
+    let (mut proper, mapping) = proper.remap_symbols();
+    let prev = proper.rules().next().unwrap().history_id;
+    proper.add_history_node(HistoryNode::Linked {
+        prev,
+        node: LinkedHistoryNode::BinarizeShared {
+            len: 2,
+            suffix: false,
+            rules: vec![],
+        },
+    });
+    let bytes = PrecompiledGrammar::from_parts(proper, nulling, mapping).to_bytes();
+
+    assert_eq!(
+        PrecompiledGrammar::from_bytes(&bytes).err(),
+        Some(PrecompiledError::Invalid)
+    );
+}
+
+#[test]
+fn test_precompiled_removed_symbols_in_history() {
+    let mut grammar = Grammar::new();
+    let [start, a, b, c, d, e, f, unreachable] = grammar.sym();
+    grammar
+        .rule(start)
+        .rhs([a])
+        .rule(unreachable)
+        .rhs([b, c, d, e, f]);
+    grammar.set_start(start);
+    let precompiled = PrecompiledGrammar::new(&grammar);
+    assert!(precompiled.grammar().num_syms() <= unreachable.usize());
+
+    let loaded = PrecompiledGrammar::from_bytes(&precompiled.to_bytes()).unwrap();
+    assert_eq!(debug(loaded.histories()), debug(precompiled.histories()));
+}
+
+#[test]
+fn test_precompiled_mapping_out_of_range() {
+    let (proper, nulling) = grammar().binarize().make_proper().eliminate_nulling();
+    let (proper, mapping) = proper.remap_symbols();
+    let num_syms = proper.num_syms();
+    let num_external = mapping.to_internal.len();
+
+    let mut to_internal = mapping.clone();
+    to_internal.to_internal[0] = Some(Symbol::from(num_syms));
+    let mut to_external = mapping;
+    to_external.to_external[0] = Symbol::from(num_external);
+    for mapping in [to_internal, to_external] {
+        let precompiled = PrecompiledGrammar::from_parts(proper.clone(), nulling.clone(), mapping);
+        assert_eq!(
+            PrecompiledGrammar::from_bytes(&precompiled.to_bytes()).err(),
+            Some(PrecompiledError::Invalid)
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use cfg::history::node::{LinkedHistoryNode, RootHistoryNode};
+use cfg::refactor::inline;
+use cfg::{Cfg, RuleContainer};
+
+#[test]
+fn test_provenance_binarized() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, d] = cfg.sym();
+    cfg.rule(start).rhs([a, b, c, d]);
+
+    let binarized = cfg.binarize();
+
+    let graph = binarized.history_graph();
+    let positions: Vec<_> = binarized
+        .rules()
+        .map(|rule| {
+            let provenance = graph.provenance(rule.history_id);
+            assert_eq!(provenance.lhs(), Some(start));
+            assert_eq!(provenance.original_rhs(), Some(&[a, b, c, d][..]));
+            assert!(matches!(
+                provenance.steps(),
+                [
+                    (_, LinkedHistoryNode::Rhs { .. }),
+                    (_, LinkedHistoryNode::Binarize { .. })
+                ]
+            ));
+            provenance.positions().unwrap().to_vec()
+        })
+        .collect();
+    // Order is significant.
+    assert_eq!(
+        positions,
+        vec![
+            vec![None, Some(3)],
+            vec![None, Some(2)],
+            vec![Some(0), Some(1)]
+        ]
+    );
+}
+
+#[test]
+fn test_provenance_nulling_and_inline() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, opt, sign, x, y, plus, minus] = cfg.sym();
+    cfg.rule(start)
+        .rhs([x, opt, sign, y])
+        .rule(opt)
+        .rhs([])
+        .rhs([x])
+        .rule(sign)
+        .rhs([plus, minus]);
+
+    inline(&mut cfg, sign);
+    cfg.eliminate_nulling_rules();
+
+    let graph = cfg.history_graph();
+    let mut positions: Vec<_> = cfg
+        .rules()
+        .filter(|rule| rule.lhs == start)
+        .map(|rule| {
+            graph
+                .provenance(rule.history_id)
+                .positions()
+                .unwrap()
+                .to_vec()
+        })
+        .collect();
+    positions.sort();
+    assert_eq!(
+        positions,
+        vec![
+            vec![Some(0), None, None, Some(3)],
+            vec![Some(0), Some(1), None, None, Some(3)],
+        ]
+    );
+}
+
+#[test]
+fn test_provenance_origin() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a] = cfg.sym();
+    let root = cfg.add_history_node(RootHistoryNode::Origin { origin: 5 }.into());
+    cfg.rule(start).history(root).rhs([a]);
+
+    let rule = cfg.rules().next().unwrap();
+    let provenance = cfg.history_graph().provenance(rule.history_id);
+    assert_eq!(provenance.origin(), Some(5));
+    assert_eq!(provenance.lhs(), None);
+    assert_eq!(provenance.root_id(), root);
+    assert_eq!(provenance.positions(), Some(&[Some(0)][..]));
+}
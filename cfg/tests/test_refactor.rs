@@ -0,0 +1,196 @@
+mod support;
+
+use std::collections::BTreeMap;
+
+use cfg::history::node::LinkedHistoryNode;
+use cfg::refactor::{extract, inline, inline_at, merge, rename};
+use cfg::rule::RuleRef;
+use cfg::{Cfg, HistoryNode, RuleContainer};
+
+fn linked_node(grammar: &Cfg, rule: RuleRef) -> LinkedHistoryNode {
+    match &grammar.history_graph()[rule.history_id.get()] {
+        HistoryNode::Linked { node, .. } => node.clone(),
+        HistoryNode::Root(..) => panic!("expected a linked history node"),
+    }
+}
+
+#[test]
+fn test_inline() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, sign, num, plus, minus] = cfg.sym();
+    cfg.rule(start)
+        .rhs([sign, num, sign])
+        .rhs([num])
+        .rule(sign)
+        .rhs([plus])
+        .rhs([minus])
+        .rhs([]);
+
+    inline(&mut cfg, sign);
+
+    let mut expected: Cfg = Cfg::new();
+    let [start, sign, num, plus, minus] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(start)
+        .rhs([num])
+        .rule(sign)
+        .rhs([plus])
+        .rhs([minus])
+        .rhs([])
+        .rule(start)
+        .rhs([plus, num, plus])
+        .rhs([minus, num, plus])
+        .rhs([num, plus])
+        .rhs([plus, num, minus])
+        .rhs([minus, num, minus])
+        .rhs([num, minus])
+        .rhs([plus, num])
+        .rhs([minus, num])
+        .rhs([num]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+
+    let rule = cfg.rules().nth(6).unwrap();
+    match linked_node(&cfg, rule) {
+        LinkedHistoryNode::Inline { pos, len, .. } => assert_eq!((pos, len), (0, 0)),
+        _ => panic!("expected inlining"),
+    }
+}
+
+#[test]
+fn test_inline_at() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, item, x, y] = cfg.sym();
+    cfg.rule(start)
+        .rhs([item, item])
+        .rule(item)
+        .rhs([x])
+        .rhs([y]);
+
+    inline_at(&mut cfg, item, |_, pos| pos == 1);
+
+    let mut expected: Cfg = Cfg::new();
+    let [start, item, x, y] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(item)
+        .rhs([x])
+        .rhs([y])
+        .rule(start)
+        .rhs([item, x])
+        .rhs([item, y]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+}
+
+#[test]
+fn test_extract() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, b, c, a, b])
+        .rhs([c])
+        .rule(c)
+        .rhs([b, a, b]);
+
+    let extracted = extract(&mut cfg, &[a, b]);
+
+    let mut expected: Cfg = Cfg::new();
+    let [start, a, b, c, ab] = expected.sym();
+    assert_eq!(extracted, ab);
+    // Order is significant.
+    expected
+        .rule(start)
+        .rhs([c])
+        .rhs([ab, c, ab])
+        .rule(c)
+        .rhs([b, ab])
+        .rule(ab)
+        .rhs([a, b]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+
+    let rule = cfg.rules().nth(1).unwrap();
+    match &cfg.history_graph()[rule.history_id.get()] {
+        &HistoryNode::Linked {
+            prev,
+            node: LinkedHistoryNode::Extract { pos: 2, len: 2 },
+        } => assert!(matches!(
+            cfg.history_graph()[prev.get()],
+            HistoryNode::Linked {
+                node: LinkedHistoryNode::Extract { pos: 0, len: 2 },
+                ..
+            }
+        )),
+        _ => panic!("expected extraction"),
+    }
+    let rule = cfg.rules().last().unwrap();
+    assert!(matches!(
+        linked_node(&cfg, rule),
+        LinkedHistoryNode::Extracted { len: 2 }
+    ));
+}
+
+#[test]
+fn test_rename_and_merge() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, x, y, z] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, b])
+        .rule(a)
+        .rhs([x])
+        .rule(b)
+        .rhs([y]);
+
+    let renames: BTreeMap<_, _> = [(a, z)].into_iter().collect();
+    rename(&mut cfg, &renames);
+    assert_eq!(merge(&mut cfg, &[z, b]), z);
+
+    let mut expected: Cfg = Cfg::new();
+    let [start, _a, _b, x, y, z] = expected.sym();
+    // Order is significant.
+    expected.rule(z).rhs([x]).rhs([y]).rule(start).rhs([z, z]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+
+    let rule = cfg.rules().nth(2).unwrap();
+    match &cfg.history_graph()[rule.history_id.get()] {
+        &HistoryNode::Linked {
+            prev,
+            node: LinkedHistoryNode::RenameSymbol { from, to },
+        } => {
+            assert_eq!((from, to), (b, z));
+            assert!(matches!(
+                cfg.history_graph()[prev.get()],
+                HistoryNode::Linked {
+                    node: LinkedHistoryNode::RenameSymbol { from, to },
+                    ..
+                } if (from, to) == (a, z)
+            ));
+        }
+        _ => panic!("expected renaming"),
+    }
+}
+
+#[cfg(feature = "cfg-earley")]
+#[test]
+fn test_refactor_dots() {
+    use cfg::earley::Grammar;
+
+    let mut grammar = Grammar::new();
+    let [start, inner, a, b, c] = grammar.sym();
+    grammar.rule(start).rhs([a, inner, c]);
+    grammar.rule(inner).rhs([b, b]);
+    grammar.set_start(start);
+    inline(&mut *grammar, inner);
+    extract(&mut *grammar, &[b, b, c]);
+
+    let binarized = grammar.binarize();
+    let histories = binarized.final_history();
+    let rule = binarized
+        .rules()
+        .find(|rule| rule.lhs == start && rule.rhs.len() == 2)
+        .unwrap();
+    let history = &histories[rule.history_id.get()];
+    // `start ::= a bbc` keeps the dots before `a`, before `inner` and at the end.
+    let traces: Vec<_> = (0..3).map(|i| history.dot(i).trace()).collect();
+    assert_eq!(traces, vec![Some((0, 0)), Some((0, 1)), Some((0, 3))]);
+    assert_eq!(history.origin().map(|origin| origin.usize()), Some(0));
+}
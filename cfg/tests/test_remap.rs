@@ -3,8 +3,9 @@
 mod support;
 
 use cfg::classify::useful::Usefulness;
+use cfg::history::node::LinkedHistoryNode;
 use cfg::symbol::remap::Remap;
-use cfg::{Cfg, RuleContainer};
+use cfg::{Cfg, HistoryNode, RuleContainer};
 
 #[test]
 fn test_remap_unused_symbols() {
@@ -138,3 +139,95 @@ fn test_maps() {
         ]
     );
 }
+
+#[test]
+fn test_merge_equivalent_nonterminals() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, d, x, y] = cfg.sym();
+
+    cfg.rule(start)
+        .rhs([a, b])
+        .rhs([c, d])
+        .rule(a)
+        .rhs([x, a])
+        .rhs([y])
+        .rule(c)
+        .rhs([x, c])
+        .rhs([y])
+        .rule(b)
+        .rhs([x])
+        .rule(d)
+        .rhs([x])
+        .rhs([x]);
+
+    let mut remap = Remap::new(&mut cfg);
+    assert_eq!(remap.merge_equivalent_nonterminals(), 2);
+    let maps = remap.get_mapping();
+
+    {
+        let mut equivalent: Cfg = Cfg::new();
+        let [start, a, b, x, y] = equivalent.sym();
+        // Order is significant. The renamed `start ::= c d` is a duplicate.
+        equivalent
+            .rule(start)
+            .rhs([a, b])
+            .rule(a)
+            .rhs([x, a])
+            .rhs([y])
+            .rule(b)
+            .rhs([x]);
+        support::assert_eq_rules(equivalent.rules(), cfg.rules());
+    };
+    assert_eq!(cfg.num_syms(), 5);
+    // `x` and `y` take the IDs of the freed `c` and `d`.
+    assert_eq!(
+        maps.to_internal,
+        &[
+            Some(start),
+            Some(a),
+            Some(b),
+            Some(a),
+            Some(b),
+            Some(c),
+            Some(d)
+        ]
+    );
+    assert_eq!(maps.to_external, &[start, a, b, x, y]);
+}
+
+#[test]
+fn test_merge_equivalent_nonterminals_renames() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, x] = cfg.sym();
+
+    cfg.rule(start)
+        .rhs([a, x])
+        .rhs([b])
+        .rule(a)
+        .rhs([x])
+        .rule(b)
+        .rhs([x]);
+
+    let mut remap = Remap::new(&mut cfg);
+    assert_eq!(remap.merge_equivalent_nonterminals(), 1);
+    assert_eq!(remap.get_mapping().to_external, &[start, a, x]);
+
+    let renames: Vec<_> = cfg
+        .rules()
+        .filter(|rule| rule.lhs == start)
+        .map(|rule| {
+            let mut renames = vec![];
+            let mut id = rule.history_id;
+            while let HistoryNode::Linked {
+                prev,
+                node: LinkedHistoryNode::RenameSymbol { from, to },
+            } = cfg.history_graph()[id.get()]
+            {
+                renames.push((from, to));
+                id = prev;
+            }
+            renames
+        })
+        .collect();
+    assert_eq!(renames, vec![vec![(b, a)], vec![]]);
+}
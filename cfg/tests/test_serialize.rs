@@ -0,0 +1,174 @@
+#![cfg(all(feature = "serialize", feature = "cfg-earley"))]
+
+mod support;
+
+use cfg::cnf::ChomskyNormalForm;
+use cfg::earley::{BinarizedGrammar, Grammar};
+use cfg::history::node::LinkedHistoryNode;
+use cfg::history::Payload;
+use cfg::{BinarizedCfg, Cfg, RuleContainer};
+use miniserde::json;
+
+fn grammar() -> Grammar {
+    let mut grammar = Grammar::new();
+    let [start, a, b, c, list, comma, empty] = grammar.sym();
+    grammar
+        .rule(start)
+        .rhs([a, list, empty, b])
+        .rhs([empty, c])
+        .rule(list)
+        .rhs([list, comma, a])
+        .rhs([a])
+        .rule(empty)
+        .rhs([]);
+    grammar.set_start(start);
+    grammar
+}
+
+fn round_trip<T>(value: &T) -> T
+where
+    T: miniserde::Serialize + miniserde::Deserialize,
+{
+    let text = json::to_string(value);
+    let result: T = json::from_str(&text).unwrap();
+    assert_eq!(json::to_string(&result), text);
+    result
+}
+
+fn final_history(grammar: &BinarizedGrammar) -> Vec<String> {
+    grammar
+        .final_history()
+        .iter()
+        .map(|history| format!("{:?}", history))
+        .collect()
+}
+
+#[test]
+fn test_serialize_cfg() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b] = cfg.sym();
+    cfg.rule(start)
+        .rhs_with_linked_history([a, b], LinkedHistoryNode::Weight { weight: 0.25 })
+        .rhs_with_linked_history([], LinkedHistoryNode::Distances { events: vec![1, 2] })
+        .rhs_with_linked_history(
+            [b],
+            LinkedHistoryNode::Payload {
+                payload: Payload::new(5u64),
+            },
+        );
+
+    let loaded = round_trip(&cfg);
+    support::assert_eq_rules(cfg.rules(), loaded.rules());
+    assert_eq!(loaded.num_syms(), cfg.num_syms());
+    assert_eq!(loaded.history_graph().len(), cfg.history_graph().len());
+    let payloads: Vec<_> = loaded
+        .rules()
+        .map(|rule| {
+            loaded
+                .history_graph()
+                .nearest_payload::<u64>(rule.history_id)
+        })
+        .collect();
+    // Order is significant.
+    assert_eq!(payloads, vec![None, None, Some(&5)]);
+
+    let binarized = cfg.binarize();
+    let loaded: BinarizedCfg = round_trip(&binarized);
+    support::assert_eq_rules(binarized.rules(), loaded.rules());
+}
+
+#[test]
+fn test_serialize_binarized_grammar() {
+    let grammar = round_trip(&grammar());
+    let mut binarized = grammar.binarize();
+    binarized.wrap_start();
+    let (proper, nulling) = binarized.make_proper().eliminate_nulling();
+
+    for grammar in [proper, nulling] {
+        let loaded = round_trip(&grammar);
+        support::assert_eq_rules(grammar.rules(), loaded.rules());
+        assert_eq!(loaded.start(), grammar.start());
+        assert_eq!(loaded.original_start(), grammar.original_start());
+        assert_eq!(loaded.eof(), grammar.eof());
+        assert_eq!(final_history(&loaded), final_history(&grammar));
+    }
+}
+
+#[test]
+fn test_serialize_cnf() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, x, a, b] = cfg.sym();
+    cfg.rule(start).rhs([a, x]).rhs([x]).rule(x).rhs([b]);
+
+    let cnf = ChomskyNormalForm::new(&cfg, start);
+    let loaded: BinarizedCfg = round_trip(cnf.grammar());
+    support::assert_eq_rules(cnf.grammar().rules(), loaded.rules());
+}
+
+#[test]
+fn test_deserialize_invalid() {
+    let rule = r#"{"lhs":0,"rhs":[1,2,3],"history_id":0}"#;
+    let grammar = format!(
+        r#"{{"sym_source":{{"next_id":4}},"rules":[{}],"nulling":[],"history_graph":{{"nodes":[{{"root":{{"NoOp":{{}}}}}}]}}}}"#,
+        rule
+    );
+    assert!(json::from_str::<Cfg>(&grammar).is_err());
+    assert!(json::from_str::<BinarizedCfg>(&grammar).is_err());
+    let grammar = grammar.replace(r#""history_id":0"#, r#""history_id":1"#);
+    assert!(json::from_str::<Cfg>(&grammar).is_err());
+    assert!(json::from_str::<BinarizedCfg>(&grammar).is_err());
+}
+
+#[test]
+fn test_deserialize_malformed() {
+    let valid = concat!(
+        r#"{"sym_source":{"next_id":2},"#,
+        r#""rules":[{"lhs":0,"rhs":[1],"history_id":3}],"nulling":[],"#,
+        r#""history_graph":{"nodes":[{"root":{"NoOp":{}}},{"root":{"Rule":{"lhs":0}}},"#,
+        r#"{"prev":1,"node":{"Rhs":{"rhs":[1]}}},"#,
+        r#"{"prev":2,"node":{"RenameSymbol":{"from":1,"to":0}}}]}}"#
+    );
+    assert!(json::from_str::<Cfg>(valid).is_ok());
+    assert!(json::from_str::<BinarizedCfg>(valid).is_ok());
+
+    let malformed = [
+        // A symbol of a rule is out of range.
+        valid.replace(r#""rhs":[1],"history_id""#, r#""rhs":[2],"history_id""#),
+        valid.replace(r#""lhs":0,"rhs""#, r#""lhs":7,"rhs""#),
+        // The history ID of a rule is out of range.
+        valid.replace(r#""history_id":3"#, r#""history_id":4"#),
+        // A symbol in a history node is out of range.
+        valid.replace(r#""to":0"#, r#""to":2"#),
+        valid.replace(r#"{"Rule":{"lhs":0}}"#, r#"{"Rule":{"lhs":5}}"#),
+        // A history node refers to itself or to a later node.
+        valid.replace(r#""prev":2"#, r#""prev":3"#),
+        valid.replace(r#""prev":1"#, r#""prev":3"#),
+        // A shared helper is part of no rules.
+        valid.replace(
+            r#"{"RenameSymbol":{"from":1,"to":0}}"#,
+            r#"{"BinarizeShared":{"len":2,"suffix":false,"rules":[]}}"#,
+        ),
+        // Node 0 is not a root.
+        valid.replace(
+            r#"{"root":{"NoOp":{}}},{"root""#,
+            r#"{"prev":1,"node":{"Binarize":{"depth":0}}},{"root""#,
+        ),
+    ];
+    for text in &malformed {
+        assert_ne!(text, valid);
+        assert!(json::from_str::<Cfg>(text).is_err(), "{}", text);
+        assert!(json::from_str::<BinarizedCfg>(text).is_err(), "{}", text);
+    }
+    let text = valid.replace(
+        r#"{"RenameSymbol":{"from":1,"to":0}}"#,
+        r#"{"BinarizeShared":{"len":2,"suffix":false,"rules":[1]}}"#,
+    );
+    assert!(json::from_str::<BinarizedCfg>(&text).is_ok());
+    // The nulling rule of a symbol that does not exist.
+    let text = valid.replace(r#""nulling":[]"#, r#""nulling":[null,null,1]"#);
+    assert!(json::from_str::<BinarizedCfg>(&text).is_err());
+    let text = valid.replace(r#""nulling":[]"#, r#""nulling":[null,4]"#);
+    assert!(json::from_str::<BinarizedCfg>(&text).is_err());
+    let text = valid.replace(r#""nulling":[]"#, r#""nulling":[null,1]"#);
+    assert!(json::from_str::<BinarizedCfg>(&text).is_ok());
+}
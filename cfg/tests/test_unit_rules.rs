@@ -0,0 +1,88 @@
+mod support;
+
+use cfg::history::node::LinkedHistoryNode;
+use cfg::unit_rules::eliminate_unit_rules;
+use cfg::{Cfg, HistoryNode, RuleContainer};
+
+#[test]
+fn test_eliminate_unit_rules() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, x, y] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a])
+        .rhs([x, start])
+        .rule(a)
+        .rhs([b])
+        .rhs([y])
+        .rule(b)
+        .rhs([c])
+        .rhs([a])
+        .rule(c)
+        .rhs([x, y])
+        .rhs([]);
+
+    eliminate_unit_rules(&mut cfg);
+
+    let mut expected: Cfg = Cfg::new();
+    let [start, a, b, c, x, y] = expected.sym();
+    // Order is significant.
+    expected
+        .rule(start)
+        .rhs([x, start])
+        .rule(a)
+        .rhs([y])
+        .rule(c)
+        .rhs([x, y])
+        .rhs([])
+        .rule(start)
+        .rhs([y])
+        .rhs([x, y])
+        .rhs([])
+        .rule(a)
+        .rhs([x, y])
+        .rhs([])
+        .rule(b)
+        .rhs([x, y])
+        .rhs([])
+        .rhs([y]);
+    support::assert_eq_rules(cfg.rules(), expected.rules());
+}
+
+#[test]
+fn test_unit_chain_history() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, expr, num, plus] = cfg.sym();
+    cfg.rule(start)
+        .rhs([expr])
+        .precedenced_rule(expr)
+        .rhs([num])
+        .lower_precedence()
+        .rhs([expr, plus, expr])
+        .finalize();
+    let unit_rules = |cfg: &Cfg| {
+        cfg.rules()
+            .filter(|rule| rule.rhs.len() == 1 && rule.rhs[0] != num)
+            .count()
+    };
+    assert!(unit_rules(&cfg) > 0);
+
+    eliminate_unit_rules(&mut cfg);
+
+    assert_eq!(unit_rules(&cfg), 0);
+    let rule = cfg
+        .rules()
+        .find(|rule| rule.lhs == start && rule.rhs == [num])
+        .unwrap();
+    let chain = match &cfg.history_graph()[rule.history_id.get()] {
+        HistoryNode::Linked {
+            node: LinkedHistoryNode::EliminateUnitRules { chain },
+            ..
+        } => chain.clone(),
+        _ => panic!("expected a unit rule elimination"),
+    };
+    // The chain goes from `start` through every precedence level.
+    assert!(chain.len() >= 2);
+    assert!(cfg
+        .rules()
+        .any(|rule| rule.lhs == start && rule.rhs.len() == 3));
+}
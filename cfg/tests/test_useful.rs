@@ -0,0 +1,85 @@
+#![cfg(feature = "cfg-classify")]
+
+use cfg::classify::useful::{Unproductive, Unreachable, Usefulness};
+use cfg::{Cfg, RuleContainer};
+
+#[test]
+fn test_explain_unproductive() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, a, b, c, x] = cfg.sym();
+    cfg.rule(start)
+        .rhs([a, x, b, c])
+        .rule(a)
+        .rhs([b])
+        .rule(b)
+        .rhs([b, x])
+        .rule(c)
+        .rhs([c, c]);
+
+    let usefulness = Usefulness::new(&mut cfg).reachable([start]);
+    let useless: Vec<_> = usefulness.useless_rules().collect();
+    assert_eq!(useless.len(), 4);
+    assert!(useless
+        .iter()
+        .all(|rule| rule.usefulness().reachable() && !rule.usefulness().productive()));
+
+    let explanation = usefulness.explain(*useless[0].rule());
+    assert_eq!(explanation.unreachable, None);
+    assert_eq!(
+        explanation.unproductive,
+        Some(Unproductive {
+            unproductive_syms: vec![a, b, c],
+            missing_productions: vec![b, c],
+        })
+    );
+}
+
+#[test]
+fn test_explain_unreachable() {
+    let mut cfg: Cfg = Cfg::new();
+    let [start, x, u, v, w, m, p, q] = cfg.sym();
+    cfg.rule(start)
+        .rhs([x])
+        .rule(u)
+        .rhs([v])
+        .rule(v)
+        .rhs([w])
+        .rule(w)
+        .rhs([x])
+        .rule(m)
+        .rhs([v])
+        .rule(p)
+        .rhs([q])
+        .rule(q)
+        .rhs([p, x])
+        .rhs([x]);
+
+    let usefulness = Usefulness::new(&mut cfg).reachable([start]);
+    let explanations: Vec<_> = usefulness
+        .useless_rules()
+        .map(|useless| {
+            let rule = *useless.rule();
+            assert!(useless.usefulness().productive());
+            assert!(usefulness.explain(rule).unproductive.is_none());
+            (rule.lhs, usefulness.explain(rule).unreachable.unwrap())
+        })
+        .collect();
+
+    let unreachable = |referrers: Vec<_>| Unreachable {
+        referrers,
+        nearest_reachable_referrer: None,
+    };
+    // Order is significant.
+    assert_eq!(
+        explanations,
+        vec![
+            (u, unreachable(vec![])),
+            (v, unreachable(vec![u, m])),
+            (w, unreachable(vec![v])),
+            (m, unreachable(vec![])),
+            (p, unreachable(vec![q])),
+            (q, unreachable(vec![p])),
+            (q, unreachable(vec![p])),
+        ]
+    );
+}